@@ -1,18 +1,27 @@
 #version 450
 #extension GL_ARB_separate_shader_objects : enable
 
-layout(binding = 0) uniform UniformObject {
-    mat4 model;
+layout(set = 0, binding = 0) uniform UniformObject {
     mat4 view;
     mat4 proj;
 } uo;
 
-layout(location = 0) in vec2 inPosition;
-layout(location = 1) in vec3 inColor;
+layout(push_constant) uniform DrawConstants {
+    mat4 model;
+    vec4 baseColor;
+} draw;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inNormal;
+layout(location = 2) in vec4 inTangent;
+layout(location = 3) in vec2 inTexCoord;
+layout(location = 4) in vec4 inColor;
 
-layout(location = 0) out vec3 fragColor;
+layout(location = 0) out vec4 fragColor;
+layout(location = 1) out vec2 fragTexCoord;
 
 void main() {
-    gl_Position = uo.proj * uo.view * uo.model * vec4(inPosition, 0.0, 1.0);
-    fragColor = inColor;
+    gl_Position = uo.proj * uo.view * draw.model * vec4(inPosition, 1.0);
+    fragColor = inColor * draw.baseColor;
+    fragTexCoord = inTexCoord;
 }
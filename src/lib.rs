@@ -0,0 +1,3 @@
+#![warn(clippy::all)]
+
+pub mod renderer;
@@ -1,41 +1,123 @@
 #![warn(clippy::all)]
 
-mod renderer;
+use wild::renderer;
 
 fn is_window_drawable(window: &winit::window::Window) -> bool {
     window.inner_size().width != 0 && window.inner_size().height != 0
 }
 
+/// The controller the number keys 1 to 3 select, starting from the renderer's default view.
+fn controller_for_key(
+    key: winit::event::VirtualKeyCode,
+    default_camera: &renderer::Camera,
+    window: &winit::window::Window,
+) -> Option<Box<dyn renderer::CameraController>> {
+    let eye = default_camera.position;
+    let target = cgmath::Point3::new(0.0, 0.0, 0.0);
+    let up = cgmath::Vector3::unit_z();
+    match key {
+        winit::event::VirtualKeyCode::Key1 => {
+            Some(Box::new(renderer::OrbitController::new(eye, target, up)))
+        }
+        winit::event::VirtualKeyCode::Key2 => {
+            Some(Box::new(renderer::FlyController::new(eye, target, up)))
+        }
+        winit::event::VirtualKeyCode::Key3 => Some(Box::new(renderer::PanZoomController::new(
+            cgmath::Point2::new(0.0, 0.0),
+            2.0,
+            window.inner_size().height,
+        ))),
+        _ => None,
+    }
+}
+
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let event_loop = winit::event_loop::EventLoop::new();
     let window = winit::window::WindowBuilder::new()
         .build(&event_loop)
         .unwrap();
 
-    let mut renderer = renderer::Renderer::new(&window);
+    let config = renderer::RendererConfig {
+        watch_shaders: true,
+        pipeline_cache_path: Some("pipeline_cache.bin".into()),
+        ..Default::default()
+    };
+    let mut renderer = match renderer::Renderer::new_with_config(&window, &config) {
+        Ok(renderer) => renderer,
+        Err(error) => {
+            eprintln!("Failed to create the renderer: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let default_camera = *renderer.camera();
+    let mut camera = default_camera;
+    let mut controller =
+        controller_for_key(winit::event::VirtualKeyCode::Key1, &default_camera, &window).unwrap();
+    let mut last_update = std::time::Instant::now();
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = winit::event_loop::ControlFlow::Poll;
-        match event {
+        if let winit::event::Event::WindowEvent { event, .. } = &event {
+            if let winit::event::WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } = event
+            {
+                if let Some(next) = controller_for_key(*key, &default_camera, &window) {
+                    camera = default_camera;
+                    controller = next;
+                }
+            }
+            controller.handle_event(event);
+        }
+        let result = match event {
             winit::event::Event::WindowEvent {
                 window_id: _,
                 event,
             } => match event {
                 winit::event::WindowEvent::CloseRequested => {
                     *control_flow = winit::event_loop::ControlFlow::Exit;
+                    Ok(())
                 }
                 winit::event::WindowEvent::Resized(_size) if is_window_drawable(&window) => {
                     renderer.render()
                 }
-                _ => {}
+                winit::event::WindowEvent::KeyboardInput {
+                    input:
+                        winit::event::KeyboardInput {
+                            state: winit::event::ElementState::Pressed,
+                            virtual_keycode: Some(winit::event::VirtualKeyCode::F12),
+                            ..
+                        },
+                    ..
+                } if is_window_drawable(&window) => renderer.capture_frame().map(|frame| {
+                    if let Err(error) = frame.save("screenshot.png") {
+                        eprintln!("Failed to save screenshot: {}", error);
+                    }
+                }),
+                _ => Ok(()),
             },
             winit::event::Event::RedrawRequested(_) if is_window_drawable(&window) => {
                 renderer.render()
             }
             winit::event::Event::MainEventsCleared if is_window_drawable(&window) => {
-                renderer.render()
+                let now = std::time::Instant::now();
+                controller.update(&mut camera, now - last_update);
+                last_update = now;
+                renderer.set_camera(camera).and_then(|_| renderer.render())
             }
-            _ => {}
+            _ => Ok(()),
+        };
+        if let Err(error) = result {
+            eprintln!("Failed to render: {}", error);
+            *control_flow = winit::event_loop::ControlFlow::Exit;
         }
     });
 }
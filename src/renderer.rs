@@ -1,39 +1,102 @@
+mod aabb;
+mod allocator;
 mod buffer;
+mod camera;
+mod camera_controller;
+mod clock;
 mod command_buffers;
 mod command_pool;
+mod compressed;
+mod config;
+mod container;
 mod debug_utils;
+mod descriptor_set_layout;
 mod device;
+mod device_selection;
+mod draw_list;
 mod entry;
+mod error;
+mod frame;
 mod framebuffers;
+mod gltf_loader;
+mod gpu_buffer;
+mod image;
 mod index_buffer;
 mod instance;
+mod material;
+mod mesh;
+mod mipmap;
+mod model;
+mod obj_loader;
+mod offscreen;
 mod pipeline;
+mod pipeline_cache;
+mod ply_loader;
+mod reflection;
 mod render_pass;
+mod ring_buffer;
+mod scene;
+mod shader;
+mod shader_watcher;
 mod surface;
 mod swapchain;
 mod sync_objects;
+mod target;
+mod texture;
+mod transfer;
 mod uniform_object;
 mod vertex;
-mod vertex_buffer;
 
+pub use aabb::Aabb;
+pub use allocator::MemoryStatistics;
+use allocator::{Allocation, AllocationStrategy, Allocator, ResourceKind};
 use buffer::Buffer;
+pub use camera::{Camera, Projection};
+pub use camera_controller::{CameraController, FlyController, OrbitController, PanZoomController};
+pub use clock::Clock;
 use command_buffers::CommandBuffers;
 use command_pool::CommandPool;
+pub use config::RendererConfig;
 #[cfg(debug_assertions)]
 use debug_utils::DebugUtils;
+use descriptor_set_layout::DescriptorSetLayout;
 use device::Device;
+use device_selection::DeviceSelection;
+pub use device_selection::{PhysicalDeviceOverride, PHYSICAL_DEVICE_ENV};
+pub use draw_list::DrawList;
+use draw_list::{Draw, DrawConstants};
 use entry::Entry;
+pub use error::{RendererError, Result};
+pub use frame::Frame;
 use framebuffers::Framebuffers;
+use gpu_buffer::GpuBuffer;
+use image::Image;
+pub use index_buffer::Index;
 use index_buffer::IndexBuffer;
 use instance::Instance;
+pub use material::{AlphaMode, Material, MaterialData};
+pub use mesh::{Indices, Mesh, MeshData, NormalGeneration, Primitive, PrimitiveData};
+use mipmap::MipGeneration;
+pub use model::{Model, ModelData, NodeData, TextureSource};
+use offscreen::Offscreen;
 use pipeline::Pipeline;
+use pipeline_cache::PipelineCache;
+pub use reflection::{DescriptorBinding, ShaderReflection, VertexInput};
 use render_pass::RenderPass;
+use ring_buffer::RingBuffer;
+pub use scene::{Light, MeshInstance, Node, NodeId, Scene};
+use shader::Shaders;
+pub use shader::{ShaderCompiler, ShaderStage};
+pub use shader_watcher::ShaderWatcher;
 use surface::Surface;
 use swapchain::Swapchain;
 use sync_objects::SyncObjects;
+use target::Target;
+use texture::Texture;
+pub use texture::{SamplerConfig, TextureData};
+use transfer::{Transfer, UploadHandle};
 use uniform_object::UniformObject;
-use vertex::Vertex;
-use vertex_buffer::VertexBuffer;
+pub use vertex::{MeshVertex, Unorm, VertexAttribute, VertexFormat, VertexLayout};
 
 use std::io::Write;
 
@@ -41,116 +104,326 @@ use ash::vk;
 
 const UNIFORM_OBJECT_SIZE: usize = std::mem::size_of::<UniformObject>();
 const MAX_FRAMES_IN_FLIGHT: usize = 2;
+/// The descriptor set of the frame's uniforms.
+const FRAME_SET: u32 = 0;
+/// The descriptor set of each draw's material.
+const MATERIAL_SET: u32 = 1;
+const UNIFORM_RING_FRAME_SIZE: usize = 64 * 1024;
+const DEPTH_FORMATS: [vk::Format; 3] = [
+    vk::Format::D32_SFLOAT,
+    vk::Format::D24_UNORM_S8_UINT,
+    vk::Format::D16_UNORM,
+];
 
 pub struct Renderer {
-    _entry: Entry,
-    instance: Instance,
-    #[cfg(debug_assertions)]
-    debug_utils: DebugUtils,
-    surface: Surface,
     device: Device,
-    swapchain: Swapchain,
+    target: Target,
     render_pass: RenderPass,
-    descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_set_layout: DescriptorSetLayout,
+    material_set_layout: DescriptorSetLayout,
+    shader_compiler: ShaderCompiler,
+    shaders: Shaders,
+    shader_watcher: Option<ShaderWatcher>,
+    shader_error: Option<RendererError>,
     pipeline: Pipeline,
+    /// Replaced pipelines, with the number of frame fences waited on since, which frames in
+    /// flight may still use.
+    retired_pipelines: Vec<(Pipeline, usize)>,
+    samples: vk::SampleCountFlags,
+    color_image: Option<Image>,
+    depth_image: Image,
     framebuffers: Framebuffers,
     command_pool: CommandPool,
-    vertex_buffer: VertexBuffer,
-    index_buffer: IndexBuffer,
-    uniform_buffers: Vec<Buffer>,
+    transfer: Transfer,
+    scene: Scene,
+    quad: NodeId,
+    camera: Camera,
+    white_texture: Texture,
+    texture: Option<Texture>,
+    uniform_ring: RingBuffer,
     descriptor_pool: vk::DescriptorPool,
-    descriptor_sets: Vec<vk::DescriptorSet>,
+    descriptor_set: vk::DescriptorSet,
+    default_material: Material,
     command_buffers: CommandBuffers,
     sync_objects: SyncObjects,
     current_frame: usize,
+    capture_requested: bool,
+    captured_frame: Option<Frame>,
     resized: bool,
     time: std::time::SystemTime,
     frames: u32,
-    timer: std::time::SystemTime,
+    clock: Clock,
     rotation: f32,
+    #[cfg(debug_assertions)]
+    _debug_utils: DebugUtils,
+    instance: Instance,
+    _entry: Entry,
 }
 
 impl Renderer {
-    pub fn new(window: &winit::window::Window) -> Self {
-        let vertices = [
-            Vertex::new(cgmath::vec2(-0.5, -0.5), cgmath::vec3(1.0, 0.0, 0.0)),
-            Vertex::new(cgmath::vec2(0.5, -0.5), cgmath::vec3(0.0, 1.0, 0.0)),
-            Vertex::new(cgmath::vec2(0.5, 0.5), cgmath::vec3(0.0, 0.0, 1.0)),
-            Vertex::new(cgmath::vec2(-0.5, 0.5), cgmath::vec3(1.0, 1.0, 1.0)),
-        ];
-        let indices = [0, 1, 2, 2, 3, 0];
-        let entry = Entry::new();
-        let instance = Instance::new(&entry, window);
+    pub fn new(window: &winit::window::Window) -> Result<Self> {
+        Self::new_with_config(window, &RendererConfig::default())
+    }
+
+    pub fn new_with_config(
+        window: &winit::window::Window,
+        config: &RendererConfig,
+    ) -> Result<Self> {
+        Self::with_target(Some(window), vk::Extent2D::default(), config)
+    }
+
+    /// Creates a renderer that draws into offscreen images instead of a window surface.
+    pub fn new_headless(width: u32, height: u32) -> Result<Self> {
+        Self::new_headless_with_config(width, height, &RendererConfig::default())
+    }
+
+    pub fn new_headless_with_config(
+        width: u32,
+        height: u32,
+        config: &RendererConfig,
+    ) -> Result<Self> {
+        Self::with_target(None, vk::Extent2D { width, height }, config)
+    }
+
+    fn with_target(
+        window: Option<&winit::window::Window>,
+        headless_extent: vk::Extent2D,
+        config: &RendererConfig,
+    ) -> Result<Self> {
+        let corner = |position: [f32; 2], color: [f32; 3], tex_coord: [f32; 2]| MeshVertex {
+            position: [position[0], position[1], 0.0],
+            normal: [0.0, 0.0, 1.0],
+            tex_coord,
+            color: [color[0], color[1], color[2], 1.0],
+            ..Default::default()
+        };
+        let quad_mesh = MeshData {
+            name: Some("quad".to_string()),
+            primitives: vec![PrimitiveData {
+                vertices: vec![
+                    corner([-0.5, -0.5], [1.0, 0.0, 0.0], [0.0, 0.0]),
+                    corner([0.5, -0.5], [0.0, 1.0, 0.0], [1.0, 0.0]),
+                    corner([0.5, 0.5], [0.0, 0.0, 1.0], [1.0, 1.0]),
+                    corner([-0.5, 0.5], [1.0, 1.0, 1.0], [0.0, 1.0]),
+                ],
+                indices: Indices::U16(vec![0, 1, 2, 2, 3, 0]),
+                material: None,
+            }],
+        };
+        let entry = Entry::new()?;
+        let instance = Instance::new(&entry, window)?;
         #[cfg(debug_assertions)]
-        let debug_utils = DebugUtils::new(&entry, &instance);
-        let mut surface = Surface::new(window, &entry, &instance);
-        let device = Device::new(&instance, &mut surface);
-        let swapchain = Swapchain::new(&instance, &surface, &device);
-        let render_pass = RenderPass::new(&surface, &device);
-        let descriptor_set_layout = Self::create_descriptor_set_layout(&device);
-        let pipeline = Pipeline::new(&surface, &device, &render_pass, &descriptor_set_layout);
-        let framebuffers = Framebuffers::new(&surface, &device, &swapchain, &render_pass);
-        let command_pool = CommandPool::new(&device);
-        let vertex_buffer = VertexBuffer::new(&instance, &device, &command_pool, vertices.into());
-        let index_buffer = IndexBuffer::new(&instance, &device, &command_pool, indices.into());
-        let uniform_buffers = Self::create_uniform_buffers(&instance, &device, &swapchain);
-        let descriptor_pool = Self::create_descriptor_pool(&device, &swapchain);
-        let descriptor_sets = Self::create_descriptor_sets(
+        let debug_utils = DebugUtils::new(&entry, &instance)?;
+        let mut surface = window
+            .map(|window| Surface::new(window, &entry, &instance))
+            .transpose()?;
+        let device = Device::new(&instance, surface.as_mut(), config)?;
+        let target = match surface {
+            Some(surface) => {
+                let swapchain =
+                    Swapchain::new(&instance, &surface, &device, vk::SwapchainKHR::null())?;
+                Target::Window { surface, swapchain }
+            }
+            None => Target::Headless(Offscreen::new(
+                &device,
+                headless_extent,
+                MAX_FRAMES_IN_FLIGHT,
+            )?),
+        };
+        let image_count = target.images().len();
+        let depth_format = image::find_depth_format(&instance, &device, &DEPTH_FORMATS)?;
+        let samples = device.supported_sample_count(config.msaa_samples);
+        let color_image = Self::create_color_image(&device, &target, samples)?;
+        let depth_image =
+            Self::create_depth_image(&device, target.extent(), depth_format, samples)?;
+        let render_pass = RenderPass::new(
             &device,
-            &swapchain,
-            &descriptor_set_layout,
-            &uniform_buffers,
-            &descriptor_pool,
-        );
-        let command_buffers = CommandBuffers::new(
-            &surface,
+            target.format(),
+            target.final_layout(),
+            depth_format,
+            samples,
+        )?;
+        let camera = Self::default_camera();
+        let shader_compiler = ShaderCompiler {
+            cache_dir: config.shader_cache_dir.clone(),
+            ..Default::default()
+        };
+        let shaders = Shaders::builtin(&shader_compiler)?;
+        let shader_watcher = if config.watch_shaders {
+            Some(ShaderWatcher::new(shaders.files.clone()))
+        } else {
+            None
+        };
+        let descriptor_set_layout =
+            DescriptorSetLayout::new(&device, Self::frame_set_bindings(&shaders.reflection)?)?;
+        let material_set_bindings = shaders.reflection.set_layout_bindings(MATERIAL_SET);
+        Material::check_set_layout_bindings(&material_set_bindings)?;
+        let material_set_layout = DescriptorSetLayout::new(&device, material_set_bindings)?;
+        let pipeline = Pipeline::new(
             &device,
             &render_pass,
-            &pipeline,
-            &framebuffers,
-            &command_pool,
-            &vertex_buffer,
-            &index_buffer,
-            &descriptor_sets,
-        );
-        let sync_objects = SyncObjects::new(&device, &swapchain);
-        Self {
-            _entry: entry,
-            instance,
-            #[cfg(debug_assertions)]
-            debug_utils,
-            surface,
+            &[descriptor_set_layout.layout, material_set_layout.layout],
+            samples,
+            Self::depth_compare_op(camera.reverse_z),
+            &shaders,
+        )?;
+        let framebuffers = Framebuffers::new(
+            &device,
+            target.extent(),
+            target.image_views(),
+            color_image.as_ref().map(|color_image| color_image.view),
+            depth_image.view,
+            &render_pass,
+        )?;
+        let command_pool = CommandPool::new(&device, device.queue_family_index)?;
+        let mut transfer = Transfer::new(&device)?;
+        // A white texel leaves the vertex colors untouched where no texture is set.
+        let white_texture = Texture::new(
+            &instance,
+            &device,
+            &mut transfer,
+            &TextureData::from_rgba8(1, 1, vec![255; 4]),
+            &SamplerConfig::default(),
+        )?;
+        let quad_model = Model::new(
+            &instance,
+            &device,
+            &mut transfer,
+            &ModelData {
+                meshes: vec![quad_mesh],
+                nodes: vec![NodeData {
+                    name: Some("quad".to_string()),
+                    transform: cgmath::SquareMatrix::identity(),
+                    mesh: Some(0),
+                    camera: None,
+                    children: Vec::new(),
+                }],
+                roots: vec![0],
+                ..Default::default()
+            },
+            &material_set_layout,
+            &white_texture,
+        )?;
+        let mut scene = Scene::new();
+        let quad = scene.add_model(quad_model, None);
+        transfer.submit(&device)?;
+        let uniform_ring = Self::create_uniform_ring(&device, image_count)?;
+        let descriptor_pool =
+            Self::create_descriptor_pool(&device, &descriptor_set_layout, &material_set_layout)?;
+        let descriptor_set = Self::create_descriptor_set(
+            &device,
+            &descriptor_set_layout,
+            &uniform_ring,
+            descriptor_pool,
+        )?;
+        let default_material = Material::new(
+            &device,
+            descriptor_pool,
+            &material_set_layout,
+            MaterialData::default(),
+            &white_texture,
+        )?;
+        let command_buffers = CommandBuffers::new(&device, &command_pool, MAX_FRAMES_IN_FLIGHT)?;
+        let sync_objects = SyncObjects::new(&device, image_count)?;
+        Ok(Self {
             device,
-            swapchain,
+            target,
             render_pass,
             descriptor_set_layout,
+            material_set_layout,
+            shader_compiler,
+            shaders,
+            shader_watcher,
+            shader_error: None,
             pipeline,
+            retired_pipelines: Vec::new(),
+            samples,
+            color_image,
+            depth_image,
             framebuffers,
             command_pool,
-            vertex_buffer,
-            index_buffer,
-            uniform_buffers,
+            transfer,
+            scene,
+            quad,
+            camera,
+            white_texture,
+            texture: None,
+            uniform_ring,
             descriptor_pool,
-            descriptor_sets,
+            descriptor_set,
+            default_material,
             command_buffers,
             sync_objects,
             current_frame: 0,
+            capture_requested: false,
+            captured_frame: None,
             resized: false,
             time: std::time::SystemTime::now(),
             frames: 0,
-            timer: std::time::SystemTime::now(),
+            clock: Clock::system(),
             rotation: 0_f32,
-        }
+            #[cfg(debug_assertions)]
+            _debug_utils: debug_utils,
+            instance,
+            _entry: entry,
+        })
+    }
+
+    /// Renders the built-in quad, spinning it by the clock.
+    pub fn render(&mut self) -> Result<()> {
+        self.rotation += self.clock.tick().as_secs_f32() * 90_f32;
+        self.scene.set_transform(
+            self.quad,
+            cgmath::Matrix4::from_angle_z(cgmath::Deg(self.rotation)),
+        );
+        self.scene.update();
+        let mut draw_list = self.begin_frame();
+        self.scene.draw(
+            &mut draw_list,
+            &self.default_material,
+            Some(&self.view_projection()),
+        );
+        let draws = draw_list.into_draws();
+        self.draw_frame(draws)
+    }
+
+    /// Updates `scene` and renders every mesh in view.
+    pub fn render_scene(&mut self, scene: &mut Scene) -> Result<()> {
+        scene.update();
+        let mut draw_list = self.begin_frame();
+        scene.draw(
+            &mut draw_list,
+            &self.default_material,
+            Some(&self.view_projection()),
+        );
+        self.end_frame(draw_list)
+    }
+
+    /// Starts a frame whose draws are recorded and submitted by `end_frame`.
+    pub fn begin_frame<'a>(&self) -> DrawList<'a> {
+        DrawList::default()
+    }
+
+    pub fn end_frame(&mut self, draw_list: DrawList) -> Result<()> {
+        self.draw_frame(draw_list.into_draws())
+    }
+
+    /// A white material, sampling the texture given to `set_texture` once there is one.
+    pub fn default_material(&self) -> &Material {
+        &self.default_material
     }
 
-    pub fn render(&mut self) {
-        if self.time.elapsed().unwrap().as_millis() > 1000 {
+    fn draw_frame(&mut self, draws: Vec<Draw>) -> Result<()> {
+        if self.time.elapsed().unwrap_or_default().as_millis() > 1000 {
             print!("\r{} FPS", self.frames);
             let _ = std::io::stdout().flush();
             self.time = std::time::SystemTime::now();
             self.frames = 0;
         }
         self.frames += 1;
+        self.reload_changed_shaders()?;
+        self.transfer.submit(&self.device)?;
+        self.transfer.collect(&self.device)?;
 
         unsafe {
             self.device.device.wait_for_fences(
@@ -160,15 +433,20 @@ impl Renderer {
                     .get(self.current_frame)
                     .unwrap()],
                 true,
-                std::u64::MAX,
+                u64::MAX,
             )
-        }
-        .unwrap();
+        }?;
+        self.destroy_retired_pipelines();
+
+        let swapchain = match &self.target {
+            Target::Window { swapchain, .. } => swapchain,
+            Target::Headless(_) => return self.render_headless(&draws),
+        };
 
         let image_index = match unsafe {
-            self.swapchain.loader.acquire_next_image(
-                self.swapchain.swapchain,
-                std::u64::MAX,
+            swapchain.loader.acquire_next_image(
+                swapchain.swapchain,
+                u64::MAX,
                 *self
                     .sync_objects
                     .image_availabe_semaphores
@@ -178,19 +456,68 @@ impl Renderer {
             )
         } {
             Ok((image_index, _)) => image_index,
-            Err(result) => {
-                if result == vk::Result::ERROR_OUT_OF_DATE_KHR {
-                    self.recreate_swapchain();
-                    self.render();
-                    return;
-                } else {
-                    panic!("Failed to acquire next image!");
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                self.recreate_swapchain()?;
+                return self.draw_frame(draws);
+            }
+            Err(result) => return Err(result.into()),
+        };
+
+        self.submit(image_index, true, &draws)?;
+
+        let swapchain = match &self.target {
+            Target::Window { swapchain, .. } => swapchain,
+            Target::Headless(_) => return Ok(()),
+        };
+        match unsafe {
+            swapchain.loader.queue_present(
+                self.device.present_queue,
+                &vk::PresentInfoKHR {
+                    wait_semaphore_count: 1,
+                    p_wait_semaphores: self
+                        .sync_objects
+                        .render_finished_semaphores
+                        .get(self.current_frame)
+                        .unwrap(),
+                    swapchain_count: 1,
+                    p_swapchains: &swapchain.swapchain,
+                    p_image_indices: &image_index,
+                    ..Default::default()
+                },
+            )
+        } {
+            Ok(suboptimal) => {
+                if suboptimal {
+                    self.resized = false;
+                    self.recreate_swapchain()?;
                 }
             }
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                self.resized = false;
+                self.recreate_swapchain()?;
+                self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+                return self.draw_frame(draws);
+            }
+            Err(result) => return Err(result.into()),
         };
 
-        self.update_uniform_buffer(image_index as _);
+        if self.resized {
+            self.resized = false;
+            self.recreate_swapchain()?;
+        }
+
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+        Ok(())
+    }
+
+    fn render_headless(&mut self, draws: &[Draw]) -> Result<()> {
+        let image_index = self.current_frame as u32;
+        self.submit(image_index, false, draws)?;
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+        Ok(())
+    }
 
+    fn submit(&mut self, image_index: u32, present: bool, draws: &[Draw]) -> Result<()> {
         if let Some(images_in_flight_fence) = self
             .sync_objects
             .images_in_flight
@@ -200,24 +527,35 @@ impl Renderer {
             unsafe {
                 self.device
                     .device
-                    .wait_for_fences(&[*images_in_flight_fence], true, std::u64::MAX)
-            }
-            .unwrap();
+                    .wait_for_fences(&[*images_in_flight_fence], true, u64::MAX)
+            }?;
         }
 
-        let _ = std::mem::replace(
-            self.sync_objects
-                .images_in_flight
-                .get_mut(image_index as usize)
-                .unwrap(),
-            Some(
+        self.sync_objects
+            .images_in_flight
+            .get_mut(image_index as usize)
+            .unwrap()
+            .replace(
                 *self
                     .sync_objects
                     .in_flight_fences
                     .get(self.current_frame)
                     .unwrap(),
-            ),
-        );
+            );
+
+        let uniform_offset = self.update_uniform_buffer(image_index as _)?;
+        let command_buffer = self.command_buffers.record(
+            &self.device,
+            self.current_frame,
+            self.target.extent(),
+            &self.render_pass,
+            &self.pipeline,
+            self.framebuffers.framebuffers[image_index as usize],
+            if self.camera.reverse_z { 0.0 } else { 1.0 },
+            self.descriptor_set,
+            uniform_offset,
+            draws,
+        )?;
 
         unsafe {
             self.device.device.reset_fences(&[*self
@@ -225,14 +563,14 @@ impl Renderer {
                 .in_flight_fences
                 .get(self.current_frame)
                 .unwrap()])
-        }
-        .unwrap();
+        }?;
 
+        let wait_semaphore_count = if present { 1 } else { 0 };
         unsafe {
             self.device.device.queue_submit(
                 self.device.queue,
                 &[vk::SubmitInfo {
-                    wait_semaphore_count: 1,
+                    wait_semaphore_count,
                     p_wait_semaphores: self
                         .sync_objects
                         .image_availabe_semaphores
@@ -240,12 +578,8 @@ impl Renderer {
                         .unwrap(),
                     p_wait_dst_stage_mask: &vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
                     command_buffer_count: 1,
-                    p_command_buffers: self
-                        .command_buffers
-                        .command_buffers
-                        .get(image_index as usize)
-                        .unwrap(),
-                    signal_semaphore_count: 1,
+                    p_command_buffers: &command_buffer,
+                    signal_semaphore_count: wait_semaphore_count,
                     p_signal_semaphores: self
                         .sync_objects
                         .render_finished_semaphores
@@ -259,267 +593,609 @@ impl Renderer {
                     .get(self.current_frame)
                     .unwrap(),
             )
+        }?;
+
+        if self.capture_requested {
+            self.capture_requested = false;
+            self.captured_frame = Some(self.read_image(image_index)?);
         }
-        .unwrap();
+        Ok(())
+    }
 
-        match unsafe {
-            self.swapchain.loader.queue_present(
-                self.device.queue,
-                &vk::PresentInfoKHR {
-                    wait_semaphore_count: 1,
-                    p_wait_semaphores: self
-                        .sync_objects
-                        .render_finished_semaphores
-                        .get(self.current_frame)
-                        .unwrap(),
-                    swapchain_count: 1,
-                    p_swapchains: &self.swapchain.swapchain,
-                    p_image_indices: &image_index,
-                    ..Default::default()
-                },
-            )
-        } {
-            Ok(suboptimal) => {
-                if suboptimal {
-                    self.resized = false;
-                    self.recreate_swapchain();
-                }
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// Rebuilds the pipeline from the shaders in the source tree, swapping it in once frames in
+    /// flight are done with the current one.
+    pub fn reload_shaders(&mut self) -> Result<()> {
+        let shaders = Shaders::load(&self.shader_compiler)?;
+        // The descriptor sets were allocated for the current bindings.
+        if shaders.reflection.bindings != self.shaders.reflection.bindings {
+            return Err(RendererError::ShaderReflection(
+                "The descriptor bindings changed, which takes a restart".to_string(),
+            ));
+        }
+        let pipeline = Pipeline::new(
+            &self.device,
+            &self.render_pass,
+            &[
+                self.descriptor_set_layout.layout,
+                self.material_set_layout.layout,
+            ],
+            self.samples,
+            Self::depth_compare_op(self.camera.reverse_z),
+            &shaders,
+        )?;
+        let retired = std::mem::replace(&mut self.pipeline, pipeline);
+        self.retired_pipelines.push((retired, 0));
+        if let Some(shader_watcher) = &mut self.shader_watcher {
+            shader_watcher.watch(shaders.files.clone());
+        }
+        self.shaders = shaders;
+        Ok(())
+    }
+
+    /// Why the last reload of the watched shaders failed, until one succeeds.
+    pub fn shader_error(&self) -> Option<&RendererError> {
+        self.shader_error.as_ref()
+    }
+
+    /// Reloads the shaders if a watched file changed. A failed reload is logged and kept for
+    /// `shader_error` instead of failing the frame.
+    fn reload_changed_shaders(&mut self) -> Result<()> {
+        let changed = match &mut self.shader_watcher {
+            Some(shader_watcher) => shader_watcher.poll(),
+            None => return Ok(()),
+        };
+        if !changed.iter().any(|file| self.shaders.files.contains(file)) {
+            return Ok(());
+        }
+        match self.reload_shaders() {
+            Ok(()) => {
+                log::info!("Reloaded shaders");
+                self.shader_error = None;
             }
-            Err(result) => {
-                if result == vk::Result::ERROR_OUT_OF_DATE_KHR {
-                    self.resized = false;
-                    self.recreate_swapchain();
-                    self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
-                    self.render();
-                    return;
-                } else {
-                    panic!("Failed to present!");
-                }
+            Err(error @ RendererError::DeviceLost) => return Err(error),
+            Err(error) => {
+                log::warn!("{}\nKeeping the previous shaders", error);
+                self.shader_error = Some(error);
             }
-        };
+        }
+        Ok(())
+    }
 
-        if self.resized {
-            self.resized = false;
-            self.recreate_swapchain();
+    /// Destroys the retired pipelines once every frame slot's fence has been waited on since
+    /// their retirement.
+    fn destroy_retired_pipelines(&mut self) {
+        let device = &self.device;
+        self.retired_pipelines
+            .retain_mut(|(pipeline, fences_waited)| {
+                *fences_waited += 1;
+                if *fences_waited < MAX_FRAMES_IN_FLIGHT {
+                    return true;
+                }
+                pipeline.destroy(device);
+                false
+            });
+    }
+
+    /// Views the following frames through `camera`, rebuilding the pipeline when its depth
+    /// direction changes.
+    pub fn set_camera(&mut self, camera: Camera) -> Result<()> {
+        let reverse_z_changed = camera.reverse_z != self.camera.reverse_z;
+        self.camera = camera;
+        if reverse_z_changed {
+            unsafe { self.device.device.device_wait_idle() }?;
+            self.destroy_render_targets();
+            self.create_render_targets()?;
         }
+        Ok(())
+    }
 
-        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
     }
 
-    fn update_uniform_buffer(&mut self, current_image: usize) {
-        let current_time = std::time::SystemTime::now();
-        self.rotation += current_time
-            .duration_since(self.timer)
-            .unwrap()
-            .as_secs_f32()
-            * 90_f32;
-        let uniform_object = UniformObject {
-            model: cgmath::Matrix4::from_angle_z(cgmath::Deg(self.rotation)),
-            view: cgmath::Matrix4::look_at_rh(
-                cgmath::Point3::new(2_f32, 2_f32, 2_f32),
-                cgmath::Point3::new(0_f32, 0_f32, 0_f32),
-                cgmath::Vector3::new(0_f32, 0_f32, 1_f32),
-            ),
-            projection: cgmath::perspective(
-                cgmath::Deg(45_f32),
-                self.surface.capabilities.unwrap().current_extent.width as f32
-                    / self.surface.capabilities.unwrap().current_extent.height as f32,
-                0.1_f32,
-                10_f32,
-            ),
-        };
+    /// Whether the built-in quad and its textures have finished uploading.
+    pub fn is_ready(&self) -> bool {
+        self.scene
+            .uploads()
+            .chain(Some(self.white_texture.upload))
+            .chain(self.texture.as_ref().map(|texture| texture.upload))
+            .all(|upload| self.transfer.is_complete(upload))
+    }
 
-        self.timer = current_time;
+    /// Replaces the 2D texture sampled by the default material.
+    pub fn set_texture(
+        &mut self,
+        data: &TextureData,
+        sampler_config: &SamplerConfig,
+    ) -> Result<()> {
+        if data.view_type() != vk::ImageViewType::TYPE_2D {
+            return Err(RendererError::ViewTypeMismatch {
+                expected: vk::ImageViewType::TYPE_2D,
+                actual: data.view_type(),
+            });
+        }
+        let texture = Texture::new(
+            &self.instance,
+            &self.device,
+            &mut self.transfer,
+            data,
+            sampler_config,
+        )?;
+        // The material's set may still be read by frames in flight.
+        unsafe { self.device.device.device_wait_idle() }?;
+        self.default_material.set_base_color_texture(
+            &self.device,
+            &self.material_set_layout,
+            &texture,
+        );
+        if let Some(texture) = self.texture.replace(texture) {
+            texture.destroy(&self.device);
+        }
+        Ok(())
+    }
 
-        self.uniform_buffers
-            .get(current_image)
-            .unwrap()
-            .write(&self.device, uniform_object);
+    /// Uploads a loaded model's meshes and textures; they are usable once `is_model_ready`.
+    pub fn load_model(&mut self, data: &ModelData) -> Result<Model> {
+        Model::new(
+            &self.instance,
+            &self.device,
+            &mut self.transfer,
+            data,
+            &self.material_set_layout,
+            &self.white_texture,
+        )
     }
 
-    fn recreate_swapchain(&mut self) {
-        unsafe { self.device.device.device_wait_idle() }.unwrap();
+    /// Whether all of the model's buffers and textures have finished uploading.
+    pub fn is_model_ready(&self, model: &Model) -> bool {
+        model
+            .uploads()
+            .all(|upload| self.transfer.is_complete(upload))
+    }
 
-        self.framebuffers.destroy(&self.device);
-        self.command_buffers.free(&self.device, &self.command_pool);
-        self.pipeline.destroy(&self.device);
-        self.render_pass.destroy(&self.device);
-        self.swapchain.destroy(&self.device);
-        self.uniform_buffers
-            .iter()
-            .for_each(|uniform_buffer| uniform_buffer.destory(&self.device));
+    /// Whether all of the scene's models have finished uploading.
+    pub fn is_scene_ready(&self, scene: &Scene) -> bool {
+        scene
+            .uploads()
+            .all(|upload| self.transfer.is_complete(upload))
+    }
+
+    /// Frees a scene's models like `destroy_model`.
+    pub fn destroy_scene(&mut self, scene: Scene) -> Result<()> {
+        self.transfer.submit(&self.device)?;
+        unsafe { self.device.device.device_wait_idle() }?;
+        scene.destroy(&self.device);
+        self.transfer.collect(&self.device)
+    }
+
+    /// Frees a model once the device no longer uses it, including for pending uploads.
+    pub fn destroy_model(&mut self, model: Model) -> Result<()> {
+        self.transfer.submit(&self.device)?;
+        unsafe { self.device.device.device_wait_idle() }?;
+        model.destroy(&self.device);
+        self.transfer.collect(&self.device)
+    }
+
+    /// Usage of the device memory blocks backing buffers and images.
+    pub fn memory_statistics(&self) -> MemoryStatistics {
+        self.device.memory_statistics()
+    }
+
+    /// Renders a frame and reads its final color attachment back to the host.
+    pub fn capture_frame(&mut self) -> Result<Frame> {
+        self.capture(Self::render)
+    }
+
+    /// Submits the draw list like `end_frame` and reads the frame back to the host.
+    pub fn end_frame_and_capture(&mut self, draw_list: DrawList) -> Result<Frame> {
+        self.capture(|renderer| renderer.end_frame(draw_list))
+    }
+
+    fn capture<F: FnOnce(&mut Self) -> Result<()>>(&mut self, render: F) -> Result<Frame> {
+        if !self.target.supports_capture() {
+            return Err(RendererError::CaptureUnsupported(self.target.format()));
+        }
+        self.capture_requested = true;
+        let result = render(self);
+        self.capture_requested = false;
+        result?;
+        self.captured_frame
+            .take()
+            .ok_or(RendererError::CaptureUnsupported(self.target.format()))
+    }
+
+    fn read_image(&self, image_index: u32) -> Result<Frame> {
+        let extent = self.target.extent();
+        let buffer = Buffer::new(
+            &self.device,
+            (extent.width * extent.height * 4) as _,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        let pixels = buffer
+            .copy_from_image(
+                &self.device,
+                &self.command_pool,
+                *self.target.images().get(image_index as usize).unwrap(),
+                self.target.final_layout(),
+                extent,
+            )
+            .and_then(|_| buffer.read_arr(&self.device));
+        buffer.destory(&self.device);
+        Frame::from_raw(extent, self.target.format(), pixels?)
+    }
+
+    /// Looks at the origin from (2, 2, 2) with Z up.
+    fn default_camera() -> Camera {
+        let mut camera = Camera::new(Projection::Perspective {
+            fov_y: cgmath::Deg(45_f32).into(),
+            near: 0.1,
+            far: 10.0,
+        });
+        camera.look_at(
+            cgmath::Point3::new(2_f32, 2_f32, 2_f32),
+            cgmath::Point3::new(0_f32, 0_f32, 0_f32),
+            cgmath::Vector3::new(0_f32, 0_f32, 1_f32),
+        );
+        camera
+    }
+
+    fn depth_compare_op(reverse_z: bool) -> vk::CompareOp {
+        if reverse_z {
+            vk::CompareOp::GREATER_OR_EQUAL
+        } else {
+            vk::CompareOp::LESS
+        }
+    }
+
+    fn uniform_object(&self) -> UniformObject {
+        let extent = self.target.extent();
+        UniformObject {
+            view: self.camera.view(),
+            projection: self
+                .camera
+                .projection_matrix(extent.width as f32 / extent.height as f32),
+        }
+    }
+
+    fn view_projection(&self) -> cgmath::Matrix4<f32> {
+        let uniform_object = self.uniform_object();
+        uniform_object.projection * uniform_object.view
+    }
+
+    /// Writes the frame's uniforms and returns their dynamic offset.
+    fn update_uniform_buffer(&mut self, current_image: usize) -> Result<u32> {
+        let uniform_object = self.uniform_object();
+        self.uniform_ring.begin_frame(current_image);
+        let offset = self.uniform_ring.push(&uniform_object)?;
+        debug_assert_eq!(
+            offset as usize,
+            self.uniform_ring.frame_offset(current_image)
+        );
+        self.uniform_ring.flush(&self.device)?;
+        Ok(offset)
+    }
+
+    /// The MSAA sample count in use, after clamping to what the device supports.
+    pub fn msaa_samples(&self) -> u32 {
+        self.samples.as_raw()
+    }
+
+    /// Changes the MSAA sample count, rebuilding the render pass, pipeline and framebuffers.
+    pub fn set_msaa_samples(&mut self, samples: u32) -> Result<()> {
+        let samples = self.device.supported_sample_count(samples);
+        if samples == self.samples {
+            return Ok(());
+        }
+        unsafe { self.device.device.device_wait_idle() }?;
+        self.destroy_render_targets();
+        self.samples = samples;
+        self.create_render_targets()
+    }
+
+    /// Replaces the swapchain after a resize. Only the attachments, framebuffers and per-image
+    /// state follow the new extent and image count, unless the surface format changed as well.
+    fn recreate_swapchain(&mut self) -> Result<()> {
+        unsafe { self.device.device.device_wait_idle() }?;
+
+        let format = self.target.format();
+        if let Target::Window { surface, swapchain } = &mut self.target {
+            surface.update_format_and_capabilities(&self.device.physical_device)?;
+            let new_swapchain =
+                Swapchain::new(&self.instance, surface, &self.device, swapchain.swapchain)?;
+            std::mem::replace(swapchain, new_swapchain).destroy(&self.device);
+        }
+        let image_count = self.target.images().len();
+        self.sync_objects.images_in_flight.clear();
+        self.sync_objects.images_in_flight.resize(image_count, None);
+
+        if self.target.format() != format {
+            self.destroy_render_targets();
+            return self.create_render_targets();
+        }
+        std::mem::take(&mut self.framebuffers).destroy(&self.device);
+        self.create_framebuffers()?;
+        if self.resize_uniform_ring()? {
+            Self::write_frame_descriptor_set(
+                &self.device,
+                &self.descriptor_set_layout,
+                self.descriptor_set,
+                &self.uniform_ring,
+            );
+        }
+        Ok(())
+    }
+
+    fn destroy_render_targets(&mut self) {
+        std::mem::take(&mut self.framebuffers).destroy(&self.device);
+        std::mem::take(&mut self.pipeline).destroy(&self.device);
+        for (pipeline, _) in self.retired_pipelines.drain(..) {
+            pipeline.destroy(&self.device);
+        }
+        std::mem::take(&mut self.render_pass).destroy(&self.device);
         unsafe {
-            self.device
-                .device
-                .destroy_descriptor_pool(self.descriptor_pool, None)
+            self.device.device.destroy_descriptor_pool(
+                std::mem::replace(&mut self.descriptor_pool, vk::DescriptorPool::null()),
+                None,
+            )
         };
+    }
 
-        self.surface
-            .update_format_and_capabilities(&self.device.physical_device);
-        self.swapchain = Swapchain::new(&self.instance, &self.surface, &self.device);
-        self.render_pass = RenderPass::new(&self.surface, &self.device);
+    /// Rebuilds everything that depends on the target or the sample count.
+    fn create_render_targets(&mut self) -> Result<()> {
+        self.render_pass = RenderPass::new(
+            &self.device,
+            self.target.format(),
+            self.target.final_layout(),
+            self.depth_image.format,
+            self.samples,
+        )?;
         self.pipeline = Pipeline::new(
-            &self.surface,
             &self.device,
             &self.render_pass,
-            &self.descriptor_set_layout,
-        );
-        self.framebuffers = Framebuffers::new(
-            &self.surface,
+            &[
+                self.descriptor_set_layout.layout,
+                self.material_set_layout.layout,
+            ],
+            self.samples,
+            Self::depth_compare_op(self.camera.reverse_z),
+            &self.shaders,
+        )?;
+        self.create_framebuffers()?;
+        self.resize_uniform_ring()?;
+        self.descriptor_pool = Self::create_descriptor_pool(
             &self.device,
-            &self.swapchain,
-            &self.render_pass,
-        );
-        self.uniform_buffers =
-            Self::create_uniform_buffers(&self.instance, &self.device, &self.swapchain);
-        self.descriptor_pool = Self::create_descriptor_pool(&self.device, &self.swapchain);
-        self.descriptor_sets = Self::create_descriptor_sets(
+            &self.descriptor_set_layout,
+            &self.material_set_layout,
+        )?;
+        self.descriptor_set = Self::create_descriptor_set(
             &self.device,
-            &self.swapchain,
             &self.descriptor_set_layout,
-            &self.uniform_buffers,
-            &self.descriptor_pool,
-        );
-        self.command_buffers = CommandBuffers::new(
-            &self.surface,
+            &self.uniform_ring,
+            self.descriptor_pool,
+        )?;
+        self.default_material = Material::new(
             &self.device,
+            self.descriptor_pool,
+            &self.material_set_layout,
+            MaterialData::default(),
+            self.texture.as_ref().unwrap_or(&self.white_texture),
+        )?;
+        Ok(())
+    }
+
+    /// Rebuilds the attachments that no longer match the target and the framebuffers.
+    fn create_framebuffers(&mut self) -> Result<()> {
+        let color_image_stale = match &self.color_image {
+            Some(color_image) => {
+                color_image.extent != self.target.extent()
+                    || color_image.format != self.target.format()
+                    || color_image.samples != self.samples
+            }
+            None => self.samples != vk::SampleCountFlags::TYPE_1,
+        };
+        if color_image_stale {
+            if let Some(color_image) = self.color_image.take() {
+                color_image.destroy(&self.device);
+            }
+            self.color_image = Self::create_color_image(&self.device, &self.target, self.samples)?;
+        }
+        if self.depth_image.extent != self.target.extent()
+            || self.depth_image.samples != self.samples
+        {
+            let depth_image = Self::create_depth_image(
+                &self.device,
+                self.target.extent(),
+                self.depth_image.format,
+                self.samples,
+            )?;
+            std::mem::replace(&mut self.depth_image, depth_image).destroy(&self.device);
+        }
+        self.framebuffers = Framebuffers::new(
+            &self.device,
+            self.target.extent(),
+            self.target.image_views(),
+            self.color_image
+                .as_ref()
+                .map(|color_image| color_image.view),
+            self.depth_image.view,
             &self.render_pass,
-            &self.pipeline,
-            &self.framebuffers,
-            &self.command_pool,
-            &self.vertex_buffer,
-            &self.index_buffer,
-            &self.descriptor_sets,
-        );
+        )?;
+        Ok(())
     }
 
-    fn create_descriptor_set_layout(device: &Device) -> vk::DescriptorSetLayout {
-        unsafe {
-            device.device.create_descriptor_set_layout(
-                &vk::DescriptorSetLayoutCreateInfo {
-                    binding_count: 1,
-                    p_bindings: &vk::DescriptorSetLayoutBinding {
-                        binding: 0,
-                        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                        descriptor_count: 1,
-                        stage_flags: vk::ShaderStageFlags::VERTEX,
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-                None,
-            )
+    /// Gives the uniform ring a slot per target image, returning whether it was replaced.
+    fn resize_uniform_ring(&mut self) -> Result<bool> {
+        let image_count = self.target.images().len();
+        if image_count * self.uniform_ring.frame_size == self.uniform_ring.buffer.size {
+            return Ok(false);
         }
-        .unwrap()
+        let uniform_ring = Self::create_uniform_ring(&self.device, image_count)?;
+        std::mem::replace(&mut self.uniform_ring, uniform_ring).destroy(&self.device);
+        Ok(true)
     }
 
-    fn create_uniform_buffers(
-        instance: &Instance,
-        device: &Device,
-        swapchain: &Swapchain,
-    ) -> Vec<Buffer> {
-        (0..swapchain.images.len())
-            .map(|_| {
-                Buffer::new(
-                    instance,
-                    device,
-                    UNIFORM_OBJECT_SIZE,
-                    vk::BufferUsageFlags::UNIFORM_BUFFER,
-                    vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-                )
+    /// The bindings of the per-frame set, whose uniforms are bound at a dynamic offset into the
+    /// ring buffer. The ring is all the set provides.
+    fn frame_set_bindings(
+        reflection: &ShaderReflection,
+    ) -> Result<Vec<vk::DescriptorSetLayoutBinding>> {
+        reflection
+            .set_layout_bindings(FRAME_SET)
+            .into_iter()
+            .map(|binding| match binding.descriptor_type {
+                vk::DescriptorType::UNIFORM_BUFFER => Ok(vk::DescriptorSetLayoutBinding {
+                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+                    ..binding
+                }),
+                descriptor_type => Err(RendererError::ShaderReflection(format!(
+                    "Frame binding {} is a {:?}, but the frame set only binds uniform buffers",
+                    binding.binding, descriptor_type
+                ))),
             })
-            .collect::<Vec<_>>()
+            .collect()
     }
 
-    fn create_descriptor_pool(device: &Device, swapchain: &Swapchain) -> vk::DescriptorPool {
-        unsafe {
-            device.device.create_descriptor_pool(
-                &vk::DescriptorPoolCreateInfo {
-                    pool_size_count: 1,
-                    p_pool_sizes: &vk::DescriptorPoolSize {
-                        ty: vk::DescriptorType::UNIFORM_BUFFER,
-                        descriptor_count: swapchain.images.len() as _,
-                    },
-                    max_sets: swapchain.images.len() as _,
-                    ..Default::default()
-                },
-                None,
-            )
-        }
-        .unwrap()
+    fn create_depth_image(
+        device: &Device,
+        extent: vk::Extent2D,
+        depth_format: vk::Format,
+        samples: vk::SampleCountFlags,
+    ) -> Result<Image> {
+        Image::new(
+            device,
+            extent,
+            depth_format,
+            1,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            samples,
+        )
     }
 
-    fn create_descriptor_sets(
+    /// The multisampled color image resolved into the target, if MSAA is enabled.
+    fn create_color_image(
         device: &Device,
-        swapchain: &Swapchain,
-        descriptor_set_layout: &vk::DescriptorSetLayout,
-        uniform_buffers: &[Buffer],
-        descriptor_pool: &vk::DescriptorPool,
-    ) -> Vec<vk::DescriptorSet> {
-        let descriptor_sets = unsafe {
-            device
-                .device
-                .allocate_descriptor_sets(&vk::DescriptorSetAllocateInfo {
-                    descriptor_pool: *descriptor_pool,
-                    descriptor_set_count: swapchain.images.len() as _,
-                    p_set_layouts: (0..swapchain.images.len())
-                        .map(|_| *descriptor_set_layout)
-                        .collect::<Vec<_>>()
-                        .as_ptr(),
-                    ..Default::default()
-                })
+        target: &Target,
+        samples: vk::SampleCountFlags,
+    ) -> Result<Option<Image>> {
+        if samples == vk::SampleCountFlags::TYPE_1 {
+            return Ok(None);
         }
-        .unwrap();
-        descriptor_sets
+        Image::new(
+            device,
+            target.extent(),
+            target.format(),
+            1,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+            samples,
+        )
+        .map(Some)
+    }
+
+    fn create_uniform_ring(device: &Device, image_count: usize) -> Result<RingBuffer> {
+        RingBuffer::new(
+            device,
+            UNIFORM_RING_FRAME_SIZE,
+            image_count,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+        )
+    }
+
+    /// A pool for the frame set and the default material's set.
+    fn create_descriptor_pool(
+        device: &Device,
+        frame_set_layout: &DescriptorSetLayout,
+        material_set_layout: &DescriptorSetLayout,
+    ) -> Result<vk::DescriptorPool> {
+        DescriptorSetLayout::create_pool(device, &[(frame_set_layout, 1), (material_set_layout, 1)])
+    }
+
+    fn create_descriptor_set(
+        device: &Device,
+        descriptor_set_layout: &DescriptorSetLayout,
+        uniform_ring: &RingBuffer,
+        descriptor_pool: vk::DescriptorPool,
+    ) -> Result<vk::DescriptorSet> {
+        let descriptor_set = descriptor_set_layout.allocate(device, descriptor_pool)?;
+        Self::write_frame_descriptor_set(
+            device,
+            descriptor_set_layout,
+            descriptor_set,
+            uniform_ring,
+        );
+        Ok(descriptor_set)
+    }
+
+    /// Points every uniform binding of the frame set at `uniform_ring`.
+    fn write_frame_descriptor_set(
+        device: &Device,
+        descriptor_set_layout: &DescriptorSetLayout,
+        descriptor_set: vk::DescriptorSet,
+        uniform_ring: &RingBuffer,
+    ) {
+        let buffer_info = vk::DescriptorBufferInfo {
+            buffer: uniform_ring.buffer.buffer,
+            offset: 0,
+            range: UNIFORM_OBJECT_SIZE as _,
+        };
+        let buffer_infos = descriptor_set_layout
+            .bindings
             .iter()
-            .enumerate()
-            .for_each(|(i, descriptor_set)| unsafe {
-                device.device.update_descriptor_sets(
-                    &[vk::WriteDescriptorSet {
-                        dst_set: *descriptor_set,
-                        dst_binding: 0,
-                        dst_array_element: 0,
-                        descriptor_count: 1,
-                        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                        p_buffer_info: &vk::DescriptorBufferInfo {
-                            buffer: uniform_buffers.get(i).unwrap().buffer,
-                            offset: 0,
-                            range: UNIFORM_OBJECT_SIZE as _,
-                        },
-                        ..Default::default()
-                    }],
-                    &[],
-                )
-            });
-        descriptor_sets
+            .map(|binding| vec![buffer_info; binding.descriptor_count as usize])
+            .collect::<Vec<_>>();
+        let writes = descriptor_set_layout
+            .bindings
+            .iter()
+            .zip(&buffer_infos)
+            .map(|(binding, buffer_infos)| vk::WriteDescriptorSet {
+                dst_set: descriptor_set,
+                dst_binding: binding.binding,
+                dst_array_element: 0,
+                descriptor_count: binding.descriptor_count,
+                descriptor_type: binding.descriptor_type,
+                p_buffer_info: buffer_infos.as_ptr(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        unsafe { device.device.update_descriptor_sets(&writes, &[]) };
     }
 }
 
 impl Drop for Renderer {
     fn drop(&mut self) {
-        unsafe { self.device.device.queue_wait_idle(self.device.queue) }.unwrap();
+        let _ = unsafe { self.device.device.device_wait_idle() };
 
         unsafe {
             self.device
                 .device
                 .destroy_descriptor_pool(self.descriptor_pool, None)
         };
-        self.index_buffer.destory(&self.device);
-        self.vertex_buffer.destory(&self.device);
+        if let Some(texture) = &self.texture {
+            texture.destroy(&self.device);
+        }
+        self.white_texture.destroy(&self.device);
+        self.scene.destroy(&self.device);
         self.sync_objects.destroy(&self.device);
         self.command_buffers.free(&self.device, &self.command_pool);
         self.command_pool.destroy(&self.device);
+        self.transfer.destroy(&self.device);
         self.framebuffers.destroy(&self.device);
+        if let Some(color_image) = &self.color_image {
+            color_image.destroy(&self.device);
+        }
+        self.depth_image.destroy(&self.device);
         self.pipeline.destroy(&self.device);
-        unsafe {
-            self.device
-                .device
-                .destroy_descriptor_set_layout(self.descriptor_set_layout, None)
-        };
-        self.render_pass.destroy(&self.device);
-        self.swapchain.destroy(&self.device);
-        self.uniform_buffers
+        self.retired_pipelines
             .iter()
-            .for_each(|uniform_buffer| uniform_buffer.destory(&self.device));
+            .for_each(|(pipeline, _)| pipeline.destroy(&self.device));
+        self.descriptor_set_layout.destroy(&self.device);
+        self.material_set_layout.destroy(&self.device);
+        self.render_pass.destroy(&self.device);
+        self.target.destroy(&self.device);
+        self.uniform_ring.destroy(&self.device);
     }
 }
@@ -0,0 +1,81 @@
+use cgmath::{Matrix4, Point3};
+
+/// An axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    /// The smallest box holding every point, or `None` without points.
+    pub fn from_points<I: IntoIterator<Item = Point3<f32>>>(points: I) -> Option<Self> {
+        points.into_iter().fold(None, |aabb: Option<Self>, point| {
+            Some(match aabb {
+                Some(aabb) => Self {
+                    min: Point3::new(
+                        aabb.min.x.min(point.x),
+                        aabb.min.y.min(point.y),
+                        aabb.min.z.min(point.z),
+                    ),
+                    max: Point3::new(
+                        aabb.max.x.max(point.x),
+                        aabb.max.y.max(point.y),
+                        aabb.max.z.max(point.z),
+                    ),
+                },
+                None => Self {
+                    min: point,
+                    max: point,
+                },
+            })
+        })
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_points(IntoIterator::into_iter([
+            self.min, self.max, other.min, other.max,
+        ]))
+        .unwrap()
+    }
+
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Point3::new(min.x, min.y, min.z),
+            Point3::new(max.x, min.y, min.z),
+            Point3::new(min.x, max.y, min.z),
+            Point3::new(max.x, max.y, min.z),
+            Point3::new(min.x, min.y, max.z),
+            Point3::new(max.x, min.y, max.z),
+            Point3::new(min.x, max.y, max.z),
+            Point3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// The box around this one's corners after `transform`.
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> Self {
+        use cgmath::Transform;
+
+        Self::from_points(
+            IntoIterator::into_iter(self.corners()).map(|corner| transform.transform_point(corner)),
+        )
+        .unwrap()
+    }
+
+    /// Whether any part of the box may be visible through `view_projection`.
+    ///
+    /// The box is rejected only when all of its corners lie outside the same clip plane.
+    pub fn intersects_frustum(&self, view_projection: &Matrix4<f32>) -> bool {
+        let corners = self
+            .corners()
+            .map(|corner| view_projection * corner.to_homogeneous());
+        let outside = |plane: fn(&cgmath::Vector4<f32>) -> bool| corners.iter().all(plane);
+        !(outside(|clip| clip.x > clip.w)
+            || outside(|clip| clip.x < -clip.w)
+            || outside(|clip| clip.y > clip.w)
+            || outside(|clip| clip.y < -clip.w)
+            || outside(|clip| clip.z > clip.w)
+            || outside(|clip| clip.z < -clip.w))
+    }
+}
@@ -0,0 +1,640 @@
+use ash::vk;
+
+const DEFAULT_BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+/// How free space inside a block is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllocationStrategy {
+    /// Best fit over every gap in the block, for long-lived resources.
+    FreeList,
+    /// Bump allocation after the last live allocation; space is reclaimed once the block empties.
+    Linear,
+}
+
+/// Resources of different kinds can't share a `bufferImageGranularity` page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// Buffers and linearly tiled images.
+    Linear,
+    /// Optimally tiled images.
+    Optimal,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStatistics {
+    pub block_count: usize,
+    pub allocation_count: usize,
+    /// Bytes allocated from the driver.
+    pub block_bytes: vk::DeviceSize,
+    /// Bytes handed out to resources, excluding alignment padding.
+    pub allocated_bytes: vk::DeviceSize,
+    pub free_region_count: usize,
+    pub largest_free_region: vk::DeviceSize,
+}
+
+impl MemoryStatistics {
+    fn add(&mut self, other: &Self) {
+        self.block_count += other.block_count;
+        self.allocation_count += other.allocation_count;
+        self.block_bytes += other.block_bytes;
+        self.allocated_bytes += other.allocated_bytes;
+        self.free_region_count += other.free_region_count;
+        self.largest_free_region = self.largest_free_region.max(other.largest_free_region);
+    }
+}
+
+/// A range of a `vk::DeviceMemory` block handed out by the `Allocator`.
+#[derive(Debug)]
+pub struct Allocation {
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+    mapped: *mut u8,
+    coherent: bool,
+    memory_type_index: u32,
+    block_index: usize,
+}
+
+impl Allocation {
+    /// Host pointer to the start of the allocation if its memory type is host visible.
+    pub fn mapped_ptr(&self) -> Option<*mut u8> {
+        if self.mapped.is_null() {
+            None
+        } else {
+            Some(self.mapped)
+        }
+    }
+}
+
+struct Suballocation {
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    kind: ResourceKind,
+}
+
+struct Block {
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    mapped: *mut u8,
+    strategy: AllocationStrategy,
+    dedicated: bool,
+    suballocations: Vec<Suballocation>,
+}
+
+impl Block {
+    /// Returns the insertion index and offset for a new suballocation.
+    fn find_space(
+        &self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        kind: ResourceKind,
+        granularity: vk::DeviceSize,
+    ) -> Option<(usize, vk::DeviceSize)> {
+        let candidates = match self.strategy {
+            AllocationStrategy::FreeList => 0..self.suballocations.len() + 1,
+            AllocationStrategy::Linear => self.suballocations.len()..self.suballocations.len() + 1,
+        };
+        candidates
+            .filter_map(|i| {
+                let previous = i.checked_sub(1).map(|i| &self.suballocations[i]);
+                let next = self.suballocations.get(i);
+                let start = previous.map_or(0, |previous| previous.offset + previous.size);
+                let end = next.map_or(self.size, |next| next.offset);
+
+                let mut offset = align_up(start, alignment);
+                if let Some(previous) = previous {
+                    if previous.kind != kind
+                        && same_page(previous.offset + previous.size - 1, offset, granularity)
+                    {
+                        offset = align_up(offset, granularity);
+                    }
+                }
+                if offset + size > end {
+                    return None;
+                }
+                if let Some(next) = next {
+                    if next.kind != kind && same_page(offset + size - 1, next.offset, granularity) {
+                        return None;
+                    }
+                }
+                Some((i, offset, end - start))
+            })
+            .min_by_key(|(_, _, gap)| *gap)
+            .map(|(i, offset, _)| (i, offset))
+    }
+
+    /// Places a suballocation where `find_space` puts it, returning its offset.
+    fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        kind: ResourceKind,
+        granularity: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let (index, offset) = self.find_space(size, alignment, kind, granularity)?;
+        self.suballocations
+            .insert(index, Suballocation { offset, size, kind });
+        Some(offset)
+    }
+
+    /// Removes a suballocation; the gaps around it merge since only live ranges are tracked.
+    fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        if let Some(i) = self
+            .suballocations
+            .iter()
+            .position(|suballocation| suballocation.offset == offset && suballocation.size == size)
+        {
+            self.suballocations.remove(i);
+        }
+    }
+
+    fn statistics(&self) -> MemoryStatistics {
+        let mut statistics = MemoryStatistics {
+            block_count: 1,
+            allocation_count: self.suballocations.len(),
+            block_bytes: self.size,
+            ..Default::default()
+        };
+        let mut start = 0;
+        self.suballocations
+            .iter()
+            .map(|suballocation| {
+                (
+                    suballocation.offset,
+                    suballocation.offset + suballocation.size,
+                )
+            })
+            .chain(std::iter::once((self.size, self.size)))
+            .for_each(|(offset, end)| {
+                if offset > start {
+                    statistics.free_region_count += 1;
+                    statistics.largest_free_region =
+                        statistics.largest_free_region.max(offset - start);
+                }
+                statistics.allocated_bytes += end - offset;
+                start = end;
+            });
+        statistics
+    }
+}
+
+#[derive(Default)]
+struct Pool {
+    blocks: Vec<Option<Block>>,
+}
+
+/// Sub-allocates device memory from large blocks, one pool per memory type.
+pub struct Allocator {
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    buffer_image_granularity: vk::DeviceSize,
+    non_coherent_atom_size: vk::DeviceSize,
+    max_memory_allocation_count: u32,
+    memory_allocation_count: u32,
+    pools: Vec<Pool>,
+}
+
+impl Allocator {
+    pub fn new(
+        instance: &super::Instance,
+        physical_device: vk::PhysicalDevice,
+        limits: &vk::PhysicalDeviceLimits,
+    ) -> Self {
+        let memory_properties = unsafe {
+            instance
+                .instance
+                .get_physical_device_memory_properties(physical_device)
+        };
+        Self {
+            memory_properties,
+            buffer_image_granularity: limits.buffer_image_granularity.max(1),
+            non_coherent_atom_size: limits.non_coherent_atom_size.max(1),
+            max_memory_allocation_count: limits.max_memory_allocation_count,
+            memory_allocation_count: 0,
+            pools: (0..memory_properties.memory_type_count)
+                .map(|_| Pool::default())
+                .collect(),
+        }
+    }
+
+    /// Picks the memory type with the requested properties and the fewest extra ones.
+    pub fn find_memory_type(
+        &self,
+        memory_type_bits: u32,
+        memory_properties: vk::MemoryPropertyFlags,
+    ) -> super::Result<u32> {
+        self.memory_properties.memory_types[..self.memory_properties.memory_type_count as usize]
+            .iter()
+            .enumerate()
+            .filter(|(i, memory_type)| {
+                (memory_type_bits & (1 << i)) != 0
+                    && memory_type.property_flags.contains(memory_properties)
+            })
+            .min_by_key(|(_, memory_type)| {
+                (memory_type.property_flags.as_raw() & !memory_properties.as_raw()).count_ones()
+            })
+            .map(|(i, _)| i as _)
+            .ok_or(super::RendererError::MissingMemoryType {
+                memory_type_bits,
+                properties: memory_properties,
+            })
+    }
+
+    pub fn allocate(
+        &mut self,
+        device: &ash::Device,
+        requirements: vk::MemoryRequirements,
+        memory_properties: vk::MemoryPropertyFlags,
+        kind: ResourceKind,
+        strategy: AllocationStrategy,
+    ) -> super::Result<Allocation> {
+        let memory_type_index =
+            self.find_memory_type(requirements.memory_type_bits, memory_properties)?;
+        let block_size = self.block_size(memory_type_index);
+        let granularity = self.buffer_image_granularity;
+        let pool = &mut self.pools[memory_type_index as usize];
+
+        let found = pool
+            .blocks
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, block)| block.as_mut().map(|block| (i, block)))
+            .filter(|(_, block)| !block.dedicated && block.strategy == strategy)
+            .find_map(|(i, block)| {
+                block
+                    .allocate(requirements.size, requirements.alignment, kind, granularity)
+                    .map(|offset| (i, offset))
+            });
+        let (block_index, offset) = match found {
+            Some(found) => found,
+            None => {
+                let dedicated = requirements.size > block_size / 2;
+                let size = if dedicated {
+                    requirements.size
+                } else {
+                    block_size
+                };
+                let block = self.allocate_block(device, memory_type_index, size, strategy)?;
+                let mut block = Block { dedicated, ..block };
+                block.suballocations.push(Suballocation {
+                    offset: 0,
+                    size: requirements.size,
+                    kind,
+                });
+                let blocks = &mut self.pools[memory_type_index as usize].blocks;
+                let block_index = match blocks.iter().position(Option::is_none) {
+                    Some(i) => {
+                        blocks[i] = Some(block);
+                        i
+                    }
+                    None => {
+                        blocks.push(Some(block));
+                        blocks.len() - 1
+                    }
+                };
+                (block_index, 0)
+            }
+        };
+
+        let block = self.pools[memory_type_index as usize].blocks[block_index]
+            .as_ref()
+            .unwrap();
+        Ok(Allocation {
+            memory: block.memory,
+            offset,
+            size: requirements.size,
+            mapped: if block.mapped.is_null() {
+                block.mapped
+            } else {
+                unsafe { block.mapped.add(offset as usize) }
+            },
+            coherent: self.memory_properties.memory_types[memory_type_index as usize]
+                .property_flags
+                .contains(vk::MemoryPropertyFlags::HOST_COHERENT),
+            memory_type_index,
+            block_index,
+        })
+    }
+
+    fn block_size(&self, memory_type_index: u32) -> vk::DeviceSize {
+        let heap_index = self.memory_properties.memory_types[memory_type_index as usize].heap_index;
+        let heap_size = self.memory_properties.memory_heaps[heap_index as usize].size;
+        DEFAULT_BLOCK_SIZE.min(heap_size / 8)
+    }
+
+    fn allocate_block(
+        &mut self,
+        device: &ash::Device,
+        memory_type_index: u32,
+        size: vk::DeviceSize,
+        strategy: AllocationStrategy,
+    ) -> super::Result<Block> {
+        if self.memory_allocation_count >= self.max_memory_allocation_count {
+            return Err(vk::Result::ERROR_TOO_MANY_OBJECTS.into());
+        }
+        let memory = unsafe {
+            device.allocate_memory(
+                &vk::MemoryAllocateInfo {
+                    allocation_size: size,
+                    memory_type_index,
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+        let host_visible = self.memory_properties.memory_types[memory_type_index as usize]
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+        let mapped = if host_visible {
+            match unsafe {
+                device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+            } {
+                Ok(ptr) => ptr.cast(),
+                Err(result) => {
+                    unsafe { device.free_memory(memory, None) };
+                    return Err(result.into());
+                }
+            }
+        } else {
+            std::ptr::null_mut()
+        };
+        self.memory_allocation_count += 1;
+        Ok(Block {
+            memory,
+            size,
+            mapped,
+            strategy,
+            dedicated: false,
+            suballocations: Vec::new(),
+        })
+    }
+
+    /// Returns the allocation's range to its block, releasing blocks that are no longer needed.
+    pub fn free(&mut self, device: &ash::Device, allocation: &Allocation) {
+        let pool = &mut self.pools[allocation.memory_type_index as usize];
+        let block = match pool.blocks[allocation.block_index].as_mut() {
+            Some(block) => block,
+            None => return,
+        };
+        block.free(allocation.offset, allocation.size);
+        if !block.suballocations.is_empty() {
+            return;
+        }
+
+        let keep = !block.dedicated
+            && !pool.blocks.iter().enumerate().any(|(i, other)| {
+                i != allocation.block_index
+                    && other
+                        .as_ref()
+                        .is_some_and(|other| !other.dedicated && other.suballocations.is_empty())
+            });
+        if !keep {
+            if let Some(block) = pool.blocks[allocation.block_index].take() {
+                unsafe { device.free_memory(block.memory, None) };
+                self.memory_allocation_count -= 1;
+            }
+        }
+    }
+
+    /// Makes host writes to `offset..offset + size` of a non-coherent allocation visible to the device.
+    pub fn flush(
+        &self,
+        device: &ash::Device,
+        allocation: &Allocation,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+    ) -> super::Result<()> {
+        if allocation.coherent || size == 0 {
+            return Ok(());
+        }
+        let range = self.mapped_range(allocation, offset, size);
+        Ok(unsafe { device.flush_mapped_memory_ranges(&[range]) }?)
+    }
+
+    /// Makes device writes to `offset..offset + size` of a non-coherent allocation visible to the host.
+    pub fn invalidate(
+        &self,
+        device: &ash::Device,
+        allocation: &Allocation,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+    ) -> super::Result<()> {
+        if allocation.coherent || size == 0 {
+            return Ok(());
+        }
+        let range = self.mapped_range(allocation, offset, size);
+        Ok(unsafe { device.invalidate_mapped_memory_ranges(&[range]) }?)
+    }
+
+    /// Widens the range to `nonCoherentAtomSize` without running past the end of the block.
+    fn mapped_range(
+        &self,
+        allocation: &Allocation,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+    ) -> vk::MappedMemoryRange {
+        let block_size = self.pools[allocation.memory_type_index as usize].blocks
+            [allocation.block_index]
+            .as_ref()
+            .map_or(0, |block| block.size);
+        let start = allocation.offset + offset;
+        let begin = start / self.non_coherent_atom_size * self.non_coherent_atom_size;
+        let end = align_up(start + size, self.non_coherent_atom_size);
+        vk::MappedMemoryRange {
+            memory: allocation.memory,
+            offset: begin,
+            size: if end >= block_size {
+                vk::WHOLE_SIZE
+            } else {
+                end - begin
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn statistics(&self) -> MemoryStatistics {
+        let mut statistics = MemoryStatistics::default();
+        self.pools
+            .iter()
+            .flat_map(|pool| pool.blocks.iter().flatten())
+            .for_each(|block| statistics.add(&block.statistics()));
+        statistics
+    }
+
+    pub fn destroy(&mut self, device: &ash::Device) {
+        self.pools
+            .iter_mut()
+            .flat_map(|pool| pool.blocks.drain(..))
+            .flatten()
+            .for_each(|block| unsafe { device.free_memory(block.memory, None) });
+        self.memory_allocation_count = 0;
+    }
+}
+
+fn align_up(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+    value.div_ceil(alignment) * alignment
+}
+
+fn same_page(
+    end_of_previous: vk::DeviceSize,
+    start_of_next: vk::DeviceSize,
+    page_size: vk::DeviceSize,
+) -> bool {
+    end_of_previous / page_size == start_of_next / page_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRANULARITY: vk::DeviceSize = 256;
+
+    fn block(strategy: AllocationStrategy) -> Block {
+        Block {
+            memory: vk::DeviceMemory::null(),
+            size: 4096,
+            mapped: std::ptr::null_mut(),
+            strategy,
+            dedicated: false,
+            suballocations: Vec::new(),
+        }
+    }
+
+    fn allocate(
+        block: &mut Block,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        kind: ResourceKind,
+    ) -> Option<vk::DeviceSize> {
+        block.allocate(size, alignment, kind, GRANULARITY)
+    }
+
+    #[test]
+    fn offsets_are_aligned() {
+        let mut free_list = block(AllocationStrategy::FreeList);
+        let mut linear = block(AllocationStrategy::Linear);
+        for block in [&mut free_list, &mut linear] {
+            assert_eq!(allocate(block, 10, 1, ResourceKind::Linear), Some(0));
+            assert_eq!(allocate(block, 16, 64, ResourceKind::Linear), Some(64));
+        }
+        // Only the free list fills the padding before an aligned allocation.
+        assert_eq!(
+            allocate(&mut free_list, 4, 4, ResourceKind::Linear),
+            Some(12)
+        );
+        assert_eq!(allocate(&mut linear, 4, 4, ResourceKind::Linear), Some(80));
+    }
+
+    #[test]
+    fn linear_and_optimal_resources_do_not_share_a_page() {
+        let mut linear = block(AllocationStrategy::Linear);
+        assert_eq!(allocate(&mut linear, 100, 4, ResourceKind::Linear), Some(0));
+        assert_eq!(
+            allocate(&mut linear, 100, 4, ResourceKind::Optimal),
+            Some(256)
+        );
+        assert_eq!(
+            allocate(&mut linear, 100, 4, ResourceKind::Optimal),
+            Some(356)
+        );
+        assert_eq!(
+            allocate(&mut linear, 100, 4, ResourceKind::Linear),
+            Some(512)
+        );
+
+        // A gap on the page of a following optimal image only takes optimal images.
+        let mut free_list = block(AllocationStrategy::FreeList);
+        assert_eq!(
+            allocate(&mut free_list, 100, 4, ResourceKind::Optimal),
+            Some(0)
+        );
+        assert_eq!(
+            allocate(&mut free_list, 100, 4, ResourceKind::Optimal),
+            Some(100)
+        );
+        free_list.free(0, 100);
+        assert_eq!(
+            allocate(&mut free_list, 50, 4, ResourceKind::Linear),
+            Some(256)
+        );
+        assert_eq!(
+            allocate(&mut free_list, 80, 4, ResourceKind::Optimal),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn freed_space_is_reused() {
+        let mut free_list = block(AllocationStrategy::FreeList);
+        let mut linear = block(AllocationStrategy::Linear);
+        for block in [&mut free_list, &mut linear] {
+            for offset in [0, 100, 200] {
+                assert_eq!(allocate(block, 100, 4, ResourceKind::Linear), Some(offset));
+            }
+            block.free(100, 100);
+        }
+        assert_eq!(
+            allocate(&mut free_list, 100, 4, ResourceKind::Linear),
+            Some(100)
+        );
+        // Linear blocks only bump past the last live allocation until they empty.
+        assert_eq!(
+            allocate(&mut linear, 100, 4, ResourceKind::Linear),
+            Some(300)
+        );
+        for offset in [0, 200, 300] {
+            linear.free(offset, 100);
+        }
+        assert_eq!(allocate(&mut linear, 100, 4, ResourceKind::Linear), Some(0));
+    }
+
+    #[test]
+    fn neighbouring_free_ranges_merge() {
+        let mut block = block(AllocationStrategy::FreeList);
+        for offset in [0, 100, 200, 300] {
+            assert_eq!(
+                allocate(&mut block, 100, 4, ResourceKind::Linear),
+                Some(offset)
+            );
+        }
+        block.free(0, 100);
+        block.free(200, 100);
+        assert_eq!(block.statistics().free_region_count, 3);
+        block.free(100, 100);
+        assert_eq!(block.statistics().free_region_count, 2);
+        assert_eq!(allocate(&mut block, 300, 4, ResourceKind::Linear), Some(0));
+    }
+
+    #[test]
+    fn statistics_count_allocations_and_free_regions() {
+        let mut first = block(AllocationStrategy::FreeList);
+        allocate(&mut first, 10, 1, ResourceKind::Linear);
+        allocate(&mut first, 16, 64, ResourceKind::Linear);
+        assert_eq!(
+            first.statistics(),
+            MemoryStatistics {
+                block_count: 1,
+                allocation_count: 2,
+                block_bytes: 4096,
+                allocated_bytes: 26,
+                free_region_count: 2,
+                largest_free_region: 4016,
+            }
+        );
+
+        let second = block(AllocationStrategy::Linear);
+        let mut total = MemoryStatistics::default();
+        total.add(&first.statistics());
+        total.add(&second.statistics());
+        assert_eq!(
+            total,
+            MemoryStatistics {
+                block_count: 2,
+                allocation_count: 2,
+                block_bytes: 8192,
+                allocated_bytes: 26,
+                free_region_count: 3,
+                largest_free_region: 4096,
+            }
+        );
+    }
+}
@@ -1,20 +1,46 @@
-use super::{CommandPool, Device, Instance};
+use super::{Allocation, AllocationStrategy, CommandPool, Device, ResourceKind};
 use ash::vk;
 
 pub struct Buffer {
     pub size: usize,
     pub buffer: vk::Buffer,
-    pub memory: vk::DeviceMemory,
+    pub allocation: Allocation,
 }
 
 impl Buffer {
     pub fn new(
-        instance: &Instance,
         device: &Device,
         size: usize,
         buffer_usage: vk::BufferUsageFlags,
         memory_properties: vk::MemoryPropertyFlags,
-    ) -> Self {
+    ) -> super::Result<Self> {
+        Self::with_strategy(
+            device,
+            size,
+            buffer_usage,
+            memory_properties,
+            AllocationStrategy::FreeList,
+        )
+    }
+
+    /// A host-visible transfer source, allocated linearly since it only lives until its upload finishes.
+    pub fn staging(device: &Device, size: usize) -> super::Result<Self> {
+        Self::with_strategy(
+            device,
+            size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            AllocationStrategy::Linear,
+        )
+    }
+
+    pub fn with_strategy(
+        device: &Device,
+        size: usize,
+        buffer_usage: vk::BufferUsageFlags,
+        memory_properties: vk::MemoryPropertyFlags,
+        strategy: AllocationStrategy,
+    ) -> super::Result<Self> {
         let buffer = unsafe {
             device.device.create_buffer(
                 &vk::BufferCreateInfo {
@@ -25,124 +51,158 @@ impl Buffer {
                 },
                 None,
             )
-        }
-        .unwrap();
+        }?;
         let memory_requirements = unsafe { device.device.get_buffer_memory_requirements(buffer) };
 
-        let memory = unsafe {
-            device.device.allocate_memory(
-                &vk::MemoryAllocateInfo {
-                    allocation_size: memory_requirements.size,
-                    memory_type_index: instance
-                        .instance
-                        .get_physical_device_memory_properties(device.physical_device)
-                        .memory_types
-                        .iter()
-                        .enumerate()
-                        .find(|(i, memeory_type)| {
-                            (memory_requirements.memory_type_bits & (1 << i)) != 0
-                                && (memeory_type.property_flags & memory_properties)
-                                    == memory_properties
-                        })
-                        .unwrap()
-                        .0 as _,
-                    ..Default::default()
-                },
-                None,
+        let allocation = device
+            .allocate_memory(
+                memory_requirements,
+                memory_properties,
+                ResourceKind::Linear,
+                strategy,
             )
-        }
-        .unwrap();
-        unsafe { device.device.bind_buffer_memory(buffer, memory, 0) }.unwrap();
+            .and_then(|allocation| {
+                match unsafe {
+                    device
+                        .device
+                        .bind_buffer_memory(buffer, allocation.memory, allocation.offset)
+                } {
+                    Ok(()) => Ok(allocation),
+                    Err(result) => {
+                        device.free_memory(&allocation);
+                        Err(result.into())
+                    }
+                }
+            });
+        let allocation = match allocation {
+            Ok(allocation) => allocation,
+            Err(error) => {
+                unsafe { device.device.destroy_buffer(buffer, None) };
+                return Err(error);
+            }
+        };
 
-        Self {
+        Ok(Self {
             size,
             buffer,
-            memory,
-        }
+            allocation,
+        })
     }
 
-    pub fn write<T>(&self, device: &super::Device, data: T) {
-        let ptr = unsafe {
-            device
-                .device
-                .map_memory(self.memory, 0, self.size as _, vk::MemoryMapFlags::empty())
-        }
-        .unwrap();
-        unsafe { (&data as *const T).copy_to(ptr.cast(), 1) };
-        unsafe { device.device.unmap_memory(self.memory) };
+    /// Host pointer to the buffer's persistently mapped memory.
+    pub fn mapped_ptr(&self) -> super::Result<*mut u8> {
+        self.allocation
+            .mapped_ptr()
+            .ok_or_else(|| vk::Result::ERROR_MEMORY_MAP_FAILED.into())
     }
 
-    pub fn write_arr<T>(&self, device: &super::Device, data: &[T]) {
-        let ptr = unsafe {
-            device
-                .device
-                .map_memory(self.memory, 0, self.size as _, vk::MemoryMapFlags::empty())
-        }
-        .unwrap();
+    pub fn flush(&self, device: &Device, offset: usize, size: usize) -> super::Result<()> {
+        device.flush_memory(&self.allocation, offset as _, size as _)
+    }
+
+    pub fn write_arr<T>(&self, device: &Device, data: &[T]) -> super::Result<()> {
+        let ptr = self.mapped_ptr()?;
         unsafe { data.as_ptr().copy_to(ptr.cast(), data.len()) };
-        unsafe { device.device.unmap_memory(self.memory) };
+        self.flush(device, 0, std::mem::size_of_val(data))
     }
 
-    pub fn copy_to(&self, device: &super::Device, command_pool: &CommandPool, dst_buffer: &Self) {
-        let command_buffer = unsafe {
-            device
-                .device
-                .allocate_command_buffers(&vk::CommandBufferAllocateInfo {
-                    level: vk::CommandBufferLevel::PRIMARY,
-                    command_pool: command_pool.command_pool,
-                    command_buffer_count: 1,
-                    ..Default::default()
-                })
-        }
-        .unwrap()
-        .remove(0);
+    pub fn read_arr<T: Copy>(&self, device: &Device) -> super::Result<Vec<T>> {
+        let len = self.size / std::mem::size_of::<T>();
+        let mut data = Vec::<T>::with_capacity(len);
+        let ptr = self.mapped_ptr()?;
+        device.invalidate_memory(&self.allocation, 0, self.size as _)?;
         unsafe {
-            device.device.begin_command_buffer(
+            data.as_mut_ptr().copy_from(ptr.cast::<T>(), len);
+            data.set_len(len);
+        }
+        Ok(data)
+    }
+
+    pub fn copy_from_image(
+        &self,
+        device: &super::Device,
+        command_pool: &CommandPool,
+        image: vk::Image,
+        layout: vk::ImageLayout,
+        extent: vk::Extent2D,
+    ) -> super::Result<()> {
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        command_pool.submit_one_time(device, |command_buffer| unsafe {
+            device.device.cmd_pipeline_barrier(
                 command_buffer,
-                &vk::CommandBufferBeginInfo {
-                    flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    old_layout: layout,
+                    new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    image,
+                    subresource_range,
                     ..Default::default()
-                },
-            )
-        }
-        .unwrap();
-        unsafe {
-            device.device.cmd_copy_buffer(
+                }],
+            );
+            device.device.cmd_copy_image_to_buffer(
                 command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
                 self.buffer,
-                dst_buffer.buffer,
-                &[vk::BufferCopy {
-                    size: self.size as _,
+                &[vk::BufferImageCopy {
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_extent: vk::Extent3D {
+                        width: extent.width,
+                        height: extent.height,
+                        depth: 1,
+                    },
                     ..Default::default()
                 }],
             );
-            device.device.end_command_buffer(command_buffer)
-        }
-        .unwrap();
-        unsafe {
-            device.device.queue_submit(
-                device.queue,
-                &[vk::SubmitInfo {
-                    command_buffer_count: 1,
-                    p_command_buffers: &command_buffer,
+            device.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST | vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[vk::MemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    dst_access_mask: vk::AccessFlags::HOST_READ,
                     ..Default::default()
                 }],
-                vk::Fence::null(),
-            )
-        }
-        .unwrap();
-        unsafe { device.device.queue_wait_idle(device.queue) }.unwrap();
-        unsafe {
-            device
-                .device
-                .free_command_buffers(command_pool.command_pool, &[command_buffer])
-        };
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    dst_access_mask: vk::AccessFlags::empty(),
+                    old_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    new_layout: layout,
+                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    image,
+                    subresource_range,
+                    ..Default::default()
+                }],
+            );
+        })
     }
 
     pub fn destory(&self, device: &Device) {
         unsafe {
             device.device.destroy_buffer(self.buffer, None);
-            device.device.free_memory(self.memory, None);
         }
+        device.free_memory(&self.allocation);
     }
 }
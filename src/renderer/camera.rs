@@ -0,0 +1,145 @@
+use cgmath::{EuclideanSpace, InnerSpace, Matrix, Matrix3, Matrix4, Point3, Quaternion, Vector3};
+
+/// How a camera maps view space to clip space.
+///
+/// Depth follows Vulkan's 0 to 1 range, from the near plane to the far one unless reversed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective {
+        fov_y: cgmath::Rad<f32>,
+        near: f32,
+        far: f32,
+    },
+    /// A box `height` units tall, as wide as the viewport's aspect ratio makes it.
+    Orthographic { height: f32, near: f32, far: f32 },
+}
+
+impl Projection {
+    /// The projection matrix; `reverse_z` maps the near plane to depth 1 and the far one to 0.
+    pub fn matrix(&self, aspect: f32, reverse_z: bool) -> Matrix4<f32> {
+        match *self {
+            Self::Perspective { fov_y, near, far } => {
+                let focal_length = 1.0 / (fov_y.0 / 2.0).tan();
+                let (depth_scale, depth_offset) = if reverse_z {
+                    (near / (far - near), near * far / (far - near))
+                } else {
+                    (far / (near - far), near * far / (near - far))
+                };
+                Matrix4::new(
+                    focal_length / aspect,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    focal_length,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    depth_scale,
+                    -1.0,
+                    0.0,
+                    0.0,
+                    depth_offset,
+                    0.0,
+                )
+            }
+            Self::Orthographic { height, near, far } => {
+                let (depth_scale, depth_offset) = if reverse_z {
+                    (1.0 / (far - near), far / (far - near))
+                } else {
+                    (1.0 / (near - far), near / (near - far))
+                };
+                Matrix4::new(
+                    2.0 / (height * aspect),
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    2.0 / height,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    depth_scale,
+                    0.0,
+                    0.0,
+                    0.0,
+                    depth_offset,
+                    1.0,
+                )
+            }
+        }
+    }
+}
+
+/// A viewpoint looking down its local negative Z axis, with its local Y axis up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub position: Point3<f32>,
+    /// Rotates the camera's local axes into world space.
+    pub orientation: Quaternion<f32>,
+    pub projection: Projection,
+    /// Puts the far plane at depth 0, which spreads floating point depth precision far more
+    /// evenly across the view.
+    pub reverse_z: bool,
+}
+
+impl Camera {
+    /// A camera at the origin looking down the negative Z axis.
+    pub fn new(projection: Projection) -> Self {
+        Self {
+            position: Point3::origin(),
+            orientation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+            projection,
+            reverse_z: false,
+        }
+    }
+
+    /// A camera placed like a scene node, with any scale in `transform` ignored.
+    pub fn from_transform(transform: &Matrix4<f32>, projection: Projection) -> Self {
+        let rotation = Matrix3::from_cols(
+            transform.x.truncate().normalize(),
+            transform.y.truncate().normalize(),
+            transform.z.truncate().normalize(),
+        );
+        Self {
+            position: Point3::from_vec(transform.w.truncate()),
+            orientation: rotation.into(),
+            ..Self::new(projection)
+        }
+    }
+
+    /// Moves the camera to `eye` and turns it towards `target`, keeping `up` above it.
+    pub fn look_at(&mut self, eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) {
+        let view = Matrix4::look_at_rh(eye, target, up);
+        let rotation = Matrix3::from_cols(view.x.truncate(), view.y.truncate(), view.z.truncate());
+        self.position = eye;
+        self.orientation = rotation.transpose().into();
+    }
+
+    pub fn forward(&self) -> Vector3<f32> {
+        self.orientation * -Vector3::unit_z()
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.orientation * Vector3::unit_x()
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.orientation * Vector3::unit_y()
+    }
+
+    pub fn view(&self) -> Matrix4<f32> {
+        Matrix4::from(self.orientation.conjugate())
+            * Matrix4::from_translation(-self.position.to_vec())
+    }
+
+    pub fn projection_matrix(&self, aspect: f32) -> Matrix4<f32> {
+        self.projection.matrix(aspect, self.reverse_z)
+    }
+
+    pub fn view_projection(&self, aspect: f32) -> Matrix4<f32> {
+        self.projection_matrix(aspect) * self.view()
+    }
+}
@@ -0,0 +1,345 @@
+use super::{Camera, Projection};
+use cgmath::{InnerSpace, Point2, Point3, Quaternion, Rad, Vector2, Vector3};
+use winit::event::{
+    ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+
+/// Pixels of a touchpad scroll counted as one line of a mouse wheel.
+const PIXELS_PER_SCROLL_LINE: f64 = 40.0;
+
+/// Moves a camera in response to window input.
+///
+/// Forward every window event to `handle_event`, then call `update` once per frame.
+pub trait CameraController {
+    /// Records the input the controller reacts to, returning whether it used the event.
+    fn handle_event(&mut self, event: &WindowEvent) -> bool;
+
+    /// Applies the input since the last update, `elapsed` ago, to `camera`.
+    fn update(&mut self, camera: &mut Camera, elapsed: std::time::Duration);
+}
+
+/// The keys, buttons, cursor motion and scrolling seen since the last update.
+struct Input {
+    keys: std::collections::HashSet<VirtualKeyCode>,
+    buttons: std::collections::HashSet<MouseButton>,
+    cursor: Option<Point2<f64>>,
+    cursor_delta: Vector2<f32>,
+    scroll: f32,
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self {
+            keys: Default::default(),
+            buttons: Default::default(),
+            cursor: None,
+            cursor_delta: Vector2::new(0.0, 0.0),
+            scroll: 0.0,
+        }
+    }
+}
+
+impl Input {
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match *event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => {
+                match state {
+                    ElementState::Pressed => self.keys.insert(key),
+                    ElementState::Released => self.keys.remove(&key),
+                };
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                match state {
+                    ElementState::Pressed => self.buttons.insert(button),
+                    ElementState::Released => self.buttons.remove(&button),
+                };
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = Point2::new(position.x, position.y);
+                if let Some(cursor) = self.cursor {
+                    self.cursor_delta += (position - cursor).cast().unwrap();
+                }
+                self.cursor = Some(position);
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll += match delta {
+                    MouseScrollDelta::LineDelta(_, lines) => lines,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        (position.y / PIXELS_PER_SCROLL_LINE) as f32
+                    }
+                }
+            }
+            // Releases aren't delivered while unfocused, so nothing may stay held.
+            WindowEvent::Focused(false) => {
+                self.keys.clear();
+                self.buttons.clear();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn is_key_held(&self, key: VirtualKeyCode) -> bool {
+        self.keys.contains(&key)
+    }
+
+    fn is_button_held(&self, button: MouseButton) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    /// The cursor motion in pixels since the last call.
+    fn take_cursor_delta(&mut self) -> Vector2<f32> {
+        std::mem::replace(&mut self.cursor_delta, Vector2::new(0.0, 0.0))
+    }
+
+    fn take_scroll(&mut self) -> f32 {
+        std::mem::take(&mut self.scroll)
+    }
+}
+
+/// The direction at `yaw` around `up` and `pitch` above the plane normal to it.
+///
+/// Yaw starts from the X axis when `up` is Y or Z.
+fn direction(up: Vector3<f32>, yaw: Rad<f32>, pitch: Rad<f32>) -> Vector3<f32> {
+    use cgmath::Angle;
+
+    let frame = Quaternion::from_arc(Vector3::unit_z(), up.normalize(), Some(Vector3::unit_x()));
+    frame
+        * Vector3::new(
+            pitch.cos() * yaw.cos(),
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+        )
+}
+
+/// The yaw and pitch `direction` turns into `forward`.
+fn angles(up: Vector3<f32>, forward: Vector3<f32>) -> (Rad<f32>, Rad<f32>) {
+    use cgmath::Angle;
+
+    let frame = Quaternion::from_arc(up.normalize(), Vector3::unit_z(), Some(Vector3::unit_x()));
+    let local = frame * forward.normalize();
+    (
+        Rad::atan2(local.y, local.x),
+        Rad::asin(local.z.clamp(-1.0, 1.0)),
+    )
+}
+
+/// Keeps the pitch just short of straight up or down, where the view would flip over.
+fn clamp_pitch(pitch: Rad<f32>) -> Rad<f32> {
+    let limit = std::f32::consts::FRAC_PI_2 * 0.99;
+    Rad(pitch.0.clamp(-limit, limit))
+}
+
+/// Orbits a target point: left drag turns around it, right or middle drag pans and scrolling
+/// zooms.
+pub struct OrbitController {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    pub yaw: Rad<f32>,
+    pub pitch: Rad<f32>,
+    pub up: Vector3<f32>,
+    /// Radians turned per pixel dragged.
+    pub rotate_speed: f32,
+    /// Distance panned per pixel dragged, relative to the distance to the target.
+    pub pan_speed: f32,
+    /// The factor the distance shrinks by per line scrolled.
+    pub zoom_factor: f32,
+    /// The closest the camera gets to the target.
+    pub min_distance: f32,
+    input: Input,
+}
+
+impl OrbitController {
+    /// Orbits `target` from `eye`, with `up` as the axis turned around.
+    pub fn new(eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+        let (yaw, pitch) = angles(up, eye - target);
+        Self {
+            target,
+            distance: (eye - target).magnitude(),
+            yaw,
+            pitch,
+            up,
+            rotate_speed: 0.01,
+            pan_speed: 0.002,
+            zoom_factor: 0.9,
+            min_distance: 0.01,
+            input: Input::default(),
+        }
+    }
+
+    pub fn eye(&self) -> Point3<f32> {
+        self.target + direction(self.up, self.yaw, self.pitch) * self.distance
+    }
+}
+
+impl CameraController for OrbitController {
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        self.input.handle_event(event)
+    }
+
+    fn update(&mut self, camera: &mut Camera, _elapsed: std::time::Duration) {
+        let delta = self.input.take_cursor_delta();
+        if self.input.is_button_held(MouseButton::Left) {
+            self.yaw -= Rad(delta.x * self.rotate_speed);
+            self.pitch = clamp_pitch(self.pitch + Rad(delta.y * self.rotate_speed));
+        } else if self.input.is_button_held(MouseButton::Right)
+            || self.input.is_button_held(MouseButton::Middle)
+        {
+            camera.look_at(self.eye(), self.target, self.up);
+            let scale = self.distance * self.pan_speed;
+            self.target += (camera.up() * delta.y - camera.right() * delta.x) * scale;
+        }
+        self.distance = (self.distance * self.zoom_factor.powf(self.input.take_scroll()))
+            .max(self.min_distance);
+        camera.look_at(self.eye(), self.target, self.up);
+    }
+}
+
+/// Flies freely: WASD moves, space and Q rise and sink, shift speeds up, right drag looks
+/// around and scrolling changes the speed.
+pub struct FlyController {
+    pub position: Point3<f32>,
+    pub yaw: Rad<f32>,
+    pub pitch: Rad<f32>,
+    pub up: Vector3<f32>,
+    /// Units moved per second.
+    pub speed: f32,
+    /// Radians turned per pixel dragged.
+    pub look_speed: f32,
+    /// The factor the speed grows by per line scrolled.
+    pub speed_factor: f32,
+    /// The factor the speed is multiplied by while shift is held.
+    pub boost: f32,
+    input: Input,
+}
+
+impl FlyController {
+    /// Flies from `eye`, initially facing `target`.
+    pub fn new(eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+        let (yaw, pitch) = angles(up, target - eye);
+        Self {
+            position: eye,
+            yaw,
+            pitch,
+            up,
+            speed: 2.0,
+            look_speed: 0.005,
+            speed_factor: 1.1,
+            boost: 4.0,
+            input: Input::default(),
+        }
+    }
+
+    pub fn forward(&self) -> Vector3<f32> {
+        direction(self.up, self.yaw, self.pitch)
+    }
+}
+
+impl CameraController for FlyController {
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        self.input.handle_event(event)
+    }
+
+    fn update(&mut self, camera: &mut Camera, elapsed: std::time::Duration) {
+        let delta = self.input.take_cursor_delta();
+        if self.input.is_button_held(MouseButton::Right) {
+            self.yaw -= Rad(delta.x * self.look_speed);
+            self.pitch = clamp_pitch(self.pitch - Rad(delta.y * self.look_speed));
+        }
+        self.speed *= self.speed_factor.powf(self.input.take_scroll());
+
+        let forward = self.forward();
+        let right = forward.cross(self.up).normalize();
+        let up = self.up.normalize();
+        let axis = |positive: &[VirtualKeyCode], negative: &[VirtualKeyCode]| {
+            let held =
+                |keys: &[VirtualKeyCode]| keys.iter().any(|&key| self.input.is_key_held(key));
+            held(positive) as i32 as f32 - held(negative) as i32 as f32
+        };
+        let movement = forward * axis(&[VirtualKeyCode::W], &[VirtualKeyCode::S])
+            + right * axis(&[VirtualKeyCode::D], &[VirtualKeyCode::A])
+            + up * axis(
+                &[VirtualKeyCode::Space, VirtualKeyCode::E],
+                &[VirtualKeyCode::Q],
+            );
+        if movement.magnitude2() > 0.0 {
+            let boost = if self.input.is_key_held(VirtualKeyCode::LShift)
+                || self.input.is_key_held(VirtualKeyCode::RShift)
+            {
+                self.boost
+            } else {
+                1.0
+            };
+            self.position += movement.normalize() * self.speed * boost * elapsed.as_secs_f32();
+        }
+        camera.look_at(self.position, self.position + forward, self.up);
+    }
+}
+
+/// Looks straight down the negative Z axis at the XY plane through an orthographic projection:
+/// left or middle drag pans and scrolling zooms.
+pub struct PanZoomController {
+    pub center: Point2<f32>,
+    /// The height of the visible part of the plane.
+    pub height: f32,
+    /// The factor the height shrinks by per line scrolled.
+    pub zoom_factor: f32,
+    pub near: f32,
+    pub far: f32,
+    /// The window height in pixels, which relates dragging to the plane.
+    viewport_height: u32,
+    input: Input,
+}
+
+impl PanZoomController {
+    pub fn new(center: Point2<f32>, height: f32, viewport_height: u32) -> Self {
+        Self {
+            center,
+            height,
+            zoom_factor: 0.9,
+            near: 0.0,
+            far: 1000.0,
+            viewport_height: viewport_height.max(1),
+            input: Input::default(),
+        }
+    }
+}
+
+impl CameraController for PanZoomController {
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        if let WindowEvent::Resized(size) = event {
+            self.viewport_height = size.height.max(1);
+            return true;
+        }
+        self.input.handle_event(event)
+    }
+
+    fn update(&mut self, camera: &mut Camera, _elapsed: std::time::Duration) {
+        let delta = self.input.take_cursor_delta();
+        if self.input.is_button_held(MouseButton::Left)
+            || self.input.is_button_held(MouseButton::Middle)
+        {
+            let scale = self.height / self.viewport_height as f32;
+            self.center += Vector2::new(-delta.x, delta.y) * scale;
+        }
+        self.height *= self.zoom_factor.powf(self.input.take_scroll());
+
+        // Halfway through the depth range, the camera sees what lies on either side of the plane.
+        camera.position = Point3::new(self.center.x, self.center.y, self.far / 2.0);
+        camera.orientation = Quaternion::new(1.0, 0.0, 0.0, 0.0);
+        camera.projection = Projection::Orthographic {
+            height: self.height,
+            near: self.near,
+            far: self.far,
+        };
+    }
+}
@@ -0,0 +1,27 @@
+pub enum Clock {
+    System(std::time::SystemTime),
+    Fixed(std::time::Duration),
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self::System(std::time::SystemTime::now())
+    }
+
+    /// Advances every frame by `step`, independent of the wall clock.
+    pub fn fixed(step: std::time::Duration) -> Self {
+        Self::Fixed(step)
+    }
+
+    pub fn tick(&mut self) -> std::time::Duration {
+        match self {
+            Self::System(last) => {
+                let current_time = std::time::SystemTime::now();
+                let elapsed = current_time.duration_since(*last).unwrap_or_default();
+                *last = current_time;
+                elapsed
+            }
+            Self::Fixed(step) => *step,
+        }
+    }
+}
@@ -1,113 +1,163 @@
-use super::{
-    CommandPool, Device, Framebuffers, IndexBuffer, Pipeline, RenderPass, Surface, VertexBuffer,
-};
+use super::{CommandPool, Device, Draw, Pipeline, RenderPass};
 use ash::vk;
 
+/// One command buffer per frame in flight, recorded again every frame from its draw list.
 pub struct CommandBuffers {
     pub command_buffers: Vec<vk::CommandBuffer>,
 }
 
 impl CommandBuffers {
-    pub fn new(
-        surface: &Surface,
-        device: &Device,
-        render_pass: &RenderPass,
-        pipeline: &Pipeline,
-        framebuffers: &Framebuffers,
-        command_pool: &CommandPool,
-        vertex_buffer: &VertexBuffer,
-        index_buffer: &IndexBuffer,
-        descriptor_sets: &[vk::DescriptorSet],
-    ) -> Self {
+    pub fn new(device: &Device, command_pool: &CommandPool, count: usize) -> super::Result<Self> {
         let command_buffers = unsafe {
             device
                 .device
                 .allocate_command_buffers(&vk::CommandBufferAllocateInfo {
                     command_pool: command_pool.command_pool,
                     level: vk::CommandBufferLevel::PRIMARY,
-                    command_buffer_count: framebuffers.framebuffers.len() as _,
+                    command_buffer_count: count as _,
                     ..Default::default()
                 })
-        }
-        .unwrap();
+        }?;
 
-        command_buffers
-            .iter()
-            .enumerate()
-            .for_each(|(i, command_buffer)| {
-                unsafe {
-                    device
-                        .device
-                        .begin_command_buffer(
-                            *command_buffer,
-                            &vk::CommandBufferBeginInfo::default(),
-                        )
-                        .unwrap();
-                    device.device.cmd_begin_render_pass(
-                        *command_buffer,
-                        &vk::RenderPassBeginInfo {
-                            render_pass: render_pass.render_pass,
-                            framebuffer: *framebuffers.framebuffers.get(i).unwrap(),
-                            render_area: vk::Rect2D {
-                                offset: vk::Offset2D { x: 0, y: 0 },
-                                extent: surface.capabilities.unwrap().current_extent,
+        Ok(Self { command_buffers })
+    }
+
+    /// Records `draws` into the command buffer of `frame`, whose previous submission must have
+    /// completed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record(
+        &self,
+        device: &Device,
+        frame: usize,
+        extent: vk::Extent2D,
+        render_pass: &RenderPass,
+        pipeline: &Pipeline,
+        framebuffer: vk::Framebuffer,
+        clear_depth: f32,
+        descriptor_set: vk::DescriptorSet,
+        uniform_offset: u32,
+        draws: &[Draw],
+    ) -> super::Result<vk::CommandBuffer> {
+        let command_buffer = self.command_buffers[frame];
+        unsafe {
+            device
+                .device
+                .reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())?;
+            device.device.begin_command_buffer(
+                command_buffer,
+                &vk::CommandBufferBeginInfo {
+                    flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                    ..Default::default()
+                },
+            )?;
+            device.device.cmd_begin_render_pass(
+                command_buffer,
+                &vk::RenderPassBeginInfo {
+                    render_pass: render_pass.render_pass,
+                    framebuffer,
+                    render_area: vk::Rect2D {
+                        offset: vk::Offset2D { x: 0, y: 0 },
+                        extent,
+                    },
+                    clear_value_count: 2,
+                    p_clear_values: [
+                        vk::ClearValue {
+                            color: vk::ClearColorValue {
+                                float32: [0.0, 0.0, 0.0, 1.0],
                             },
-                            clear_value_count: 1,
-                            p_clear_values: &vk::ClearValue {
-                                color: vk::ClearColorValue {
-                                    float32: [0.0, 0.0, 0.0, 1.0],
-                                },
+                        },
+                        vk::ClearValue {
+                            depth_stencil: vk::ClearDepthStencilValue {
+                                depth: clear_depth,
+                                stencil: 0,
                             },
-                            ..Default::default()
                         },
-                        vk::SubpassContents::INLINE,
-                    );
-                    device.device.cmd_bind_pipeline(
-                        *command_buffer,
-                        vk::PipelineBindPoint::GRAPHICS,
-                        pipeline.pipeline,
-                    );
-                    device.device.cmd_bind_vertex_buffers(
-                        *command_buffer,
-                        0,
-                        &[vertex_buffer.buffer.buffer],
-                        &[0],
-                    );
-                    device.device.cmd_bind_index_buffer(
-                        *command_buffer,
-                        index_buffer.buffer.buffer,
-                        0,
-                        vk::IndexType::UINT16,
-                    );
-                    device.device.cmd_bind_descriptor_sets(
-                        *command_buffer,
-                        vk::PipelineBindPoint::GRAPHICS,
+                    ]
+                    .as_ptr(),
+                    ..Default::default()
+                },
+                vk::SubpassContents::INLINE,
+            );
+            device.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.pipeline,
+            );
+            device.device.cmd_set_viewport(
+                command_buffer,
+                0,
+                &[vk::Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    width: extent.width as _,
+                    height: extent.height as _,
+                    min_depth: 0.0,
+                    max_depth: 1.0,
+                }],
+            );
+            device.device.cmd_set_scissor(
+                command_buffer,
+                0,
+                &[vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent,
+                }],
+            );
+            device.device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.layout,
+                0,
+                &[descriptor_set],
+                &[uniform_offset],
+            );
+            for draw in draws {
+                device.device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    pipeline.layout,
+                    1,
+                    &[draw.material_set],
+                    &[],
+                );
+                if !pipeline.push_constant_stages.is_empty() {
+                    device.device.cmd_push_constants(
+                        command_buffer,
                         pipeline.layout,
+                        pipeline.push_constant_stages,
                         0,
-                        &[*descriptor_sets.get(i).unwrap()],
-                        &[],
+                        bytemuck::bytes_of(&draw.constants),
                     );
-                    device.device.cmd_draw_indexed(
-                        *command_buffer,
-                        index_buffer.indices.len() as _,
-                        1,
-                        0,
-                        0,
-                        0,
-                    );
-                    device.device.cmd_end_render_pass(*command_buffer);
-                    device.device.end_command_buffer(*command_buffer).unwrap();
-                };
-            });
-
-        Self { command_buffers }
+                }
+                device.device.cmd_bind_vertex_buffers(
+                    command_buffer,
+                    0,
+                    &[draw.vertex_buffer],
+                    &[0],
+                );
+                device.device.cmd_bind_index_buffer(
+                    command_buffer,
+                    draw.index_buffer,
+                    0,
+                    draw.index_type,
+                );
+                device
+                    .device
+                    .cmd_draw_indexed(command_buffer, draw.index_count, 1, 0, 0, 0);
+            }
+            device.device.cmd_end_render_pass(command_buffer);
+            device.device.end_command_buffer(command_buffer)?;
+        }
+        Ok(command_buffer)
     }
 
     pub fn free(&self, device: &super::Device, command_pool: &super::CommandPool) {
-        unsafe {
-            device
-                .device
-                .free_command_buffers(command_pool.command_pool, &self.command_buffers);
+        if !self.command_buffers.is_empty() {
+            unsafe {
+                device
+                    .device
+                    .free_command_buffers(command_pool.command_pool, &self.command_buffers);
+            }
         }
     }
 }
@@ -5,19 +5,68 @@ pub struct CommandPool {
 }
 
 impl CommandPool {
-    pub fn new(device: &super::Device) -> Self {
+    pub fn new(device: &super::Device, queue_family_index: u32) -> super::Result<Self> {
         let command_pool = unsafe {
             device.device.create_command_pool(
                 &vk::CommandPoolCreateInfo {
-                    queue_family_index: 0,
+                    flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+                    queue_family_index,
                     ..Default::default()
                 },
                 None,
             )
-        }
-        .unwrap();
+        }?;
+
+        Ok(Self { command_pool })
+    }
 
-        Self { command_pool }
+    pub fn submit_one_time<F: FnOnce(vk::CommandBuffer)>(
+        &self,
+        device: &super::Device,
+        record: F,
+    ) -> super::Result<()> {
+        let command_buffer = unsafe {
+            device
+                .device
+                .allocate_command_buffers(&vk::CommandBufferAllocateInfo {
+                    level: vk::CommandBufferLevel::PRIMARY,
+                    command_pool: self.command_pool,
+                    command_buffer_count: 1,
+                    ..Default::default()
+                })
+        }?
+        .remove(0);
+        let result = unsafe {
+            device.device.begin_command_buffer(
+                command_buffer,
+                &vk::CommandBufferBeginInfo {
+                    flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                    ..Default::default()
+                },
+            )
+        }
+        .and_then(|_| {
+            record(command_buffer);
+            unsafe { device.device.end_command_buffer(command_buffer) }
+        })
+        .and_then(|_| unsafe {
+            device.device.queue_submit(
+                device.queue,
+                &[vk::SubmitInfo {
+                    command_buffer_count: 1,
+                    p_command_buffers: &command_buffer,
+                    ..Default::default()
+                }],
+                vk::Fence::null(),
+            )
+        })
+        .and_then(|_| unsafe { device.device.queue_wait_idle(device.queue) });
+        unsafe {
+            device
+                .device
+                .free_command_buffers(self.command_pool, &[command_buffer])
+        };
+        Ok(result?)
     }
 
     pub fn destroy(&self, device: &super::Device) {
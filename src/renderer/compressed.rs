@@ -0,0 +1,1639 @@
+use ash::vk;
+use std::convert::TryInto;
+
+/// The footprint of one texel block: a single texel for uncompressed formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TexelBlock {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: u32,
+}
+
+impl TexelBlock {
+    const fn new(width: u32, height: u32, bytes: u32) -> Self {
+        Self {
+            width,
+            height,
+            bytes,
+        }
+    }
+
+    /// The size of a `width` x `height` image in this format.
+    pub fn image_size(&self, width: u32, height: u32) -> usize {
+        (width.div_ceil(self.width) * height.div_ceil(self.height) * self.bytes) as usize
+    }
+}
+
+/// The block layout of the texture formats the loaders understand.
+pub fn texel_block(format: vk::Format) -> Option<TexelBlock> {
+    Some(match format {
+        vk::Format::R8_UNORM | vk::Format::R8_SRGB => TexelBlock::new(1, 1, 1),
+        vk::Format::R8G8_UNORM | vk::Format::R8G8_SRGB => TexelBlock::new(1, 1, 2),
+        vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SNORM
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::B8G8R8A8_UNORM
+        | vk::Format::B8G8R8A8_SRGB => TexelBlock::new(1, 1, 4),
+        vk::Format::R16G16B16A16_SFLOAT => TexelBlock::new(1, 1, 8),
+        vk::Format::R32G32B32A32_SFLOAT => TexelBlock::new(1, 1, 16),
+        vk::Format::BC1_RGB_UNORM_BLOCK
+        | vk::Format::BC1_RGB_SRGB_BLOCK
+        | vk::Format::BC1_RGBA_UNORM_BLOCK
+        | vk::Format::BC1_RGBA_SRGB_BLOCK
+        | vk::Format::BC4_UNORM_BLOCK
+        | vk::Format::BC4_SNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK
+        | vk::Format::EAC_R11_UNORM_BLOCK
+        | vk::Format::EAC_R11_SNORM_BLOCK => TexelBlock::new(4, 4, 8),
+        vk::Format::BC2_UNORM_BLOCK
+        | vk::Format::BC2_SRGB_BLOCK
+        | vk::Format::BC3_UNORM_BLOCK
+        | vk::Format::BC3_SRGB_BLOCK
+        | vk::Format::BC5_UNORM_BLOCK
+        | vk::Format::BC5_SNORM_BLOCK
+        | vk::Format::BC6H_UFLOAT_BLOCK
+        | vk::Format::BC6H_SFLOAT_BLOCK
+        | vk::Format::BC7_UNORM_BLOCK
+        | vk::Format::BC7_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK
+        | vk::Format::EAC_R11G11_UNORM_BLOCK
+        | vk::Format::EAC_R11G11_SNORM_BLOCK => TexelBlock::new(4, 4, 16),
+        vk::Format::ASTC_4X4_UNORM_BLOCK | vk::Format::ASTC_4X4_SRGB_BLOCK => {
+            TexelBlock::new(4, 4, 16)
+        }
+        vk::Format::ASTC_5X4_UNORM_BLOCK | vk::Format::ASTC_5X4_SRGB_BLOCK => {
+            TexelBlock::new(5, 4, 16)
+        }
+        vk::Format::ASTC_5X5_UNORM_BLOCK | vk::Format::ASTC_5X5_SRGB_BLOCK => {
+            TexelBlock::new(5, 5, 16)
+        }
+        vk::Format::ASTC_6X5_UNORM_BLOCK | vk::Format::ASTC_6X5_SRGB_BLOCK => {
+            TexelBlock::new(6, 5, 16)
+        }
+        vk::Format::ASTC_6X6_UNORM_BLOCK | vk::Format::ASTC_6X6_SRGB_BLOCK => {
+            TexelBlock::new(6, 6, 16)
+        }
+        vk::Format::ASTC_8X5_UNORM_BLOCK | vk::Format::ASTC_8X5_SRGB_BLOCK => {
+            TexelBlock::new(8, 5, 16)
+        }
+        vk::Format::ASTC_8X6_UNORM_BLOCK | vk::Format::ASTC_8X6_SRGB_BLOCK => {
+            TexelBlock::new(8, 6, 16)
+        }
+        vk::Format::ASTC_8X8_UNORM_BLOCK | vk::Format::ASTC_8X8_SRGB_BLOCK => {
+            TexelBlock::new(8, 8, 16)
+        }
+        vk::Format::ASTC_10X5_UNORM_BLOCK | vk::Format::ASTC_10X5_SRGB_BLOCK => {
+            TexelBlock::new(10, 5, 16)
+        }
+        vk::Format::ASTC_10X6_UNORM_BLOCK | vk::Format::ASTC_10X6_SRGB_BLOCK => {
+            TexelBlock::new(10, 6, 16)
+        }
+        vk::Format::ASTC_10X8_UNORM_BLOCK | vk::Format::ASTC_10X8_SRGB_BLOCK => {
+            TexelBlock::new(10, 8, 16)
+        }
+        vk::Format::ASTC_10X10_UNORM_BLOCK | vk::Format::ASTC_10X10_SRGB_BLOCK => {
+            TexelBlock::new(10, 10, 16)
+        }
+        vk::Format::ASTC_12X10_UNORM_BLOCK | vk::Format::ASTC_12X10_SRGB_BLOCK => {
+            TexelBlock::new(12, 10, 16)
+        }
+        vk::Format::ASTC_12X12_UNORM_BLOCK | vk::Format::ASTC_12X12_SRGB_BLOCK => {
+            TexelBlock::new(12, 12, 16)
+        }
+        _ => return None,
+    })
+}
+
+/// The ASTC formats of each footprint with linear color.
+const ASTC_UNORM_FORMATS: [vk::Format; 14] = [
+    vk::Format::ASTC_4X4_UNORM_BLOCK,
+    vk::Format::ASTC_5X4_UNORM_BLOCK,
+    vk::Format::ASTC_5X5_UNORM_BLOCK,
+    vk::Format::ASTC_6X5_UNORM_BLOCK,
+    vk::Format::ASTC_6X6_UNORM_BLOCK,
+    vk::Format::ASTC_8X5_UNORM_BLOCK,
+    vk::Format::ASTC_8X6_UNORM_BLOCK,
+    vk::Format::ASTC_8X8_UNORM_BLOCK,
+    vk::Format::ASTC_10X5_UNORM_BLOCK,
+    vk::Format::ASTC_10X6_UNORM_BLOCK,
+    vk::Format::ASTC_10X8_UNORM_BLOCK,
+    vk::Format::ASTC_10X10_UNORM_BLOCK,
+    vk::Format::ASTC_12X10_UNORM_BLOCK,
+    vk::Format::ASTC_12X12_UNORM_BLOCK,
+];
+
+/// The ASTC formats of each footprint with sRGB color.
+const ASTC_SRGB_FORMATS: [vk::Format; 14] = [
+    vk::Format::ASTC_4X4_SRGB_BLOCK,
+    vk::Format::ASTC_5X4_SRGB_BLOCK,
+    vk::Format::ASTC_5X5_SRGB_BLOCK,
+    vk::Format::ASTC_6X5_SRGB_BLOCK,
+    vk::Format::ASTC_6X6_SRGB_BLOCK,
+    vk::Format::ASTC_8X5_SRGB_BLOCK,
+    vk::Format::ASTC_8X6_SRGB_BLOCK,
+    vk::Format::ASTC_8X8_SRGB_BLOCK,
+    vk::Format::ASTC_10X5_SRGB_BLOCK,
+    vk::Format::ASTC_10X6_SRGB_BLOCK,
+    vk::Format::ASTC_10X8_SRGB_BLOCK,
+    vk::Format::ASTC_10X10_SRGB_BLOCK,
+    vk::Format::ASTC_12X10_SRGB_BLOCK,
+    vk::Format::ASTC_12X12_SRGB_BLOCK,
+];
+
+/// The uncompressed format `format` is decompressed to, if `decompress` handles it.
+///
+/// Every BC, ETC2 and EAC format is handled, with one and two channel formats keeping their
+/// channels in red and green. BC6H keeps its HDR colors as half floats, and ASTC is decoded
+/// with the LDR profile, so HDR ASTC blocks come out magenta like on LDR-only hardware.
+pub fn decompressed_format(format: vk::Format) -> Option<vk::Format> {
+    match format {
+        vk::Format::BC6H_UFLOAT_BLOCK | vk::Format::BC6H_SFLOAT_BLOCK => {
+            Some(vk::Format::R16G16B16A16_SFLOAT)
+        }
+        format if ASTC_UNORM_FORMATS.contains(&format) => Some(vk::Format::R8G8B8A8_UNORM),
+        format if ASTC_SRGB_FORMATS.contains(&format) => Some(vk::Format::R8G8B8A8_SRGB),
+        vk::Format::BC1_RGB_SRGB_BLOCK
+        | vk::Format::BC1_RGBA_SRGB_BLOCK
+        | vk::Format::BC2_SRGB_BLOCK
+        | vk::Format::BC3_SRGB_BLOCK
+        | vk::Format::BC7_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK => Some(vk::Format::R8G8B8A8_SRGB),
+        vk::Format::BC1_RGB_UNORM_BLOCK
+        | vk::Format::BC1_RGBA_UNORM_BLOCK
+        | vk::Format::BC2_UNORM_BLOCK
+        | vk::Format::BC3_UNORM_BLOCK
+        | vk::Format::BC4_UNORM_BLOCK
+        | vk::Format::BC5_UNORM_BLOCK
+        | vk::Format::BC7_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK
+        | vk::Format::EAC_R11_UNORM_BLOCK
+        | vk::Format::EAC_R11G11_UNORM_BLOCK => Some(vk::Format::R8G8B8A8_UNORM),
+        vk::Format::BC4_SNORM_BLOCK
+        | vk::Format::BC5_SNORM_BLOCK
+        | vk::Format::EAC_R11_SNORM_BLOCK
+        | vk::Format::EAC_R11G11_SNORM_BLOCK => Some(vk::Format::R8G8B8A8_SNORM),
+        _ => None,
+    }
+}
+
+/// Decompresses one `width` x `height` image in a format `decompressed_format` accepts to
+/// tightly packed texels of the format it returns.
+pub fn decompress(format: vk::Format, width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+    let footprint = texel_block(format).unwrap();
+    let texel_bytes = texel_block(decompressed_format(format).unwrap())
+        .unwrap()
+        .bytes as usize;
+    let (block_width, block_height) = (footprint.width as usize, footprint.height as usize);
+    let blocks_wide = width.div_ceil(footprint.width) as usize;
+    let mut pixels = vec![0; width as usize * height as usize * texel_bytes];
+    for (index, block) in data.chunks_exact(footprint.bytes as usize).enumerate() {
+        let texels = decode_block(format, footprint, block);
+        let (block_x, block_y) = (
+            index % blocks_wide * block_width,
+            index / blocks_wide * block_height,
+        );
+        for (texel, color) in texels.chunks_exact(texel_bytes).enumerate() {
+            let (x, y) = (block_x + texel % block_width, block_y + texel / block_width);
+            if x < width as usize && y < height as usize {
+                let offset = (y * width as usize + x) * texel_bytes;
+                pixels[offset..offset + texel_bytes].copy_from_slice(color);
+            }
+        }
+    }
+    pixels
+}
+
+/// Decodes one block to the bytes of its texels in row order.
+fn decode_block(format: vk::Format, footprint: TexelBlock, block: &[u8]) -> Vec<u8> {
+    let (width, height) = (footprint.width as usize, footprint.height as usize);
+    match format {
+        vk::Format::BC6H_UFLOAT_BLOCK | vk::Format::BC6H_SFLOAT_BLOCK => {
+            decode_bc6h(block, format == vk::Format::BC6H_SFLOAT_BLOCK)
+                .iter()
+                .flatten()
+                .flat_map(|half| half.to_le_bytes())
+                .collect()
+        }
+        format if ASTC_UNORM_FORMATS.contains(&format) => {
+            decode_astc(block, width, height, false).concat()
+        }
+        format if ASTC_SRGB_FORMATS.contains(&format) => {
+            decode_astc(block, width, height, true).concat()
+        }
+        format => decode_rgba8_block(format, block).concat(),
+    }
+}
+
+/// Decodes one 4x4 block of a format that decompresses to 8-bit RGBA to 16 texels in row order.
+fn decode_rgba8_block(format: vk::Format, block: &[u8]) -> [[u8; 4]; 16] {
+    match format {
+        vk::Format::BC1_RGB_UNORM_BLOCK | vk::Format::BC1_RGB_SRGB_BLOCK => {
+            decode_color(&block[..8], Some(255))
+        }
+        vk::Format::BC1_RGBA_UNORM_BLOCK | vk::Format::BC1_RGBA_SRGB_BLOCK => {
+            decode_color(&block[..8], Some(0))
+        }
+        vk::Format::BC2_UNORM_BLOCK | vk::Format::BC2_SRGB_BLOCK => {
+            let mut texels = decode_color(&block[8..], None);
+            for (index, texel) in texels.iter_mut().enumerate() {
+                let alpha = (block[index / 2] >> (index % 2 * 4)) & 0xf;
+                texel[3] = alpha * 17;
+            }
+            texels
+        }
+        vk::Format::BC3_UNORM_BLOCK | vk::Format::BC3_SRGB_BLOCK => {
+            let alpha = decode_channel(&block[..8], false);
+            with_alpha(decode_color(&block[8..], None), alpha)
+        }
+        vk::Format::BC4_UNORM_BLOCK => red_green(decode_channel(block, false), None, 255),
+        vk::Format::BC4_SNORM_BLOCK => red_green(decode_channel(block, true), None, 127),
+        vk::Format::BC5_UNORM_BLOCK => {
+            let green = decode_channel(&block[8..], false);
+            red_green(decode_channel(&block[..8], false), Some(green), 255)
+        }
+        vk::Format::BC5_SNORM_BLOCK => {
+            let green = decode_channel(&block[8..], true);
+            red_green(decode_channel(&block[..8], true), Some(green), 127)
+        }
+        vk::Format::BC7_UNORM_BLOCK | vk::Format::BC7_SRGB_BLOCK => decode_bc7(block),
+        vk::Format::ETC2_R8G8B8_UNORM_BLOCK | vk::Format::ETC2_R8G8B8_SRGB_BLOCK => {
+            decode_etc2(block, false)
+        }
+        vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK | vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK => {
+            decode_etc2(block, true)
+        }
+        vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK | vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK => {
+            let alpha = decode_eac(&block[..8], EacPrecision::Alpha).map(|alpha| alpha as u8);
+            with_alpha(decode_etc2(&block[8..], false), alpha)
+        }
+        vk::Format::EAC_R11_UNORM_BLOCK => red_green(decode_eac_unorm(block), None, 255),
+        vk::Format::EAC_R11_SNORM_BLOCK => red_green(decode_eac_snorm(block), None, 127),
+        vk::Format::EAC_R11G11_UNORM_BLOCK => {
+            let green = decode_eac_unorm(&block[8..]);
+            red_green(decode_eac_unorm(&block[..8]), Some(green), 255)
+        }
+        vk::Format::EAC_R11G11_SNORM_BLOCK => {
+            let green = decode_eac_snorm(&block[8..]);
+            red_green(decode_eac_snorm(&block[..8]), Some(green), 127)
+        }
+        format => unreachable!("{:?} can't be decompressed", format),
+    }
+}
+
+fn with_alpha(mut texels: [[u8; 4]; 16], alpha: [u8; 16]) -> [[u8; 4]; 16] {
+    for (texel, alpha) in texels.iter_mut().zip(alpha.iter()) {
+        texel[3] = *alpha;
+    }
+    texels
+}
+
+/// Builds texels of one or two channels, with `one` (255 for UNORM, 127 for SNORM) in alpha.
+fn red_green(red: [u8; 16], green: Option<[u8; 16]>, one: u8) -> [[u8; 4]; 16] {
+    let mut texels = [[0, 0, 0, one]; 16];
+    for (index, texel) in texels.iter_mut().enumerate() {
+        texel[0] = red[index];
+        texel[1] = green.map_or(0, |green| green[index]);
+    }
+    texels
+}
+
+/// Decodes the 8 byte color half of a BC1-BC3 block.
+///
+/// Every BC1 variant switches to three colors and black when `color0 <= color1`, with black
+/// taking `three_color_alpha` (opaque for BC1 RGB, transparent for BC1 RGBA). BC2 and BC3 pass
+/// `None`, as their color blocks always use four colors.
+fn decode_color(block: &[u8], three_color_alpha: Option<u8>) -> [[u8; 4]; 16] {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let expand = |color: u16| {
+        let (r, g, b) = ((color >> 11) & 0x1f, (color >> 5) & 0x3f, color & 0x1f);
+        [
+            ((r << 3) | (r >> 2)) as u32,
+            ((g << 2) | (g >> 4)) as u32,
+            ((b << 3) | (b >> 2)) as u32,
+        ]
+    };
+    let (rgb0, rgb1) = (expand(color0), expand(color1));
+    let mix = |weight0: u32, weight1: u32, sum: u32| {
+        let channel = |index: usize| ((rgb0[index] * weight0 + rgb1[index] * weight1) / sum) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+    let palette = match three_color_alpha {
+        Some(alpha) if color0 <= color1 => {
+            [mix(1, 0, 1), mix(0, 1, 1), mix(1, 1, 2), [0, 0, 0, alpha]]
+        }
+        _ => [mix(1, 0, 1), mix(0, 1, 1), mix(2, 1, 3), mix(1, 2, 3)],
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut texels = [[0; 4]; 16];
+    for (index, texel) in texels.iter_mut().enumerate() {
+        *texel = palette[((indices >> (index * 2)) & 0x3) as usize];
+    }
+    texels
+}
+
+/// Decodes an 8 byte BC4-style block of one interpolated channel. Signed values are returned
+/// as the bytes of their `i8`s.
+fn decode_channel(block: &[u8], signed: bool) -> [u8; 16] {
+    // -128 decodes like -127, so that both ends of the signed range are equally far from zero.
+    let endpoint = |byte: u8| match signed {
+        true => (byte as i8).max(-127) as i32,
+        false => byte as i32,
+    };
+    let (value0, value1) = (endpoint(block[0]), endpoint(block[1]));
+    let mut palette = [0; 8];
+    palette[0] = value0;
+    palette[1] = value1;
+    if value0 > value1 {
+        for (index, value) in palette.iter_mut().enumerate().skip(2) {
+            let weight = index as i32 - 1;
+            *value = ((7 - weight) * value0 + weight * value1) / 7;
+        }
+    } else {
+        for (index, value) in palette.iter_mut().enumerate().take(6).skip(2) {
+            let weight = index as i32 - 1;
+            *value = ((5 - weight) * value0 + weight * value1) / 5;
+        }
+        palette[6] = if signed { -127 } else { 0 };
+        palette[7] = if signed { 127 } else { 255 };
+    }
+    let mut indices = [0; 8];
+    indices[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(indices);
+    let mut values = [0; 16];
+    for (index, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (index * 3)) & 0x7) as usize] as u8;
+    }
+    values
+}
+
+/// The fields of one BC7 mode, in bits.
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: usize,
+    rotation_bits: usize,
+    index_selection_bits: usize,
+    color_bits: usize,
+    alpha_bits: usize,
+    /// Whether each endpoint has its own p-bit, rather than each subset sharing one.
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: usize,
+    secondary_index_bits: usize,
+}
+
+/// Builds a mode from its field widths, grouped in the order they appear in the block.
+const fn bc7_mode(
+    subsets: usize,
+    partition_bits: usize,
+    (rotation_bits, index_selection_bits): (usize, usize),
+    (color_bits, alpha_bits): (usize, usize),
+    (endpoint_p_bits, shared_p_bits): (bool, bool),
+    (index_bits, secondary_index_bits): (usize, usize),
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_p_bits,
+        shared_p_bits,
+        index_bits,
+        secondary_index_bits,
+    }
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    bc7_mode(3, 4, (0, 0), (4, 0), (true, false), (3, 0)),
+    bc7_mode(2, 6, (0, 0), (6, 0), (false, true), (3, 0)),
+    bc7_mode(3, 6, (0, 0), (5, 0), (false, false), (2, 0)),
+    bc7_mode(2, 6, (0, 0), (7, 0), (true, false), (2, 0)),
+    bc7_mode(1, 0, (2, 1), (5, 6), (false, false), (2, 3)),
+    bc7_mode(1, 0, (2, 0), (7, 8), (false, false), (2, 2)),
+    bc7_mode(1, 0, (0, 0), (7, 7), (true, false), (4, 0)),
+    bc7_mode(2, 6, (0, 0), (5, 5), (true, false), (2, 0)),
+];
+
+/// The subset of each texel in the two subset partitions, one bit per texel.
+const BC7_PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80, 0xc800, 0xffec, 0xfe80, 0xe800,
+    0xffe8, 0xff00, 0xfff0, 0xf000, 0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c, 0xaaaa, 0xf0f0, 0x5a5a, 0x33cc,
+    0x3c3c, 0x55aa, 0x9696, 0xa55a, 0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c, 0x9336, 0x9cc6, 0x817e, 0xe718,
+    0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// The subset of each texel in the three subset partitions, two bits per texel.
+const BC7_PARTITIONS_3: [u32; 64] = [
+    0xaa685050, 0x6a5a5040, 0x5a5a4200, 0x5450a0a8, 0xa5a50000, 0xa0a05050, 0x5555a0a0, 0x5a5a5050,
+    0xaa550000, 0xaa555500, 0xaaaa5500, 0x90909090, 0x94949494, 0xa4a4a4a4, 0xa9a59450, 0x2a0a4250,
+    0xa5945040, 0x0a425054, 0xa5a5a500, 0x55a0a0a0, 0xa8a85454, 0x6a6a4040, 0xa4a45000, 0x1a1a0500,
+    0x0050a4a4, 0xaaa59090, 0x14696914, 0x69691400, 0xa08585a0, 0xaa821414, 0x50a4a450, 0x6a5a0200,
+    0xa9a58000, 0x5090a0a8, 0xa8a09050, 0x24242424, 0x00aa5500, 0x24924924, 0x24499224, 0x50a50a50,
+    0x500aa550, 0xaaaa4444, 0x66660000, 0xa5a0a5a0, 0x50a050a0, 0x69286928, 0x44aaaa44, 0x66666600,
+    0xaa444444, 0x54a854a8, 0x95809580, 0x96969600, 0xa85454a8, 0x80959580, 0xaa141414, 0x96960000,
+    0xaaaa1414, 0xa05050a0, 0xa0a5a5a0, 0x96000000, 0x40804080, 0xa9a8a9a8, 0xaaaaaa44, 0x2a4a5254,
+];
+
+/// The texel holding the implicit high index bit of subset 1 in the two subset partitions.
+const BC7_ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// The anchor texels of subsets 1 and 2 in the three subset partitions.
+const BC7_ANCHORS_3: [[u8; 2]; 64] = [
+    [3, 15],
+    [3, 8],
+    [15, 8],
+    [15, 3],
+    [8, 15],
+    [3, 15],
+    [15, 3],
+    [15, 8],
+    [8, 15],
+    [8, 15],
+    [6, 15],
+    [6, 15],
+    [6, 15],
+    [5, 15],
+    [3, 15],
+    [3, 8],
+    [3, 15],
+    [3, 8],
+    [8, 15],
+    [15, 3],
+    [3, 15],
+    [3, 8],
+    [6, 15],
+    [10, 8],
+    [5, 3],
+    [8, 15],
+    [8, 6],
+    [6, 10],
+    [8, 15],
+    [5, 15],
+    [15, 10],
+    [15, 8],
+    [8, 15],
+    [15, 3],
+    [3, 15],
+    [5, 10],
+    [6, 10],
+    [10, 8],
+    [8, 9],
+    [15, 10],
+    [15, 6],
+    [3, 15],
+    [15, 8],
+    [5, 15],
+    [15, 3],
+    [15, 6],
+    [15, 6],
+    [15, 8],
+    [3, 15],
+    [15, 3],
+    [5, 15],
+    [5, 15],
+    [5, 15],
+    [8, 15],
+    [5, 15],
+    [10, 15],
+    [5, 15],
+    [10, 15],
+    [8, 15],
+    [13, 15],
+    [15, 3],
+    [12, 15],
+    [3, 15],
+    [3, 8],
+];
+
+const BC7_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Reads little-endian bit fields from the front of a block.
+struct BitReader {
+    bits: u128,
+    offset: usize,
+}
+
+impl BitReader {
+    fn read(&mut self, count: usize) -> u32 {
+        let value = (self.bits >> self.offset) as u32 & ((1 << count) - 1);
+        self.offset += count;
+        value
+    }
+}
+
+/// Decodes a 16 byte BC7 block. Blocks with the reserved mode decode to transparent black.
+fn decode_bc7(block: &[u8]) -> [[u8; 4]; 16] {
+    let mode = match block[0].trailing_zeros() {
+        mode if mode < 8 => &BC7_MODES[mode as usize],
+        _ => return [[0; 4]; 16],
+    };
+    let mut bits = BitReader {
+        bits: u128::from_le_bytes(block.try_into().unwrap()),
+        offset: block[0].trailing_zeros() as usize + 1,
+    };
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[255; 4]; 6];
+    for channel in 0..4 {
+        let width = if channel < 3 {
+            mode.color_bits
+        } else {
+            mode.alpha_bits
+        };
+        if width > 0 {
+            for endpoint in &mut endpoints[..endpoint_count] {
+                endpoint[channel] = bits.read(width);
+            }
+        }
+    }
+    let mut p_bits = [None; 6];
+    if mode.endpoint_p_bits {
+        for p_bit in &mut p_bits[..endpoint_count] {
+            *p_bit = Some(bits.read(1));
+        }
+    } else if mode.shared_p_bits {
+        for subset in p_bits[..endpoint_count].chunks_exact_mut(2) {
+            let p_bit = bits.read(1);
+            subset.fill(Some(p_bit));
+        }
+    }
+    // Endpoints are extended to 8 bits by appending the p-bit and then repeating their top bits.
+    for (endpoint, p_bit) in endpoints[..endpoint_count].iter_mut().zip(p_bits) {
+        for (channel, value) in endpoint.iter_mut().enumerate() {
+            let width = if channel < 3 {
+                mode.color_bits
+            } else {
+                mode.alpha_bits
+            };
+            if width == 0 {
+                continue;
+            }
+            let (extended, width) = match p_bit {
+                Some(p_bit) => ((*value << 1) | p_bit, width + 1),
+                None => (*value, width),
+            };
+            *value = (extended << (8 - width)) | (extended >> (2 * width - 8));
+        }
+    }
+
+    let subset = |texel: usize| match mode.subsets {
+        1 => 0,
+        2 => (BC7_PARTITIONS_2[partition] >> texel) as usize & 1,
+        _ => (BC7_PARTITIONS_3[partition] >> (texel * 2)) as usize & 3,
+    };
+    // The first texel of each subset stores its index with the high bit left out as zero.
+    let is_anchor = |texel: usize| match mode.subsets {
+        1 => texel == 0,
+        2 => texel == 0 || texel == BC7_ANCHORS_2[partition] as usize,
+        _ => texel == 0 || BC7_ANCHORS_3[partition].contains(&(texel as u8)),
+    };
+    let mut indices = [0; 16];
+    for (texel, index) in indices.iter_mut().enumerate() {
+        *index = bits.read(mode.index_bits - is_anchor(texel) as usize);
+    }
+    let mut secondary_indices = [0; 16];
+    if mode.secondary_index_bits > 0 {
+        for (texel, index) in secondary_indices.iter_mut().enumerate() {
+            *index = bits.read(mode.secondary_index_bits - (texel == 0) as usize);
+        }
+    }
+
+    let interpolate = |value0: u32, value1: u32, index: u32, index_bits: usize| {
+        let weight = match index_bits {
+            2 => BC7_WEIGHTS_2[index as usize],
+            3 => BC7_WEIGHTS_3[index as usize],
+            _ => BC7_WEIGHTS_4[index as usize],
+        };
+        (((64 - weight) * value0 + weight * value1 + 32) >> 6) as u8
+    };
+    let mut texels = [[0; 4]; 16];
+    for (texel, color) in texels.iter_mut().enumerate() {
+        let (endpoint0, endpoint1) = (
+            endpoints[subset(texel) * 2],
+            endpoints[subset(texel) * 2 + 1],
+        );
+        let primary = (indices[texel], mode.index_bits);
+        let secondary = (secondary_indices[texel], mode.secondary_index_bits);
+        let ((color_index, color_bits), (alpha_index, alpha_bits)) =
+            match (mode.secondary_index_bits, index_selection) {
+                (0, _) => (primary, primary),
+                (_, 0) => (primary, secondary),
+                _ => (secondary, primary),
+            };
+        for channel in 0..3 {
+            color[channel] = interpolate(
+                endpoint0[channel],
+                endpoint1[channel],
+                color_index,
+                color_bits,
+            );
+        }
+        color[3] = interpolate(endpoint0[3], endpoint1[3], alpha_index, alpha_bits);
+        if rotation > 0 {
+            color.swap(rotation as usize - 1, 3);
+        }
+    }
+    texels
+}
+
+/// The fields of one BC6H mode, in bits.
+struct Bc6hMode {
+    /// The mode bits, two for the first two modes and five for the rest.
+    id: u32,
+    /// Whether the other endpoints are stored as deltas from the first.
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    /// Where each run of endpoint bits goes, in block order: the endpoint value (`W`, `X`, `Y`
+    /// and `Z` for each channel), the shift of its first bit and the run's length.
+    fields: &'static [(usize, u32, usize)],
+}
+
+// The endpoint values BC6H fields are read into, by endpoint and then channel.
+const RW: usize = 0;
+const GW: usize = 1;
+const BW: usize = 2;
+const RX: usize = 3;
+const GX: usize = 4;
+const BX: usize = 5;
+const RY: usize = 6;
+const GY: usize = 7;
+const BY: usize = 8;
+const RZ: usize = 9;
+const GZ: usize = 10;
+const BZ: usize = 11;
+
+/// The BC6H modes, with the ten two subset modes first.
+#[rustfmt::skip]
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode { id: 0x00, transformed: true, endpoint_bits: 10, delta_bits: [5, 5, 5], fields: &[
+        (GY, 4, 1), (BY, 4, 1), (BZ, 4, 1), (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5),
+        (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ] },
+    Bc6hMode { id: 0x01, transformed: true, endpoint_bits: 7, delta_bits: [6, 6, 6], fields: &[
+        (GY, 5, 1), (GZ, 4, 1), (GZ, 5, 1), (RW, 0, 7), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1),
+        (GW, 0, 7), (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 7), (BZ, 3, 1), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4),
+        (RY, 0, 6), (RZ, 0, 6),
+    ] },
+    Bc6hMode { id: 0x02, transformed: true, endpoint_bits: 11, delta_bits: [5, 4, 4], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5), (RW, 10, 1), (GY, 0, 4), (GX, 0, 4),
+        (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4),
+        (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ] },
+    Bc6hMode { id: 0x06, transformed: true, endpoint_bits: 11, delta_bits: [4, 5, 4], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (GZ, 4, 1), (GY, 0, 4),
+        (GX, 0, 5), (GW, 10, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4),
+        (RY, 0, 4), (BZ, 0, 1), (BZ, 2, 1), (RZ, 0, 4), (GY, 4, 1), (BZ, 3, 1),
+    ] },
+    Bc6hMode { id: 0x0a, transformed: true, endpoint_bits: 11, delta_bits: [4, 4, 5], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (BY, 4, 1), (GY, 0, 4),
+        (GX, 0, 4), (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BW, 10, 1), (BY, 0, 4),
+        (RY, 0, 4), (BZ, 1, 1), (BZ, 2, 1), (RZ, 0, 4), (BZ, 4, 1), (BZ, 3, 1),
+    ] },
+    Bc6hMode { id: 0x0e, transformed: true, endpoint_bits: 9, delta_bits: [5, 5, 5], fields: &[
+        (RW, 0, 9), (BY, 4, 1), (GW, 0, 9), (GY, 4, 1), (BW, 0, 9), (BZ, 4, 1), (RX, 0, 5),
+        (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ] },
+    Bc6hMode { id: 0x12, transformed: true, endpoint_bits: 8, delta_bits: [6, 5, 5], fields: &[
+        (RW, 0, 8), (GZ, 4, 1), (BY, 4, 1), (GW, 0, 8), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 8),
+        (BZ, 3, 1), (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4),
+        (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6),
+    ] },
+    Bc6hMode { id: 0x16, transformed: true, endpoint_bits: 8, delta_bits: [5, 6, 5], fields: &[
+        (RW, 0, 8), (BZ, 0, 1), (BY, 4, 1), (GW, 0, 8), (GY, 5, 1), (GY, 4, 1), (BW, 0, 8),
+        (GZ, 5, 1), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4),
+        (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ] },
+    Bc6hMode { id: 0x1a, transformed: true, endpoint_bits: 8, delta_bits: [5, 5, 6], fields: &[
+        (RW, 0, 8), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 8), (BY, 5, 1), (GY, 4, 1), (BW, 0, 8),
+        (BZ, 5, 1), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1),
+        (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ] },
+    Bc6hMode { id: 0x1e, transformed: false, endpoint_bits: 6, delta_bits: [6, 6, 6], fields: &[
+        (RW, 0, 6), (GZ, 4, 1), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 6), (GY, 5, 1),
+        (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 6), (GZ, 5, 1), (BZ, 3, 1), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4),
+        (RY, 0, 6), (RZ, 0, 6),
+    ] },
+    Bc6hMode { id: 0x03, transformed: false, endpoint_bits: 10, delta_bits: [10, 10, 10], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 10), (GX, 0, 10), (BX, 0, 10),
+    ] },
+    Bc6hMode { id: 0x07, transformed: true, endpoint_bits: 11, delta_bits: [9, 9, 9], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 9), (RW, 10, 1), (GX, 0, 9), (GW, 10, 1),
+        (BX, 0, 9), (BW, 10, 1),
+    ] },
+    // The last two modes store the high bits of the first endpoint reversed.
+    Bc6hMode { id: 0x0b, transformed: true, endpoint_bits: 12, delta_bits: [8, 8, 8], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 8), (RW, 11, 1), (RW, 10, 1), (GX, 0, 8),
+        (GW, 11, 1), (GW, 10, 1), (BX, 0, 8), (BW, 11, 1), (BW, 10, 1),
+    ] },
+    Bc6hMode { id: 0x0f, transformed: true, endpoint_bits: 16, delta_bits: [4, 4, 4], fields: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 15, 1), (RW, 14, 1), (RW, 13, 1),
+        (RW, 12, 1), (RW, 11, 1), (RW, 10, 1), (GX, 0, 4), (GW, 15, 1), (GW, 14, 1), (GW, 13, 1),
+        (GW, 12, 1), (GW, 11, 1), (GW, 10, 1), (BX, 0, 4), (BW, 15, 1), (BW, 14, 1), (BW, 13, 1),
+        (BW, 12, 1), (BW, 11, 1), (BW, 10, 1),
+    ] },
+];
+
+/// Sign extends the low `bits` bits of `value`.
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    (value << (32 - bits)) >> (32 - bits)
+}
+
+/// Decodes a 16 byte BC6H block to 16 half float texels in row order, with an alpha of one.
+/// Blocks with a reserved mode decode to black.
+fn decode_bc6h(block: &[u8], signed: bool) -> [[u16; 4]; 16] {
+    const ONE: u16 = 0x3c00;
+    let mut bits = BitReader {
+        bits: u128::from_le_bytes(block.try_into().unwrap()),
+        offset: 0,
+    };
+    let id = match bits.read(2) {
+        id if id < 2 => id,
+        id => id | (bits.read(3) << 2),
+    };
+    let mode = match BC6H_MODES.iter().find(|mode| mode.id == id) {
+        Some(mode) => mode,
+        None => return [[0, 0, 0, ONE]; 16],
+    };
+    let mut values = [0; 12];
+    for &(value, shift, count) in mode.fields {
+        values[value] |= (bits.read(count) << shift) as i32;
+    }
+    // Only the two subset modes store a second pair of endpoints.
+    let subsets = if mode.fields.iter().any(|&(value, ..)| value == RY) {
+        2
+    } else {
+        1
+    };
+    let partition = if subsets == 2 { bits.read(5) } else { 0 } as usize;
+
+    // Endpoints are unquantized to 16 bits, or to 15 bits and a sign when signed.
+    let max = if signed {
+        (1 << (mode.endpoint_bits - 1)) - 1
+    } else {
+        (1 << mode.endpoint_bits) - 1
+    };
+    let unquantize = |value: i32| match (signed, mode.endpoint_bits) {
+        (false, 15..) => value,
+        (false, _) if value == 0 => 0,
+        (false, _) if value == max => 0xffff,
+        (false, bits) => ((value << 16) + 0x8000) >> bits,
+        (true, 16) => value,
+        (true, _) if value == 0 => 0,
+        (true, _) if value.abs() >= max => value.signum() * 0x7fff,
+        (true, bits) => value.signum() * (((value.abs() << 15) + 0x4000) >> (bits - 1)),
+    };
+    let mut endpoints = [[0; 3]; 4];
+    for channel in 0..3 {
+        let mut first = values[channel];
+        if signed {
+            first = sign_extend(first, mode.endpoint_bits);
+        }
+        endpoints[0][channel] = unquantize(first);
+        for endpoint in 1..subsets * 2 {
+            let mut value = values[endpoint * 3 + channel];
+            if mode.transformed || signed {
+                value = sign_extend(value, mode.delta_bits[channel]);
+            }
+            if mode.transformed {
+                value = (first + value) & ((1 << mode.endpoint_bits) - 1);
+                if signed {
+                    value = sign_extend(value, mode.endpoint_bits);
+                }
+            }
+            endpoints[endpoint][channel] = unquantize(value);
+        }
+    }
+
+    let subset = |texel: usize| match subsets {
+        1 => 0,
+        _ => (BC7_PARTITIONS_2[partition] >> texel) as usize & 1,
+    };
+    let is_anchor = |texel: usize| match subsets {
+        1 => texel == 0,
+        _ => texel == 0 || texel == BC7_ANCHORS_2[partition] as usize,
+    };
+    let index_bits = if subsets == 2 { 3 } else { 4 };
+    let mut texels = [[0, 0, 0, ONE]; 16];
+    for (texel, color) in texels.iter_mut().enumerate() {
+        let index = bits.read(index_bits - is_anchor(texel) as usize) as usize;
+        let weight = match index_bits {
+            3 => BC7_WEIGHTS_3[index],
+            _ => BC7_WEIGHTS_4[index],
+        } as i32;
+        let (endpoint0, endpoint1) = (
+            endpoints[subset(texel) * 2],
+            endpoints[subset(texel) * 2 + 1],
+        );
+        for channel in 0..3 {
+            let value =
+                (endpoint0[channel] * (64 - weight) + endpoint1[channel] * weight + 32) >> 6;
+            // Scale to the largest finite half, keeping the sign separate when signed.
+            color[channel] = match signed {
+                false => ((value * 31) >> 6) as u16,
+                true if value < 0 => 0x8000 | (((-value * 31) >> 5) as u16),
+                true => ((value * 31) >> 5) as u16,
+            };
+        }
+    }
+    texels
+}
+
+/// The luminance modifiers of ETC1 and ETC2 sub-blocks, before their sign.
+const ETC_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// The distances between the paint colors of ETC2 T and H blocks.
+const ETC_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+/// Decodes an 8 byte ETC2 RGB block. Punch-through alpha blocks that clear the opaque bit turn
+/// index 2 into transparent black.
+fn decode_etc2(block: &[u8], punch_through: bool) -> [[u8; 4]; 16] {
+    let bits = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let field = |offset: u32, count: u32| ((bits >> offset) & ((1 << count) - 1)) as i32;
+    // Punch-through blocks reuse the individual mode bit as the opaque bit.
+    let differential = punch_through || field(33, 1) == 1;
+    let opaque = !punch_through || field(33, 1) == 1;
+    // Texels are stored in column order, with the high bit of every index in the upper half.
+    let index = |texel: usize| {
+        let bit = (texel % 4 * 4 + texel / 4) as u32;
+        ((field(bit + 16, 1) << 1) | field(bit, 1)) as usize
+    };
+    let clamp = |value: i32| value.clamp(0, 255) as u8;
+    let extend4 = |value: i32| value * 17;
+    let extend5 = |value: i32| (value << 3) | (value >> 2);
+    let signed3 = |value: i32| if value >= 4 { value - 8 } else { value };
+
+    let (red, green, blue) = (field(59, 5), field(51, 5), field(43, 5));
+    let (red2, green2, blue2) = (
+        red + signed3(field(56, 3)),
+        green + signed3(field(48, 3)),
+        blue + signed3(field(40, 3)),
+    );
+    let base_colors = if !differential {
+        let color = |offset: u32| {
+            [
+                extend4(field(offset + 16, 4)),
+                extend4(field(offset + 8, 4)),
+                extend4(field(offset, 4)),
+            ]
+        };
+        [color(44), color(40)]
+    } else if !(0..32).contains(&red2) {
+        // T mode: one color, and three more around a second one.
+        let color0 = [
+            extend4((field(59, 2) << 2) | field(56, 2)),
+            extend4(field(52, 4)),
+            extend4(field(48, 4)),
+        ];
+        let color1 = [
+            extend4(field(44, 4)),
+            extend4(field(40, 4)),
+            extend4(field(36, 4)),
+        ];
+        let distance = ETC_DISTANCES[((field(34, 2) << 1) | field(32, 1)) as usize];
+        let shift = |color: [i32; 3], offset: i32| color.map(|channel| channel + offset);
+        return paint_etc(
+            [
+                color0,
+                shift(color1, distance),
+                color1,
+                shift(color1, -distance),
+            ],
+            index,
+            opaque,
+        );
+    } else if !(0..32).contains(&green2) {
+        // H mode: two pairs of colors around two base colors.
+        let color0 = [
+            field(59, 4),
+            (field(56, 3) << 1) | field(52, 1),
+            (field(51, 1) << 3) | field(47, 3),
+        ];
+        let color1 = [
+            field(43, 4),
+            (field(40, 3) << 1) | field(39, 1),
+            field(35, 4),
+        ];
+        let value = |color: [i32; 3]| (color[0] << 8) | (color[1] << 4) | color[2];
+        let distance_index =
+            (field(34, 1) << 2) | (field(32, 1) << 1) | (value(color0) >= value(color1)) as i32;
+        let distance = ETC_DISTANCES[distance_index as usize];
+        let shift = |color: [i32; 3], offset: i32| color.map(|channel| extend4(channel) + offset);
+        return paint_etc(
+            [
+                shift(color0, distance),
+                shift(color0, -distance),
+                shift(color1, distance),
+                shift(color1, -distance),
+            ],
+            index,
+            opaque,
+        );
+    } else if !(0..32).contains(&blue2) {
+        // Planar mode: colors interpolated from the origin, horizontal and vertical corners.
+        let extend6 = |value: i32| (value << 2) | (value >> 4);
+        let extend7 = |value: i32| (value << 1) | (value >> 6);
+        let origin = [
+            extend6(field(57, 6)),
+            extend7((field(56, 1) << 6) | field(49, 6)),
+            extend6((field(48, 1) << 5) | (field(43, 2) << 3) | field(39, 3)),
+        ];
+        let horizontal = [
+            extend6((field(34, 5) << 1) | field(32, 1)),
+            extend7(field(25, 7)),
+            extend6(field(19, 6)),
+        ];
+        let vertical = [
+            extend6(field(13, 6)),
+            extend7(field(6, 7)),
+            extend6(field(0, 6)),
+        ];
+        let mut texels = [[0; 4]; 16];
+        for (texel, color) in texels.iter_mut().enumerate() {
+            let (x, y) = ((texel % 4) as i32, (texel / 4) as i32);
+            for channel in 0..3 {
+                color[channel] = clamp(
+                    (x * (horizontal[channel] - origin[channel])
+                        + y * (vertical[channel] - origin[channel])
+                        + 4 * origin[channel]
+                        + 2)
+                        >> 2,
+                );
+            }
+            color[3] = 255;
+        }
+        return texels;
+    } else {
+        [
+            [extend5(red), extend5(green), extend5(blue)],
+            [extend5(red2), extend5(green2), extend5(blue2)],
+        ]
+    };
+
+    // Individual and differential modes split the block into two halves with a color and a
+    // modifier table each, side by side or stacked when the flip bit is set.
+    let tables = [field(37, 3) as usize, field(34, 3) as usize];
+    let flip = field(32, 1) == 1;
+    let mut texels = [[0; 4]; 16];
+    for (texel, color) in texels.iter_mut().enumerate() {
+        let half = if flip { texel / 4 >= 2 } else { texel % 4 >= 2 } as usize;
+        let [small, large] = ETC_MODIFIERS[tables[half]];
+        let modifier = match (index(texel), opaque) {
+            (0, false) => 0,
+            (0, true) => small,
+            (1, _) => large,
+            (2, false) => {
+                *color = [0; 4];
+                continue;
+            }
+            (2, true) => -small,
+            _ => -large,
+        };
+        let base = base_colors[half];
+        *color = [
+            clamp(base[0] + modifier),
+            clamp(base[1] + modifier),
+            clamp(base[2] + modifier),
+            255,
+        ];
+    }
+    texels
+}
+
+/// Colors the texels of an ETC2 T or H block from its four paint colors.
+fn paint_etc(paint: [[i32; 3]; 4], index: impl Fn(usize) -> usize, opaque: bool) -> [[u8; 4]; 16] {
+    let mut texels = [[0; 4]; 16];
+    for (texel, color) in texels.iter_mut().enumerate() {
+        let index = index(texel);
+        if !opaque && index == 2 {
+            continue;
+        }
+        let [red, green, blue] = paint[index].map(|channel| channel.clamp(0, 255) as u8);
+        *color = [red, green, blue, 255];
+    }
+    texels
+}
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EacPrecision {
+    /// 8-bit ETC2 alpha.
+    Alpha,
+    /// 11-bit values in 0..=2047.
+    Unsigned,
+    /// 11-bit values in -1023..=1023.
+    Signed,
+}
+
+/// Decodes an 8 byte EAC block.
+fn decode_eac(block: &[u8], precision: EacPrecision) -> [i32; 16] {
+    let bits = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let base = match precision {
+        EacPrecision::Signed => (block[0] as i8).max(-127) as i32 * 8,
+        EacPrecision::Unsigned => block[0] as i32 * 8 + 4,
+        EacPrecision::Alpha => block[0] as i32,
+    };
+    let multiplier = (block[1] >> 4) as i32;
+    let multiplier = match precision {
+        EacPrecision::Alpha => multiplier,
+        _ if multiplier == 0 => 1,
+        _ => multiplier * 8,
+    };
+    let (min, max) = match precision {
+        EacPrecision::Alpha => (0, 255),
+        EacPrecision::Unsigned => (0, 2047),
+        EacPrecision::Signed => (-1023, 1023),
+    };
+    let modifiers = EAC_MODIFIERS[(block[1] & 0xf) as usize];
+    let mut values = [0; 16];
+    for (texel, value) in values.iter_mut().enumerate() {
+        // Texels are stored in column order from the most significant bits down.
+        let position = (texel % 4 * 4 + texel / 4) as u32;
+        let modifier = modifiers[((bits >> (45 - 3 * position)) & 0x7) as usize];
+        *value = (base + modifier * multiplier).clamp(min, max);
+    }
+    values
+}
+
+fn decode_eac_unorm(block: &[u8]) -> [u8; 16] {
+    decode_eac(block, EacPrecision::Unsigned).map(|value| ((value * 255 + 1023) / 2047) as u8)
+}
+
+/// Decodes a signed EAC block to the bytes of SNORM8 values.
+fn decode_eac_snorm(block: &[u8]) -> [u8; 16] {
+    decode_eac(block, EacPrecision::Signed).map(|value| {
+        let rounding = if value < 0 { -511 } else { 511 };
+        ((value * 127 + rounding) / 1023) as i8 as u8
+    })
+}
+
+/// The color ASTC blocks that can't be decoded come out as.
+const ASTC_ERROR_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// The ranges integer sequences can encode, from 2 to 256 levels, as a multiplier of one (plain
+/// bits), three (a trit) or five (a quint) and the bits below it.
+const ASTC_RANGES: [(u32, usize); 21] = [
+    (1, 1),
+    (3, 0),
+    (1, 2),
+    (5, 0),
+    (3, 1),
+    (1, 3),
+    (5, 1),
+    (3, 2),
+    (1, 4),
+    (5, 2),
+    (3, 3),
+    (1, 5),
+    (5, 3),
+    (3, 4),
+    (1, 6),
+    (5, 4),
+    (3, 5),
+    (1, 7),
+    (5, 5),
+    (3, 6),
+    (1, 8),
+];
+
+/// The layout of an ASTC block's weights.
+struct AstcBlockMode {
+    grid_width: usize,
+    grid_height: usize,
+    dual_plane: bool,
+    /// The index of the weights' range in `ASTC_RANGES`.
+    weight_range: usize,
+}
+
+/// Decodes the 11 bit block mode, or `None` for the reserved encodings.
+fn astc_block_mode(mode: u32) -> Option<AstcBlockMode> {
+    let bits = |shift: u32, count: u32| ((mode >> shift) & ((1 << count) - 1)) as usize;
+    let (a, b) = (bits(5, 2), bits(7, 2));
+    let (mut high_precision, mut dual_plane) = (bits(9, 1) == 1, bits(10, 1) == 1);
+    let (range, grid_width, grid_height) = if bits(0, 2) != 0 {
+        let (grid_width, grid_height) = match bits(2, 2) {
+            0 => (b + 4, a + 2),
+            1 => (b + 8, a + 2),
+            2 => (a + 2, b + 8),
+            _ if bits(8, 1) == 0 => (a + 2, (b & 1) + 6),
+            _ => ((b & 1) + 2, a + 2),
+        };
+        ((bits(0, 2) << 1) | bits(4, 1), grid_width, grid_height)
+    } else {
+        let (grid_width, grid_height) = match bits(7, 2) {
+            0 => (12, a + 2),
+            1 => (a + 2, 12),
+            2 => {
+                high_precision = false;
+                dual_plane = false;
+                (a + 6, bits(9, 2) + 6)
+            }
+            _ => match a {
+                0 => (6, 10),
+                1 => (10, 6),
+                _ => return None,
+            },
+        };
+        ((bits(2, 2) << 1) | bits(4, 1), grid_width, grid_height)
+    };
+    if range < 2 {
+        return None;
+    }
+    Some(AstcBlockMode {
+        grid_width,
+        grid_height,
+        dual_plane,
+        weight_range: range - 2 + 6 * high_precision as usize,
+    })
+}
+
+/// The number of bits `count` values take in an integer sequence of `range`.
+fn astc_sequence_bits(count: usize, (multiplier, bits): (u32, usize)) -> usize {
+    count * bits
+        + match multiplier {
+            3 => (count * 8).div_ceil(5),
+            5 => (count * 7).div_ceil(3),
+            _ => 0,
+        }
+}
+
+/// Reads `count` values of an integer sequence, each as its trit or quint and its low bits.
+fn decode_integer_sequence(
+    reader: &mut BitReader,
+    count: usize,
+    (multiplier, bits): (u32, usize),
+) -> Vec<(u32, u32)> {
+    // Trits are packed five to 8 bits and quints three to 7 bits, interleaved with the values'
+    // low bits in runs of these lengths. A truncated last group leaves its missing bits zero.
+    let packing: &[usize] = match multiplier {
+        3 => &[2, 2, 1, 2, 1],
+        5 => &[3, 2, 2],
+        _ => &[0],
+    };
+    let mut values = Vec::with_capacity(count);
+    while values.len() < count {
+        let group = packing.len().min(count - values.len());
+        let (mut low, mut packed, mut shift) = ([0; 5], 0, 0);
+        for (low, &packed_bits) in low.iter_mut().zip(packing).take(group) {
+            *low = reader.read(bits);
+            packed |= reader.read(packed_bits) << shift;
+            shift += packed_bits;
+        }
+        let digits = match multiplier {
+            3 => decode_trits(packed),
+            5 => decode_quints(packed),
+            _ => [0; 5],
+        };
+        values.extend(digits.iter().copied().zip(low.iter().copied()).take(group));
+    }
+    values
+}
+
+/// Unpacks five trits from the 8 bits they are packed in.
+fn decode_trits(packed: u32) -> [u32; 5] {
+    let bits = |value: u32, shift: u32, count: u32| (value >> shift) & ((1 << count) - 1);
+    let (c, t4, t3) = if bits(packed, 2, 3) == 7 {
+        ((bits(packed, 5, 3) << 2) | bits(packed, 0, 2), 2, 2)
+    } else if bits(packed, 5, 2) == 3 {
+        (bits(packed, 0, 5), 2, bits(packed, 7, 1))
+    } else {
+        (bits(packed, 0, 5), bits(packed, 7, 1), bits(packed, 5, 2))
+    };
+    let (t2, t1, t0) = if bits(c, 0, 2) == 3 {
+        let high = bits(c, 3, 1);
+        (2, bits(c, 4, 1), (high << 1) | (bits(c, 2, 1) & !high))
+    } else if bits(c, 2, 2) == 3 {
+        (2, 2, bits(c, 0, 2))
+    } else {
+        let high = bits(c, 1, 1);
+        (
+            bits(c, 4, 1),
+            bits(c, 2, 2),
+            (high << 1) | (bits(c, 0, 1) & !high),
+        )
+    };
+    [t0, t1, t2, t3, t4]
+}
+
+/// Unpacks three quints from the 7 bits they are packed in.
+fn decode_quints(packed: u32) -> [u32; 5] {
+    let bits = |value: u32, shift: u32, count: u32| (value >> shift) & ((1 << count) - 1);
+    if bits(packed, 1, 2) == 3 && bits(packed, 5, 2) == 0 {
+        let low = bits(packed, 0, 1);
+        let q2 = (low << 2) | ((bits(packed, 4, 1) & !low) << 1) | (bits(packed, 3, 1) & !low);
+        return [4, 4, q2, 0, 0];
+    }
+    let (q2, c) = if bits(packed, 1, 2) == 3 {
+        let c = (bits(packed, 3, 2) << 3) | ((!bits(packed, 5, 2) & 3) << 1) | bits(packed, 0, 1);
+        (4, c)
+    } else {
+        (bits(packed, 5, 2), bits(packed, 0, 5))
+    };
+    let (q1, q0) = if bits(c, 0, 3) == 5 {
+        (4, bits(c, 3, 2))
+    } else {
+        (bits(c, 3, 2), bits(c, 0, 3))
+    };
+    [q0, q1, q2, 0, 0]
+}
+
+/// Repeats the `bits` low bits of `value` to fill `width` bits.
+fn replicate(value: u32, bits: usize, width: usize) -> u32 {
+    let (mut result, mut filled) = (0, 0);
+    while filled < width {
+        result = (result << bits) | value;
+        filled += bits;
+    }
+    result >> (filled - width)
+}
+
+/// Unquantizes a color endpoint value to 0..=255.
+fn unquantize_astc_color((digit, low): (u32, u32), (multiplier, bits): (u32, usize)) -> u32 {
+    if multiplier == 1 {
+        return replicate(low, bits, 8);
+    }
+    let bit = |index: usize| (low >> index) & 1;
+    let (b, c) = match (multiplier, bits) {
+        (3, 1) => (0, 204),
+        (5, 1) => (0, 113),
+        (3, 2) => (bit(1) * 0x116, 93),
+        (5, 2) => (bit(1) * 0x10c, 54),
+        (3, 3) => (bit(2) * 0x10a + bit(1) * 0x85, 44),
+        (5, 3) => (bit(2) * 0x105 + bit(1) * 0x82, 26),
+        (3, 4) => (bit(3) * 0x104 + bit(2) * 0x82 + bit(1) * 0x41, 22),
+        (5, 4) => (bit(3) * 0x102 + bit(2) * 0x81 + bit(1) * 0x40, 13),
+        (3, 5) => (
+            bit(4) * 0x102 + bit(3) * 0x81 + bit(2) * 0x40 + bit(1) * 0x20,
+            11,
+        ),
+        (5, 5) => (
+            bit(4) * 0x101 + bit(3) * 0x80 + bit(2) * 0x40 + bit(1) * 0x20,
+            6,
+        ),
+        _ => (
+            bit(5) * 0x101 + bit(4) * 0x80 + bit(3) * 0x40 + bit(2) * 0x20 + bit(1) * 0x10,
+            5,
+        ),
+    };
+    let a = bit(0) * 0x1ff;
+    let value = (digit * c + b) ^ a;
+    (a & 0x80) | (value >> 2)
+}
+
+/// Unquantizes a weight to 0..=64.
+fn unquantize_astc_weight((digit, low): (u32, u32), (multiplier, bits): (u32, usize)) -> u32 {
+    let bit = |index: usize| (low >> index) & 1;
+    let value = match (multiplier, bits) {
+        (1, _) => replicate(low, bits, 6),
+        (3, 0) => [0, 32, 63][digit as usize],
+        (5, 0) => [0, 16, 32, 47, 63][digit as usize],
+        _ => {
+            let (b, c) = match (multiplier, bits) {
+                (3, 1) => (0, 50),
+                (5, 1) => (0, 28),
+                (3, 2) => (bit(1) * 0x45, 23),
+                (5, 2) => (bit(1) * 0x42, 13),
+                _ => (bit(2) * 0x42 + bit(1) * 0x21, 11),
+            };
+            let a = bit(0) * 0x7f;
+            let value = (digit * c + b) ^ a;
+            (a & 0x20) | (value >> 2)
+        }
+    };
+    value + (value > 32) as u32
+}
+
+/// Decodes the two endpoints of color endpoint mode `mode` from its unquantized values, or
+/// `None` for the HDR modes.
+fn astc_endpoints(mode: u32, values: &[u32]) -> Option<[[u8; 4]; 2]> {
+    let mut v = [0; 8];
+    for (v, &value) in v.iter_mut().zip(values) {
+        *v = value as i32;
+    }
+    // Moves the top bit of `a` to `b`, leaving `a` as a signed 6 bit offset from `b`.
+    let bit_transfer_signed = |a: i32, b: i32| {
+        let b = (b >> 1) | (a & 0x80);
+        let a = (a >> 1) & 0x3f;
+        (if a & 0x20 != 0 { a - 0x40 } else { a }, b)
+    };
+    let blue_contract = |[r, g, b, a]: [i32; 4]| [(r + b) >> 1, (g + b) >> 1, b, a];
+    let (endpoint0, endpoint1) = match mode {
+        0 => ([v[0], v[0], v[0], 255], [v[1], v[1], v[1], 255]),
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xc0);
+            let l1 = (l0 + (v[1] & 0x3f)).min(255);
+            ([l0, l0, l0, 255], [l1, l1, l1, 255])
+        }
+        4 => ([v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]),
+        5 => {
+            let (v1, v0) = bit_transfer_signed(v[1], v[0]);
+            let (v3, v2) = bit_transfer_signed(v[3], v[2]);
+            let l1 = v0 + v1;
+            ([v0, v0, v0, v2], [l1, l1, l1, v2 + v3])
+        }
+        6 | 10 => {
+            let alpha = if mode == 10 { (v[4], v[5]) } else { (255, 255) };
+            let scaled = [0, 1, 2].map(|channel| (v[channel] * v[3]) >> 8);
+            (
+                [scaled[0], scaled[1], scaled[2], alpha.0],
+                [v[0], v[1], v[2], alpha.1],
+            )
+        }
+        8 | 12 => {
+            let alpha = if mode == 12 { (v[6], v[7]) } else { (255, 255) };
+            let low = [v[0], v[2], v[4], alpha.0];
+            let high = [v[1], v[3], v[5], alpha.1];
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                (low, high)
+            } else {
+                (blue_contract(high), blue_contract(low))
+            }
+        }
+        9 | 13 => {
+            let (v1, v0) = bit_transfer_signed(v[1], v[0]);
+            let (v3, v2) = bit_transfer_signed(v[3], v[2]);
+            let (v5, v4) = bit_transfer_signed(v[5], v[4]);
+            let alpha = if mode == 13 {
+                let (v7, v6) = bit_transfer_signed(v[7], v[6]);
+                (v6, v6 + v7)
+            } else {
+                (255, 255)
+            };
+            let base = [v0, v2, v4, alpha.0];
+            let offset = [v0 + v1, v2 + v3, v4 + v5, alpha.1];
+            if v1 + v3 + v5 >= 0 {
+                (base, offset)
+            } else {
+                (blue_contract(offset), blue_contract(base))
+            }
+        }
+        _ => return None,
+    };
+    let clamp = |endpoint: [i32; 4]| endpoint.map(|channel| channel.clamp(0, 255) as u8);
+    Some([clamp(endpoint0), clamp(endpoint1)])
+}
+
+/// Picks the partition of the texel at `x`, `y` with the ASTC partition hash.
+fn astc_partition(seed: u32, x: u32, y: u32, partitions: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block {
+        (x << 1, y << 1)
+    } else {
+        (x, y)
+    };
+    let seed = seed + (partitions - 1) * 1024;
+    let mut random = seed;
+    random ^= random >> 15;
+    random = random.wrapping_sub(random << 17);
+    random = random.wrapping_add(random << 7);
+    random = random.wrapping_add(random << 4);
+    random ^= random >> 5;
+    random = random.wrapping_add(random << 16);
+    random ^= random >> 7;
+    random ^= random >> 3;
+    random ^= random << 6;
+    random ^= random >> 17;
+
+    let mut seeds = [0, 4, 8, 12, 16, 20, 24, 28, 18, 22, 26]
+        .map(|shift| ((random >> shift) & 0xf).pow(2))
+        .to_vec();
+    seeds.push((random.rotate_left(2) & 0xf).pow(2));
+    let (shift1, shift2) = match (seed & 1 == 1, seed & 2 == 2) {
+        (true, quarter) => (
+            if quarter { 4 } else { 5 },
+            if partitions == 3 { 6 } else { 5 },
+        ),
+        (false, quarter) => (
+            if partitions == 3 { 6 } else { 5 },
+            if quarter { 4 } else { 5 },
+        ),
+    };
+    let shift3 = if seed & 0x10 != 0 { shift1 } else { shift2 };
+    for (index, seed) in seeds.iter_mut().enumerate() {
+        *seed >>= match index {
+            8.. => shift3,
+            _ if index % 2 == 0 => shift1,
+            _ => shift2,
+        };
+    }
+    // The z terms of 3D blocks drop out, as 2D textures are always at z = 0.
+    let mut sums = [
+        seeds[0] * x + seeds[1] * y + (random >> 14),
+        seeds[2] * x + seeds[3] * y + (random >> 10),
+        seeds[4] * x + seeds[5] * y + (random >> 6),
+        seeds[6] * x + seeds[7] * y + (random >> 2),
+    ]
+    .map(|sum| sum & 0x3f);
+    for sum in &mut sums[partitions as usize..] {
+        *sum = 0;
+    }
+    // Ties go to the lowest partition.
+    let max = *sums.iter().max().unwrap();
+    sums.iter().position(|&sum| sum == max).unwrap()
+}
+
+/// Decodes a 16 byte ASTC block with a `width` x `height` footprint to 8-bit texels in row
+/// order. HDR blocks and blocks with reserved or invalid encodings decode to magenta.
+fn decode_astc(block: &[u8], width: usize, height: usize, srgb: bool) -> Vec<[u8; 4]> {
+    decode_astc_ldr(block, width, height, srgb)
+        .unwrap_or_else(|| vec![ASTC_ERROR_COLOR; width * height])
+}
+
+fn decode_astc_ldr(block: &[u8], width: usize, height: usize, srgb: bool) -> Option<Vec<[u8; 4]>> {
+    let bits = u128::from_le_bytes(block.try_into().unwrap());
+    let mut reader = BitReader { bits, offset: 0 };
+    let mode = reader.read(11);
+    // Void extent blocks hold one 16-bit color for the whole block.
+    if mode & 0x1ff == 0x1fc {
+        if mode & 0x200 != 0 {
+            return None;
+        }
+        let color = [0, 1, 2, 3].map(|channel| (bits >> (64 + channel * 16 + 8)) as u8);
+        return Some(vec![color; width * height]);
+    }
+    let mode = astc_block_mode(mode)?;
+    let weight_range = ASTC_RANGES[mode.weight_range];
+    let planes = 1 + mode.dual_plane as usize;
+    let weight_count = mode.grid_width * mode.grid_height * planes;
+    let weight_bits = astc_sequence_bits(weight_count, weight_range);
+    if mode.grid_width > width
+        || mode.grid_height > height
+        || weight_count > 64
+        || !(24..=96).contains(&weight_bits)
+    {
+        return None;
+    }
+
+    let partitions = reader.read(2) + 1;
+    if partitions == 4 && mode.dual_plane {
+        return None;
+    }
+    // Everything that doesn't fit before the endpoints is stored just below the weights.
+    let mut below_weights = 128 - weight_bits;
+    let (seed, endpoint_modes) = if partitions == 1 {
+        (0, [reader.read(4); 4])
+    } else {
+        let seed = reader.read(10);
+        let low = reader.read(6);
+        let mut endpoint_modes = [low >> 2; 4];
+        if low & 3 != 0 {
+            let extra_bits = 3 * partitions as usize - 4;
+            below_weights -= extra_bits;
+            let high = (bits >> below_weights) as u32 & ((1 << extra_bits) - 1);
+            let encoded = (high << 6) | low;
+            for (partition, endpoint_mode) in endpoint_modes
+                .iter_mut()
+                .take(partitions as usize)
+                .enumerate()
+            {
+                let class = (encoded & 3) - 1 + ((encoded >> (2 + partition)) & 1);
+                let offset = 2 + partitions as usize + partition * 2;
+                *endpoint_mode = (class << 2) | ((encoded >> offset) & 3);
+            }
+        }
+        (seed, endpoint_modes)
+    };
+    let endpoint_modes = &endpoint_modes[..partitions as usize];
+    let plane2_channel = if mode.dual_plane {
+        below_weights -= 2;
+        Some((bits >> below_weights) as usize & 3)
+    } else {
+        None
+    };
+
+    // Endpoints take the finest range that fits the bits left over.
+    let value_count = endpoint_modes
+        .iter()
+        .map(|endpoint_mode| ((endpoint_mode >> 2) as usize + 1) * 2)
+        .sum();
+    let color_bits = below_weights.checked_sub(reader.offset)?;
+    let color_range = ASTC_RANGES
+        .iter()
+        .rposition(|&range| astc_sequence_bits(value_count, range) <= color_bits)?;
+    if value_count > 18 || color_range < 4 {
+        return None;
+    }
+    let values = decode_integer_sequence(&mut reader, value_count, ASTC_RANGES[color_range])
+        .into_iter()
+        .map(|value| unquantize_astc_color(value, ASTC_RANGES[color_range]))
+        .collect::<Vec<_>>();
+    let mut endpoints = Vec::with_capacity(endpoint_modes.len());
+    let mut values = values.as_slice();
+    for &endpoint_mode in endpoint_modes {
+        let (used, rest) = values.split_at(((endpoint_mode >> 2) as usize + 1) * 2);
+        endpoints.push(astc_endpoints(endpoint_mode, used)?);
+        values = rest;
+    }
+
+    // Weights are stored backwards from the end of the block.
+    let mut weight_reader = BitReader {
+        bits: bits.reverse_bits(),
+        offset: 0,
+    };
+    let weights = decode_integer_sequence(&mut weight_reader, weight_count, weight_range)
+        .into_iter()
+        .map(|weight| unquantize_astc_weight(weight, weight_range))
+        .collect::<Vec<_>>();
+    // Infills a texel's weight from the four nearest grid weights of `plane`.
+    let texel_weight = |x: usize, y: usize, plane: usize| {
+        let scale = |position: usize, size: usize, grid_size: usize| {
+            let scaled = (1024 + size / 2) / (size - 1) * position;
+            let grid = (scaled * (grid_size - 1) + 32) >> 6;
+            (grid >> 4, grid & 0xf)
+        };
+        let (grid_x, fraction_x) = scale(x, width, mode.grid_width);
+        let (grid_y, fraction_y) = scale(y, height, mode.grid_height);
+        let weight11 = (fraction_x * fraction_y + 8) >> 4;
+        let contributions = [
+            (0, 0, 16 - fraction_x - fraction_y + weight11),
+            (1, 0, fraction_x - weight11),
+            (0, 1, fraction_y - weight11),
+            (1, 1, weight11),
+        ];
+        let sum: usize = contributions
+            .iter()
+            .filter(|&&(.., factor)| factor > 0)
+            .map(|&(dx, dy, factor)| {
+                let index = (grid_y + dy) * mode.grid_width + grid_x + dx;
+                weights[index * planes + plane] as usize * factor
+            })
+            .sum();
+        ((sum + 8) >> 4) as u32
+    };
+
+    let mut texels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let partition = match partitions {
+                1 => 0,
+                _ => astc_partition(seed, x as u32, y as u32, partitions, width * height < 31),
+            };
+            let [endpoint0, endpoint1] = endpoints[partition];
+            let weights = [texel_weight(x, y, 0), texel_weight(x, y, planes - 1)];
+            let mut color = [0; 4];
+            for (channel, value) in color.iter_mut().enumerate() {
+                let weight = weights[(plane2_channel == Some(channel)) as usize];
+                // Endpoints are expanded to 16 bits, and sRGB keeps only the top byte exact.
+                let expand = |value: u8| match srgb {
+                    true => ((value as u32) << 8) | 0x80,
+                    false => value as u32 * 257,
+                };
+                let (value0, value1) = (expand(endpoint0[channel]), expand(endpoint1[channel]));
+                *value = (((value0 * (64 - weight) + value1 * weight + 32) >> 6) >> 8) as u8;
+            }
+            texels.push(color);
+        }
+    }
+    Some(texels)
+}
@@ -0,0 +1,19 @@
+use super::PhysicalDeviceOverride;
+
+#[derive(Default)]
+pub struct RendererConfig {
+    /// Forces a physical device by enumeration index or name. The `WILD_PHYSICAL_DEVICE`
+    /// environment variable takes precedence over this.
+    pub physical_device: Option<PhysicalDeviceOverride>,
+    /// Requested MSAA sample count (1, 2, 4 or 8), clamped to what the device supports.
+    /// 0 is treated as 1.
+    pub msaa_samples: u32,
+    /// Where compiled shaders are cached between runs. `None` compiles them at every start.
+    pub shader_cache_dir: Option<std::path::PathBuf>,
+    /// Recompiles the shaders from the source tree whenever they are edited, keeping the
+    /// previous ones when that fails.
+    pub watch_shaders: bool,
+    /// Where the pipeline cache is kept between runs. It is ignored when another device or
+    /// driver version wrote it. `None` starts with an empty cache at every start.
+    pub pipeline_cache_path: Option<std::path::PathBuf>,
+}
@@ -0,0 +1,140 @@
+use super::TextureData;
+use ash::vk;
+
+pub const KTX2_IDENTIFIER: [u8; 12] = [
+    0xab, b'K', b'T', b'X', b' ', b'2', b'0', 0xbb, b'\r', b'\n', 0x1a, b'\n',
+];
+
+fn decode_error(message: String) -> super::RendererError {
+    super::RendererError::ImageDecode(message)
+}
+
+/// Reads the prebuilt levels, layers and faces of a KTX2 texture.
+///
+/// Supercompressed and Basis Universal files need transcoding, which isn't supported.
+pub fn read_ktx2(bytes: &[u8]) -> super::Result<TextureData> {
+    let reader = ktx2::Reader::new(bytes)
+        .map_err(|error| decode_error(format!("Invalid KTX2: {}", error)))?;
+    let header = reader.header();
+    if let Some(scheme) = header.supercompression_scheme {
+        return Err(decode_error(format!(
+            "Unsupported KTX2 supercompression: {:?}",
+            scheme
+        )));
+    }
+    let format = header
+        .format
+        .map(|format| vk::Format::from_raw(format.value() as _))
+        .ok_or_else(|| decode_error("KTX2 without a Vulkan format".to_string()))?;
+    if header.pixel_depth > 1 {
+        return Err(decode_error(
+            "3D KTX2 textures are not supported".to_string(),
+        ));
+    }
+    let levels = reader.levels().map(|level| level.data).collect::<Vec<_>>();
+    TextureData::from_levels(
+        header.pixel_width,
+        header.pixel_height.max(1),
+        format,
+        header.layer_count.max(1) * header.face_count,
+        header.face_count == 6,
+        &levels,
+    )
+}
+
+/// Reads the prebuilt levels, layers and faces of a DDS texture.
+pub fn read_dds(bytes: &[u8]) -> super::Result<TextureData> {
+    let dds = ddsfile::Dds::read(bytes)
+        .map_err(|error| decode_error(format!("Invalid DDS: {}", error)))?;
+    let format = match (dds.get_dxgi_format(), dds.get_d3d_format()) {
+        (Some(format), _) => dxgi_format(format),
+        (None, Some(format)) => d3d_format(format),
+        (None, None) => None,
+    }
+    .ok_or_else(|| decode_error("Unsupported DDS pixel format".to_string()))?;
+    let cube = match &dds.header10 {
+        Some(header10) => header10.misc_flag.contains(ddsfile::MiscFlag::TEXTURECUBE),
+        None => dds.header.caps2.contains(ddsfile::Caps2::CUBEMAP),
+    };
+    let array_layers = match &dds.header10 {
+        Some(header10) if cube => header10.array_size.max(1) * 6,
+        Some(header10) => header10.array_size.max(1),
+        None if cube => 6,
+        None => 1,
+    };
+    let mip_levels = dds.get_num_mipmap_levels().max(1);
+
+    // DDS stores each layer's whole mip chain in turn; regroup it by level.
+    let block = super::compressed::texel_block(format)
+        .ok_or_else(|| super::RendererError::NoSupportedFormat(vec![format]))?;
+    let (width, height) = (dds.get_width(), dds.get_height());
+    let level_sizes = (0..mip_levels)
+        .map(|level| block.image_size((width >> level).max(1), (height >> level).max(1)))
+        .collect::<Vec<_>>();
+    let layer_size = level_sizes.iter().sum::<usize>();
+    if dds.data.len() < layer_size * array_layers as usize {
+        return Err(decode_error("Truncated DDS data".to_string()));
+    }
+    let levels = level_sizes
+        .iter()
+        .enumerate()
+        .map(|(level, size)| {
+            let level_offset = level_sizes[..level].iter().sum::<usize>();
+            (0..array_layers as usize)
+                .flat_map(|layer| {
+                    let offset = layer * layer_size + level_offset;
+                    dds.data[offset..offset + size].iter().copied()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    TextureData::from_levels(
+        width,
+        height,
+        format,
+        array_layers,
+        cube,
+        &levels.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+    )
+}
+
+fn dxgi_format(format: ddsfile::DxgiFormat) -> Option<vk::Format> {
+    use ddsfile::DxgiFormat;
+
+    Some(match format {
+        DxgiFormat::R8G8B8A8_UNorm => vk::Format::R8G8B8A8_UNORM,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => vk::Format::R8G8B8A8_SRGB,
+        DxgiFormat::B8G8R8A8_UNorm => vk::Format::B8G8R8A8_UNORM,
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => vk::Format::B8G8R8A8_SRGB,
+        DxgiFormat::R16G16B16A16_Float => vk::Format::R16G16B16A16_SFLOAT,
+        DxgiFormat::R32G32B32A32_Float => vk::Format::R32G32B32A32_SFLOAT,
+        DxgiFormat::BC1_UNorm => vk::Format::BC1_RGBA_UNORM_BLOCK,
+        DxgiFormat::BC1_UNorm_sRGB => vk::Format::BC1_RGBA_SRGB_BLOCK,
+        DxgiFormat::BC2_UNorm => vk::Format::BC2_UNORM_BLOCK,
+        DxgiFormat::BC2_UNorm_sRGB => vk::Format::BC2_SRGB_BLOCK,
+        DxgiFormat::BC3_UNorm => vk::Format::BC3_UNORM_BLOCK,
+        DxgiFormat::BC3_UNorm_sRGB => vk::Format::BC3_SRGB_BLOCK,
+        DxgiFormat::BC4_UNorm => vk::Format::BC4_UNORM_BLOCK,
+        DxgiFormat::BC4_SNorm => vk::Format::BC4_SNORM_BLOCK,
+        DxgiFormat::BC5_UNorm => vk::Format::BC5_UNORM_BLOCK,
+        DxgiFormat::BC5_SNorm => vk::Format::BC5_SNORM_BLOCK,
+        DxgiFormat::BC6H_UF16 => vk::Format::BC6H_UFLOAT_BLOCK,
+        DxgiFormat::BC6H_SF16 => vk::Format::BC6H_SFLOAT_BLOCK,
+        DxgiFormat::BC7_UNorm => vk::Format::BC7_UNORM_BLOCK,
+        DxgiFormat::BC7_UNorm_sRGB => vk::Format::BC7_SRGB_BLOCK,
+        _ => return None,
+    })
+}
+
+fn d3d_format(format: ddsfile::D3DFormat) -> Option<vk::Format> {
+    use ddsfile::D3DFormat;
+
+    Some(match format {
+        D3DFormat::A8B8G8R8 => vk::Format::R8G8B8A8_UNORM,
+        D3DFormat::A8R8G8B8 => vk::Format::B8G8R8A8_UNORM,
+        D3DFormat::DXT1 => vk::Format::BC1_RGBA_UNORM_BLOCK,
+        D3DFormat::DXT3 => vk::Format::BC2_UNORM_BLOCK,
+        D3DFormat::DXT5 => vk::Format::BC3_UNORM_BLOCK,
+        _ => return None,
+    })
+}
@@ -14,9 +14,7 @@ unsafe extern "system" fn debug_utils_messenger_callback(
         "[{:?}][{:?}] {}",
         message_severity,
         message_types,
-        std::ffi::CStr::from_ptr(callback_data.p_message)
-            .to_str()
-            .unwrap()
+        std::ffi::CStr::from_ptr(callback_data.p_message).to_string_lossy()
     );
     vk::FALSE
 }
@@ -27,7 +25,7 @@ pub struct DebugUtils {
 }
 
 impl DebugUtils {
-    pub fn new(entry: &Entry, instance: &Instance) -> Self {
+    pub fn new(entry: &Entry, instance: &Instance) -> super::Result<Self> {
         let loader = ash::extensions::ext::DebugUtils::new(&entry.entry, &instance.instance);
         #[cfg(debug_assertions)]
         let messenger = unsafe {
@@ -43,9 +41,8 @@ impl DebugUtils {
                 },
                 None,
             )
-        }
-        .unwrap();
-        Self { loader, messenger }
+        }?;
+        Ok(Self { loader, messenger })
     }
 }
 
@@ -0,0 +1,88 @@
+use super::Device;
+use ash::vk;
+
+/// A descriptor set layout with the bindings it was created from, so that pools and writes can
+/// follow the shaders' reflected interface.
+pub struct DescriptorSetLayout {
+    pub layout: vk::DescriptorSetLayout,
+    pub bindings: Vec<vk::DescriptorSetLayoutBinding>,
+}
+
+impl DescriptorSetLayout {
+    pub fn new(
+        device: &Device,
+        bindings: Vec<vk::DescriptorSetLayoutBinding>,
+    ) -> super::Result<Self> {
+        let layout = unsafe {
+            device.device.create_descriptor_set_layout(
+                &vk::DescriptorSetLayoutCreateInfo {
+                    binding_count: bindings.len() as _,
+                    p_bindings: bindings.as_ptr(),
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+        Ok(Self { layout, bindings })
+    }
+
+    /// A pool with room for the given number of sets of each layout.
+    pub fn create_pool(
+        device: &Device,
+        sets: &[(&Self, u32)],
+    ) -> super::Result<vk::DescriptorPool> {
+        let mut pool_sizes: Vec<vk::DescriptorPoolSize> = Vec::new();
+        for &(layout, count) in sets {
+            for binding in &layout.bindings {
+                let descriptor_count = binding.descriptor_count * count;
+                match pool_sizes
+                    .iter_mut()
+                    .find(|pool_size| pool_size.ty == binding.descriptor_type)
+                {
+                    Some(pool_size) => pool_size.descriptor_count += descriptor_count,
+                    None => pool_sizes.push(vk::DescriptorPoolSize {
+                        ty: binding.descriptor_type,
+                        descriptor_count,
+                    }),
+                }
+            }
+        }
+        Ok(unsafe {
+            device.device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo {
+                    pool_size_count: pool_sizes.len() as _,
+                    p_pool_sizes: pool_sizes.as_ptr(),
+                    max_sets: sets.iter().map(|&(_, count)| count).sum(),
+                    ..Default::default()
+                },
+                None,
+            )
+        }?)
+    }
+
+    pub fn allocate(
+        &self,
+        device: &Device,
+        descriptor_pool: vk::DescriptorPool,
+    ) -> super::Result<vk::DescriptorSet> {
+        Ok(unsafe {
+            device
+                .device
+                .allocate_descriptor_sets(&vk::DescriptorSetAllocateInfo {
+                    descriptor_pool,
+                    descriptor_set_count: 1,
+                    p_set_layouts: &self.layout,
+                    ..Default::default()
+                })
+        }?
+        .remove(0))
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe {
+            device
+                .device
+                .destroy_descriptor_set_layout(self.layout, None)
+        };
+    }
+}
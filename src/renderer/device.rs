@@ -1,71 +1,239 @@
+use super::{
+    Allocation, AllocationStrategy, Allocator, MemoryStatistics, PipelineCache, ResourceKind,
+};
 use ash::vk;
+use std::cell::RefCell;
 
 pub struct Device {
     pub physical_device: vk::PhysicalDevice,
     pub device: ash::Device,
     pub queue: vk::Queue,
+    pub queue_family_index: u32,
+    pub present_queue: vk::Queue,
+    pub present_queue_family_index: u32,
+    pub transfer_queue: vk::Queue,
+    pub transfer_queue_family_index: u32,
+    /// Runs compute mip generation when it is separate from the graphics queue.
+    pub compute_queue: vk::Queue,
+    pub compute_queue_family_index: u32,
+    pub limits: vk::PhysicalDeviceLimits,
+    /// The optional features that were enabled on this device.
+    pub features: vk::PhysicalDeviceFeatures,
+    pub allocator: RefCell<Allocator>,
+    /// Used for every pipeline created on this device, and saved when it is dropped.
+    pub pipeline_cache: PipelineCache,
 }
 
 impl Device {
-    pub fn new(instance: &super::Instance, surface: &mut super::Surface) -> Self {
-        let physical_devices = unsafe { instance.instance.enumerate_physical_devices() }.unwrap();
-        let physical_device = physical_devices
-            .iter()
-            .find(|physical_device| surface.is_supported(physical_device))
-            .unwrap();
-
-        #[cfg(debug_assertions)]
-        {
-            let physical_device_properties = unsafe {
-                instance
-                    .instance
-                    .get_physical_device_properties(*physical_device)
-            };
-            println!(
-                "{} (api_version: {}.{}.{})",
-                unsafe {
-                    std::ffi::CStr::from_ptr(physical_device_properties.device_name.as_ptr())
-                }
-                .to_str()
-                .unwrap(),
-                vk::api_version_major(physical_device_properties.api_version),
-                vk::api_version_minor(physical_device_properties.api_version),
-                vk::api_version_patch(physical_device_properties.api_version)
-            );
+    pub fn new(
+        instance: &super::Instance,
+        surface: Option<&mut super::Surface>,
+        config: &super::RendererConfig,
+    ) -> super::Result<Self> {
+        let extensions = match surface {
+            Some(_) => vec![ash::extensions::khr::Swapchain::name()],
+            None => Vec::new(),
+        };
+        let selection = super::DeviceSelection::select(
+            instance,
+            surface.as_deref(),
+            &extensions,
+            config.physical_device.as_ref(),
+        )?;
+        if let Some(surface) = surface {
+            surface.update_format_and_capabilities(&selection.physical_device)?;
         }
 
+        let physical_device_properties = unsafe {
+            instance
+                .instance
+                .get_physical_device_properties(selection.physical_device)
+        };
+        log::info!(
+            "{} (api_version: {}.{}.{})",
+            selection.name,
+            vk::api_version_major(physical_device_properties.api_version),
+            vk::api_version_minor(physical_device_properties.api_version),
+            vk::api_version_patch(physical_device_properties.api_version)
+        );
+
+        let supported_features = unsafe {
+            instance
+                .instance
+                .get_physical_device_features(selection.physical_device)
+        };
+        let features = vk::PhysicalDeviceFeatures {
+            sampler_anisotropy: supported_features.sampler_anisotropy,
+            image_cube_array: supported_features.image_cube_array,
+            ..Default::default()
+        };
+
+        let transfer_queue_family_index = selection
+            .transfer_queue_family_index
+            .unwrap_or(selection.queue_family_index);
+        let compute_queue_family_index = selection
+            .compute_queue_family_index
+            .unwrap_or(selection.queue_family_index);
+        let mut queue_family_indices = vec![
+            selection.queue_family_index,
+            selection.present_queue_family_index,
+            transfer_queue_family_index,
+            compute_queue_family_index,
+        ];
+        queue_family_indices.sort_unstable();
+        queue_family_indices.dedup();
+        let queue_create_infos = queue_family_indices
+            .iter()
+            .map(|queue_family_index| vk::DeviceQueueCreateInfo {
+                queue_family_index: *queue_family_index,
+                queue_count: 1,
+                p_queue_priorities: &1.0,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let extension_names = extensions
+            .iter()
+            .map(|extension| extension.as_ptr())
+            .collect::<Vec<_>>();
         let device = unsafe {
             instance.instance.create_device(
-                *physical_device,
+                selection.physical_device,
                 &vk::DeviceCreateInfo {
-                    queue_create_info_count: 1,
-                    p_queue_create_infos: &vk::DeviceQueueCreateInfo {
-                        queue_family_index: 0,
-                        queue_count: 1,
-                        p_queue_priorities: &1.0,
-                        ..Default::default()
-                    },
-                    enabled_extension_count: 1,
-                    pp_enabled_extension_names: [ash::extensions::khr::Swapchain::name().as_ptr()]
-                        .as_ptr(),
+                    queue_create_info_count: queue_create_infos.len() as _,
+                    p_queue_create_infos: queue_create_infos.as_ptr(),
+                    enabled_extension_count: extension_names.len() as _,
+                    pp_enabled_extension_names: extension_names.as_ptr(),
+                    p_enabled_features: &features,
                     ..Default::default()
                 },
                 None,
             )
-        }
-        .unwrap();
-        let queue = unsafe { device.get_device_queue(0, 0) };
+        }?;
+        let queue = unsafe { device.get_device_queue(selection.queue_family_index, 0) };
+        let present_queue =
+            unsafe { device.get_device_queue(selection.present_queue_family_index, 0) };
+        let transfer_queue = unsafe { device.get_device_queue(transfer_queue_family_index, 0) };
+        let compute_queue = unsafe { device.get_device_queue(compute_queue_family_index, 0) };
+
+        log::debug!(
+            "Queue families: graphics {}, present {}, transfer {}, compute {}",
+            selection.queue_family_index,
+            selection.present_queue_family_index,
+            transfer_queue_family_index,
+            compute_queue_family_index
+        );
 
-        Self {
-            physical_device: *physical_device,
+        let pipeline_cache = match PipelineCache::new(
+            &device,
+            &physical_device_properties,
+            config.pipeline_cache_path.as_deref(),
+        ) {
+            Ok(pipeline_cache) => pipeline_cache,
+            Err(error) => {
+                unsafe { device.destroy_device(None) };
+                return Err(error);
+            }
+        };
+
+        Ok(Self {
+            physical_device: selection.physical_device,
             device,
             queue,
-        }
+            queue_family_index: selection.queue_family_index,
+            present_queue,
+            present_queue_family_index: selection.present_queue_family_index,
+            transfer_queue,
+            transfer_queue_family_index,
+            compute_queue,
+            compute_queue_family_index,
+            limits: physical_device_properties.limits,
+            features,
+            allocator: RefCell::new(Allocator::new(
+                instance,
+                selection.physical_device,
+                &physical_device_properties.limits,
+            )),
+            pipeline_cache,
+        })
+    }
+
+    pub fn has_dedicated_transfer_queue(&self) -> bool {
+        self.transfer_queue_family_index != self.queue_family_index
+    }
+
+    pub fn has_dedicated_compute_queue(&self) -> bool {
+        self.compute_queue_family_index != self.queue_family_index
+    }
+
+    /// The largest sample count up to `requested` usable for both color and depth attachments.
+    pub fn supported_sample_count(&self, requested: u32) -> vk::SampleCountFlags {
+        let supported = self.limits.framebuffer_color_sample_counts
+            & self.limits.framebuffer_depth_sample_counts;
+        [
+            vk::SampleCountFlags::TYPE_8,
+            vk::SampleCountFlags::TYPE_4,
+            vk::SampleCountFlags::TYPE_2,
+        ]
+        .iter()
+        .copied()
+        .find(|samples| samples.as_raw() <= requested && supported.contains(*samples))
+        .unwrap_or(vk::SampleCountFlags::TYPE_1)
+    }
+
+    pub fn allocate_memory(
+        &self,
+        requirements: vk::MemoryRequirements,
+        memory_properties: vk::MemoryPropertyFlags,
+        kind: ResourceKind,
+        strategy: AllocationStrategy,
+    ) -> super::Result<Allocation> {
+        self.allocator.borrow_mut().allocate(
+            &self.device,
+            requirements,
+            memory_properties,
+            kind,
+            strategy,
+        )
+    }
+
+    pub fn free_memory(&self, allocation: &Allocation) {
+        self.allocator.borrow_mut().free(&self.device, allocation);
+    }
+
+    pub fn flush_memory(
+        &self,
+        allocation: &Allocation,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+    ) -> super::Result<()> {
+        self.allocator
+            .borrow()
+            .flush(&self.device, allocation, offset, size)
+    }
+
+    pub fn invalidate_memory(
+        &self,
+        allocation: &Allocation,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+    ) -> super::Result<()> {
+        self.allocator
+            .borrow()
+            .invalidate(&self.device, allocation, offset, size)
+    }
+
+    pub fn memory_statistics(&self) -> MemoryStatistics {
+        self.allocator.borrow().statistics()
     }
 }
 
 impl Drop for Device {
     fn drop(&mut self) {
+        if let Err(error) = self.pipeline_cache.save(&self.device) {
+            log::warn!("Failed to save the pipeline cache: {}", error);
+        }
+        self.pipeline_cache.destroy(&self.device);
+        self.allocator.get_mut().destroy(&self.device);
         unsafe { self.device.destroy_device(None) };
     }
 }
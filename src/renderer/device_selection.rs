@@ -0,0 +1,242 @@
+use super::{Instance, Surface};
+use ash::vk;
+
+pub const PHYSICAL_DEVICE_ENV: &str = "WILD_PHYSICAL_DEVICE";
+
+#[derive(Clone, Debug)]
+pub enum PhysicalDeviceOverride {
+    /// Index into the instance's enumerated physical devices.
+    Index(usize),
+    /// Case-insensitive substring of the device name.
+    Name(String),
+}
+
+impl std::str::FromStr for PhysicalDeviceOverride {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match value.trim().parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(value.trim().to_owned()),
+        })
+    }
+}
+
+impl PhysicalDeviceOverride {
+    fn matches(&self, index: usize, name: &str) -> bool {
+        match self {
+            Self::Index(override_index) => *override_index == index,
+            Self::Name(override_name) => {
+                name.to_lowercase().contains(&override_name.to_lowercase())
+            }
+        }
+    }
+}
+
+pub struct DeviceSelection {
+    pub physical_device: vk::PhysicalDevice,
+    pub name: String,
+    pub queue_family_index: u32,
+    pub present_queue_family_index: u32,
+    /// A family that supports transfers but neither graphics nor compute, if there is one.
+    pub transfer_queue_family_index: Option<u32>,
+    /// A compute family other than the graphics family, if there is one.
+    pub compute_queue_family_index: Option<u32>,
+    pub score: u32,
+}
+
+impl DeviceSelection {
+    pub fn select(
+        instance: &Instance,
+        surface: Option<&Surface>,
+        required_extensions: &[&std::ffi::CStr],
+        device_override: Option<&PhysicalDeviceOverride>,
+    ) -> super::Result<Self> {
+        let env_override = std::env::var(PHYSICAL_DEVICE_ENV)
+            .ok()
+            .map(|value| value.parse::<PhysicalDeviceOverride>().unwrap());
+        let device_override = env_override.as_ref().or(device_override);
+
+        let physical_devices = unsafe { instance.instance.enumerate_physical_devices() }?;
+        let mut selection: Option<Self> = None;
+        for (index, physical_device) in physical_devices.into_iter().enumerate() {
+            let properties = unsafe {
+                instance
+                    .instance
+                    .get_physical_device_properties(physical_device)
+            };
+            let name = unsafe { std::ffi::CStr::from_ptr(properties.device_name.as_ptr()) }
+                .to_string_lossy()
+                .into_owned();
+
+            if let Some(device_override) = device_override {
+                if !device_override.matches(index, &name) {
+                    log::info!(
+                        "Rejected physical device {} ({}): does not match {:?}",
+                        index,
+                        name,
+                        device_override
+                    );
+                    continue;
+                }
+            }
+
+            match Self::evaluate(
+                instance,
+                surface,
+                required_extensions,
+                physical_device,
+                &properties,
+                name.clone(),
+            )? {
+                Ok(candidate) => {
+                    log::debug!(
+                        "Physical device {} ({}) is suitable with a score of {}",
+                        index,
+                        name,
+                        candidate.score
+                    );
+                    if selection
+                        .as_ref()
+                        .is_none_or(|selection| candidate.score > selection.score)
+                    {
+                        selection = Some(candidate);
+                    }
+                }
+                Err(reason) => {
+                    log::info!("Rejected physical device {} ({}): {}", index, name, reason)
+                }
+            }
+        }
+
+        let selection = selection.ok_or(super::RendererError::NoSuitablePhysicalDevice)?;
+        log::info!(
+            "Selected physical device {} with a score of {}",
+            selection.name,
+            selection.score
+        );
+        Ok(selection)
+    }
+
+    fn evaluate(
+        instance: &Instance,
+        surface: Option<&Surface>,
+        required_extensions: &[&std::ffi::CStr],
+        physical_device: vk::PhysicalDevice,
+        properties: &vk::PhysicalDeviceProperties,
+        name: String,
+    ) -> super::Result<std::result::Result<Self, String>> {
+        let available_extensions = unsafe {
+            instance
+                .instance
+                .enumerate_device_extension_properties(physical_device)
+        }?;
+        if let Some(missing_extension) = required_extensions.iter().find(|extension| {
+            !available_extensions.iter().any(|available_extension| {
+                let name = unsafe {
+                    std::ffi::CStr::from_ptr(available_extension.extension_name.as_ptr())
+                };
+                name == **extension
+            })
+        }) {
+            return Ok(Err(format!(
+                "missing extension {}",
+                missing_extension.to_string_lossy()
+            )));
+        }
+
+        let queue_families = unsafe {
+            instance
+                .instance
+                .get_physical_device_queue_family_properties(physical_device)
+        };
+        let graphics_families = queue_families
+            .iter()
+            .enumerate()
+            .filter(|(_, queue_family)| {
+                queue_family.queue_count > 0
+                    && queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+            })
+            .map(|(i, _)| i as u32)
+            .collect::<Vec<_>>();
+        if graphics_families.is_empty() {
+            return Ok(Err("no queue family supports graphics".to_owned()));
+        }
+
+        let (queue_family_index, present_queue_family_index) = match surface {
+            Some(surface) => {
+                let mut present_families = Vec::new();
+                for i in 0..queue_families.len() as u32 {
+                    if surface.supports_queue_family(&physical_device, i)? {
+                        present_families.push(i);
+                    }
+                }
+                if !surface.has_formats_and_present_modes(&physical_device)? {
+                    return Ok(Err("the surface has no formats or present modes".to_owned()));
+                }
+                match graphics_families
+                    .iter()
+                    .find(|i| present_families.contains(i))
+                {
+                    Some(i) => (*i, *i),
+                    None => match present_families.first() {
+                        Some(present_family) => (graphics_families[0], *present_family),
+                        None => {
+                            return Ok(Err("no queue family can present to the surface".to_owned()))
+                        }
+                    },
+                }
+            }
+            None => (graphics_families[0], graphics_families[0]),
+        };
+
+        let transfer_queue_family_index = queue_families
+            .iter()
+            .position(|queue_family| {
+                queue_family.queue_count > 0
+                    && queue_family.queue_flags.contains(vk::QueueFlags::TRANSFER)
+                    && !queue_family
+                        .queue_flags
+                        .intersects(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+            })
+            .map(|i| i as u32);
+        let compute_families = queue_families
+            .iter()
+            .enumerate()
+            .filter(|(i, queue_family)| {
+                *i as u32 != queue_family_index
+                    && queue_family.queue_count > 0
+                    && queue_family.queue_flags.contains(vk::QueueFlags::COMPUTE)
+            })
+            .collect::<Vec<_>>();
+        let compute_queue_family_index = compute_families
+            .iter()
+            .find(|(_, queue_family)| !queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS))
+            .or_else(|| compute_families.first())
+            .map(|(i, _)| *i as u32);
+
+        let type_score = match properties.device_type {
+            vk::PhysicalDeviceType::DISCRETE_GPU => 4000,
+            vk::PhysicalDeviceType::INTEGRATED_GPU => 3000,
+            vk::PhysicalDeviceType::VIRTUAL_GPU => 2000,
+            vk::PhysicalDeviceType::CPU => 1000,
+            _ => 0,
+        };
+        let limits_score = properties.limits.max_image_dimension2_d / 1024;
+        let queue_score = if queue_family_index == present_queue_family_index {
+            10
+        } else {
+            0
+        };
+
+        Ok(Ok(Self {
+            physical_device,
+            name,
+            queue_family_index,
+            present_queue_family_index,
+            transfer_queue_family_index,
+            compute_queue_family_index,
+            score: type_score + limits_score + queue_score,
+        }))
+    }
+}
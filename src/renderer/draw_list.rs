@@ -0,0 +1,70 @@
+use super::{Material, Mesh, Primitive};
+use ash::vk;
+
+/// Per-draw values pushed as constants before each draw.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DrawConstants {
+    pub model: [[f32; 4]; 4],
+    pub base_color_factor: [f32; 4],
+}
+
+/// One indexed draw, holding the handles it is recorded with.
+pub struct Draw {
+    pub vertex_buffer: vk::Buffer,
+    pub index_buffer: vk::Buffer,
+    pub index_type: vk::IndexType,
+    pub index_count: u32,
+    pub material_set: vk::DescriptorSet,
+    pub constants: DrawConstants,
+}
+
+/// The draws of one frame, returned by `Renderer::begin_frame` and submitted by `end_frame`.
+///
+/// The list borrows the meshes it draws so they outlive the frame's recording. Materials are
+/// only referenced by their descriptor sets, which live as long as their model or renderer.
+#[derive(Default)]
+pub struct DrawList<'a> {
+    draws: Vec<Draw>,
+    _resources: std::marker::PhantomData<&'a ()>,
+}
+
+impl<'a> DrawList<'a> {
+    /// Draws every primitive of `mesh` with `material`, placed by `transform`.
+    pub fn draw(&mut self, mesh: &'a Mesh, material: &Material, transform: cgmath::Matrix4<f32>) {
+        mesh.primitives
+            .iter()
+            .for_each(|primitive| self.draw_primitive(primitive, material, transform));
+    }
+
+    pub fn draw_primitive(
+        &mut self,
+        primitive: &'a Primitive,
+        material: &Material,
+        transform: cgmath::Matrix4<f32>,
+    ) {
+        self.draws.push(Draw {
+            vertex_buffer: primitive.vertex_buffer.buffer.buffer,
+            index_buffer: primitive.index_buffer.buffer.buffer,
+            index_type: primitive.index_buffer.index_type,
+            index_count: primitive.index_buffer.index_count,
+            material_set: material.descriptor_set,
+            constants: DrawConstants {
+                model: transform.into(),
+                base_color_factor: material.data.base_color_factor,
+            },
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.draws.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+
+    pub(super) fn into_draws(self) -> Vec<Draw> {
+        self.draws
+    }
+}
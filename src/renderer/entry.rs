@@ -3,9 +3,9 @@ pub struct Entry {
 }
 
 impl Entry {
-    pub fn new() -> Self {
-        Self {
-            entry: unsafe { ash::Entry::load() }.unwrap(),
-        }
+    pub fn new() -> super::Result<Self> {
+        Ok(Self {
+            entry: unsafe { ash::Entry::load() }?,
+        })
     }
 }
@@ -0,0 +1,169 @@
+use ash::vk;
+
+#[derive(Debug)]
+pub enum RendererError {
+    /// The Vulkan loader library could not be found or loaded.
+    LoaderMissing(ash::LoadingError),
+    /// No physical device (or no installed driver) satisfies the renderer's requirements.
+    NoSuitablePhysicalDevice,
+    /// No memory type matches both the resource's type bits and the requested properties.
+    MissingMemoryType {
+        memory_type_bits: u32,
+        properties: vk::MemoryPropertyFlags,
+    },
+    DeviceLost,
+    SurfaceLost,
+    ShaderModule(vk::Result),
+    /// A shader failed to preprocess or compile; the message locates each error by file and line.
+    ShaderCompile(String),
+    /// A shader's interface can't be reflected, or doesn't fit the pipeline built from it.
+    ShaderReflection(String),
+    /// The vertex shader reads a location the vertex layout leaves out or fills with another
+    /// numeric type.
+    VertexLayoutMismatch {
+        location: u32,
+        shader_format: vk::Format,
+        layout_format: Option<vk::Format>,
+    },
+    /// The final color attachment can't be read back in its current format or usage.
+    CaptureUnsupported(vk::Format),
+    /// The device supports none of the candidate formats.
+    NoSupportedFormat(Vec<vk::Format>),
+    /// The device can't sample a compressed format that has no decoder to fall back on.
+    DecompressionUnsupported(vk::Format),
+    /// The compute downsampler has no storage format to generate mips of this format with.
+    MipGenerationUnsupported(vk::Format),
+    /// A texture's view type doesn't match the binding it is used with.
+    ViewTypeMismatch {
+        expected: vk::ImageViewType,
+        actual: vk::ImageViewType,
+    },
+    /// A primitive has nothing to draw, and Vulkan buffers can't be empty.
+    EmptyPrimitive {
+        vertices: usize,
+        indices: usize,
+    },
+    /// A ring buffer frame has no room left for this frame's transient data.
+    RingBufferFull {
+        requested: usize,
+        frame_size: usize,
+    },
+    /// A file could not be read.
+    Io(std::io::Error),
+    /// Image data is malformed or in a layout the decoder doesn't handle.
+    ImageDecode(String),
+    /// A model file is malformed or uses features the loader doesn't handle.
+    ModelLoad(String),
+    Vulkan(vk::Result),
+}
+
+impl std::fmt::Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LoaderMissing(error) => write!(f, "Failed to load the Vulkan loader: {}", error),
+            Self::NoSuitablePhysicalDevice => write!(f, "No suitable physical device found"),
+            Self::MissingMemoryType {
+                memory_type_bits,
+                properties,
+            } => write!(
+                f,
+                "No memory type in {:#b} supports {:?}",
+                memory_type_bits, properties
+            ),
+            Self::DeviceLost => write!(f, "The device was lost"),
+            Self::SurfaceLost => write!(f, "The surface was lost"),
+            Self::ShaderModule(result) => write!(f, "Failed to create a shader module: {}", result),
+            Self::ShaderCompile(message) => write!(f, "Failed to compile shader:\n{}", message),
+            Self::ShaderReflection(message) => write!(f, "Failed to reflect shader: {}", message),
+            Self::VertexLayoutMismatch {
+                location,
+                shader_format,
+                layout_format: Some(layout_format),
+            } => write!(
+                f,
+                "The vertex shader reads location {} as {:?} but the vertex layout provides {:?}",
+                location, shader_format, layout_format
+            ),
+            Self::VertexLayoutMismatch {
+                location,
+                shader_format,
+                layout_format: None,
+            } => write!(
+                f,
+                "The vertex shader reads location {} as {:?} but the vertex layout has no attribute there",
+                location, shader_format
+            ),
+            Self::CaptureUnsupported(format) => {
+                write!(f, "Capturing {:?} images is not supported", format)
+            }
+            Self::NoSupportedFormat(candidates) => {
+                write!(f, "None of the formats {:?} is supported", candidates)
+            }
+            Self::DecompressionUnsupported(format) => write!(
+                f,
+                "{:?} isn't supported by the device and can't be decompressed",
+                format
+            ),
+            Self::MipGenerationUnsupported(format) => {
+                write!(f, "Mips of {:?} can't be generated by a compute shader", format)
+            }
+            Self::ViewTypeMismatch { expected, actual } => write!(
+                f,
+                "Expected a {:?} texture but got a {:?} one",
+                expected, actual
+            ),
+            Self::EmptyPrimitive { vertices, indices } => write!(
+                f,
+                "Can't upload a primitive with {} vertices and {} indices",
+                vertices, indices
+            ),
+            Self::RingBufferFull {
+                requested,
+                frame_size,
+            } => write!(
+                f,
+                "Can't fit {} more bytes into a {} byte ring buffer frame",
+                requested, frame_size
+            ),
+            Self::Io(error) => write!(f, "I/O error: {}", error),
+            Self::ImageDecode(message) => write!(f, "Failed to decode image: {}", message),
+            Self::ModelLoad(message) => write!(f, "Failed to load model: {}", message),
+            Self::Vulkan(result) => write!(f, "Vulkan call failed: {}", result),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::LoaderMissing(error) => Some(error),
+            Self::Io(error) => Some(error),
+            Self::ShaderModule(result) | Self::Vulkan(result) => Some(result),
+            _ => None,
+        }
+    }
+}
+
+impl From<vk::Result> for RendererError {
+    fn from(result: vk::Result) -> Self {
+        match result {
+            vk::Result::ERROR_DEVICE_LOST => Self::DeviceLost,
+            vk::Result::ERROR_SURFACE_LOST_KHR => Self::SurfaceLost,
+            result => Self::Vulkan(result),
+        }
+    }
+}
+
+impl From<ash::LoadingError> for RendererError {
+    fn from(error: ash::LoadingError) -> Self {
+        Self::LoaderMissing(error)
+    }
+}
+
+impl From<std::io::Error> for RendererError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, RendererError>;
@@ -0,0 +1,92 @@
+use ash::vk;
+
+/// A captured color attachment as tightly packed, sRGB-encoded RGBA8 pixels.
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    pub fn from_raw(
+        extent: vk::Extent2D,
+        format: vk::Format,
+        mut pixels: Vec<u8>,
+    ) -> super::Result<Self> {
+        match format {
+            vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => {}
+            vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM => pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2)),
+            format => return Err(super::RendererError::CaptureUnsupported(format)),
+        }
+        Ok(Self {
+            width: extent.width,
+            height: extent.height,
+            pixels,
+        })
+    }
+
+    pub fn load_png<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        let mut decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(path)?));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
+        let mut reader = decoder.read_info().map_err(std::io::Error::other)?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut pixels)
+            .map_err(std::io::Error::other)?;
+        pixels.truncate(info.buffer_size());
+        if info.bit_depth != png::BitDepth::Eight || info.color_type != png::ColorType::Rgba {
+            return Err(std::io::Error::other(format!(
+                "Unsupported PNG layout: {:?} {:?}",
+                info.color_type, info.bit_depth
+            )));
+        }
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * self.width + x) * 4) as usize;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[offset..offset + 4]);
+        pixel
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("ppm") => self.save_ppm(path),
+            _ => self.save_png(path),
+        }
+    }
+
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(std::io::Error::other)
+    }
+
+    pub fn save_ppm<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height)?;
+        self.pixels
+            .chunks_exact(4)
+            .try_for_each(|pixel| file.write_all(&pixel[..3]))?;
+        file.flush()
+    }
+}
@@ -1,39 +1,45 @@
 use ash::vk;
 
+#[derive(Default)]
 pub struct Framebuffers {
     pub framebuffers: Vec<vk::Framebuffer>,
 }
 
 impl Framebuffers {
     pub fn new(
-        surface: &super::Surface,
         device: &super::Device,
-        swapchain: &super::Swapchain,
+        extent: vk::Extent2D,
+        image_views: &[vk::ImageView],
+        color_image_view: Option<vk::ImageView>,
+        depth_image_view: vk::ImageView,
         render_pass: &super::RenderPass,
-    ) -> Self {
-        Self {
-            framebuffers: swapchain
-                .image_views
+    ) -> super::Result<Self> {
+        Ok(Self {
+            framebuffers: image_views
                 .iter()
-                .map(|image_view| {
-                    unsafe {
-                        device.device.create_framebuffer(
-                            &vk::FramebufferCreateInfo {
-                                render_pass: render_pass.render_pass,
-                                attachment_count: 1,
-                                p_attachments: image_view,
-                                width: surface.capabilities.unwrap().current_extent.width,
-                                height: surface.capabilities.unwrap().current_extent.height,
-                                layers: 1,
-                                ..Default::default()
-                            },
-                            None,
-                        )
-                    }
-                    .unwrap()
+                .map(|image_view| unsafe {
+                    // Multisampled passes render into the shared color image and resolve into the target.
+                    let attachments = match color_image_view {
+                        Some(color_image_view) => {
+                            vec![color_image_view, depth_image_view, *image_view]
+                        }
+                        None => vec![*image_view, depth_image_view],
+                    };
+                    device.device.create_framebuffer(
+                        &vk::FramebufferCreateInfo {
+                            render_pass: render_pass.render_pass,
+                            attachment_count: attachments.len() as _,
+                            p_attachments: attachments.as_ptr(),
+                            width: extent.width,
+                            height: extent.height,
+                            layers: 1,
+                            ..Default::default()
+                        },
+                        None,
+                    )
                 })
-                .collect::<Vec<_>>(),
-        }
+                .collect::<Result<Vec<_>, _>>()?,
+        })
     }
 
     pub fn destroy(&self, device: &super::Device) {
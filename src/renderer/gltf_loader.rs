@@ -0,0 +1,301 @@
+use super::{
+    AlphaMode, Indices, MaterialData, MeshData, MeshVertex, ModelData, NodeData, PrimitiveData,
+    Projection, SamplerConfig, TextureData, TextureSource,
+};
+use ash::vk;
+use base64::Engine;
+
+fn load_error(message: impl std::fmt::Display) -> super::RendererError {
+    super::RendererError::ModelLoad(message.to_string())
+}
+
+/// Reads the default scene of a `.gltf` or `.glb` file, or its first scene if none is marked.
+///
+/// Point and line primitives are skipped.
+pub fn load(bytes: &[u8], base_dir: Option<&std::path::Path>) -> super::Result<ModelData> {
+    let gltf::Gltf { document, mut blob } = gltf::Gltf::from_slice(bytes).map_err(load_error)?;
+    let buffers = document
+        .buffers()
+        .map(|buffer| {
+            let data = match buffer.source() {
+                gltf::buffer::Source::Bin => blob
+                    .take()
+                    .ok_or_else(|| load_error("Missing the binary chunk"))?,
+                gltf::buffer::Source::Uri(uri) => read_uri(uri, base_dir)?,
+            };
+            if data.len() < buffer.length() {
+                return Err(load_error(format!(
+                    "Buffer {} has {} bytes, expected {}",
+                    buffer.index(),
+                    data.len(),
+                    buffer.length()
+                )));
+            }
+            Ok(data)
+        })
+        .collect::<super::Result<Vec<_>>>()?;
+
+    let mut images = document
+        .images()
+        .map(|image| match image.source() {
+            gltf::image::Source::View { view, .. } => buffers[view.buffer().index()]
+                .get(view.offset()..view.offset() + view.length())
+                .ok_or_else(|| load_error(format!("Image {} is out of bounds", image.index())))
+                .and_then(TextureData::from_memory),
+            gltf::image::Source::Uri { uri, .. } => {
+                TextureData::from_memory(&read_uri(uri, base_dir)?)
+            }
+        })
+        .collect::<super::Result<Vec<_>>>()?;
+    let textures = document
+        .textures()
+        .map(|texture| TextureSource {
+            image: texture.source().index(),
+            sampler: sampler_config(&texture.sampler()),
+        })
+        .collect::<Vec<_>>();
+    let materials = document.materials().map(material).collect::<Vec<_>>();
+    // Decoded 8-bit images default to sRGB, which would distort normals and roughness.
+    for texture in materials.iter().flat_map(MaterialData::linear_textures) {
+        let image = &mut images[textures[texture].image];
+        if image.format == vk::Format::R8G8B8A8_SRGB {
+            image.format = vk::Format::R8G8B8A8_UNORM;
+        }
+    }
+
+    let meshes = document
+        .meshes()
+        .map(|mesh| {
+            let primitives = mesh
+                .primitives()
+                .map(|primitive| self::primitive(&primitive, &buffers))
+                .collect::<super::Result<Vec<_>>>()?;
+            Ok(MeshData {
+                name: mesh.name().map(str::to_string),
+                primitives: primitives.into_iter().flatten().collect(),
+            })
+        })
+        .collect::<super::Result<Vec<_>>>()?;
+    let nodes = document
+        .nodes()
+        .map(|node| NodeData {
+            name: node.name().map(str::to_string),
+            transform: node.transform().matrix().into(),
+            mesh: node.mesh().map(|mesh| mesh.index()),
+            camera: node.camera().map(|camera| projection(&camera)),
+            children: node.children().map(|child| child.index()).collect(),
+        })
+        .collect();
+    let roots = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .map(|scene| scene.nodes().map(|node| node.index()).collect())
+        .unwrap_or_default();
+
+    Ok(ModelData {
+        meshes,
+        materials,
+        textures,
+        images,
+        nodes,
+        roots,
+    })
+}
+
+/// Reads a data URI, or a file relative to `base_dir`.
+fn read_uri(uri: &str, base_dir: Option<&std::path::Path>) -> super::Result<Vec<u8>> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data
+            .split_once(";base64,")
+            .ok_or_else(|| load_error("Only base64 data URIs are supported"))?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(load_error)
+    } else {
+        let base_dir = base_dir
+            .ok_or_else(|| load_error(format!("No directory to resolve {} against", uri)))?;
+        let path = urlencoding::decode(uri).map_err(load_error)?;
+        Ok(std::fs::read(base_dir.join(&*path))?)
+    }
+}
+
+/// Reads a triangle primitive, converting strips and fans to lists.
+fn primitive(
+    primitive: &gltf::Primitive,
+    buffers: &[Vec<u8>],
+) -> super::Result<Option<PrimitiveData>> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+    let mut vertices = reader
+        .read_positions()
+        .ok_or_else(|| load_error("Primitive without positions"))?
+        .map(|position| MeshVertex {
+            position,
+            color: [1.0; 4],
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    let has_normals = match reader.read_normals() {
+        Some(normals) => {
+            vertices
+                .iter_mut()
+                .zip(normals)
+                .for_each(|(vertex, normal)| vertex.normal = normal);
+            true
+        }
+        None => false,
+    };
+    if let Some(tangents) = reader.read_tangents() {
+        vertices
+            .iter_mut()
+            .zip(tangents)
+            .for_each(|(vertex, tangent)| vertex.tangent = tangent);
+    }
+    if let Some(tex_coords) = reader.read_tex_coords(0) {
+        vertices
+            .iter_mut()
+            .zip(tex_coords.into_f32())
+            .for_each(|(vertex, tex_coord)| vertex.tex_coord = tex_coord);
+    }
+    if let Some(colors) = reader.read_colors(0) {
+        vertices
+            .iter_mut()
+            .zip(colors.into_rgba_f32())
+            .for_each(|(vertex, color)| vertex.color = color);
+    }
+
+    // Keep the file's index width; only generated indices get to pick their own.
+    let (indices, wide) = match reader.read_indices() {
+        Some(gltf::mesh::util::ReadIndices::U32(indices)) => (indices.collect(), Some(true)),
+        Some(indices) => (indices.into_u32().collect(), Some(false)),
+        None => ((0..vertices.len() as u32).collect::<Vec<_>>(), None),
+    };
+    if let Some(&index) = indices
+        .iter()
+        .find(|&&index| index as usize >= vertices.len())
+    {
+        return Err(load_error(format!(
+            "Index {} is out of bounds for {} vertices",
+            index,
+            vertices.len()
+        )));
+    }
+    let indices = match primitive.mode() {
+        gltf::mesh::Mode::Triangles => indices,
+        gltf::mesh::Mode::TriangleStrip => (0..indices.len().saturating_sub(2))
+            .flat_map(|i| [indices[i], indices[i + 1 + i % 2], indices[i + 2 - i % 2]])
+            .collect(),
+        gltf::mesh::Mode::TriangleFan => (1..indices.len().saturating_sub(1))
+            .flat_map(|i| [indices[i], indices[i + 1], indices[0]])
+            .collect(),
+        _ => return Ok(None),
+    };
+    if indices.len() < 3 {
+        return Ok(None);
+    }
+
+    let vertex_count = vertices.len();
+    let mut primitive = PrimitiveData {
+        vertices,
+        indices: match wide {
+            Some(true) => Indices::U32(indices),
+            Some(false) => Indices::U16(indices.into_iter().map(|index| index as u16).collect()),
+            None => Indices::from_u32(indices, vertex_count),
+        },
+        material: primitive.material().index(),
+    };
+    // The spec asks for flat shading when normals are missing.
+    if !has_normals {
+        primitive.generate_flat_normals();
+    }
+    Ok(Some(primitive))
+}
+
+fn material(material: gltf::Material) -> MaterialData {
+    let pbr = material.pbr_metallic_roughness();
+    MaterialData {
+        name: material.name().map(str::to_string),
+        base_color_factor: pbr.base_color_factor(),
+        base_color_texture: pbr.base_color_texture().map(|info| info.texture().index()),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr
+            .metallic_roughness_texture()
+            .map(|info| info.texture().index()),
+        normal_texture: material
+            .normal_texture()
+            .map(|normal| normal.texture().index()),
+        normal_scale: material
+            .normal_texture()
+            .map_or(1.0, |normal| normal.scale()),
+        occlusion_texture: material
+            .occlusion_texture()
+            .map(|occlusion| occlusion.texture().index()),
+        occlusion_strength: material
+            .occlusion_texture()
+            .map_or(1.0, |occlusion| occlusion.strength()),
+        emissive_factor: material.emissive_factor(),
+        emissive_texture: material
+            .emissive_texture()
+            .map(|info| info.texture().index()),
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => {
+                AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5))
+            }
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        double_sided: material.double_sided(),
+    }
+}
+
+fn sampler_config(sampler: &gltf::texture::Sampler) -> SamplerConfig {
+    use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+    let mag_filter = match sampler.mag_filter() {
+        Some(MagFilter::Nearest) => vk::Filter::NEAREST,
+        _ => vk::Filter::LINEAR,
+    };
+    let (min_filter, mipmap_mode) = match sampler.min_filter() {
+        Some(MinFilter::Nearest) | Some(MinFilter::NearestMipmapNearest) => {
+            (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST)
+        }
+        Some(MinFilter::NearestMipmapLinear) => {
+            (vk::Filter::NEAREST, vk::SamplerMipmapMode::LINEAR)
+        }
+        Some(MinFilter::Linear) | Some(MinFilter::LinearMipmapNearest) => {
+            (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST)
+        }
+        Some(MinFilter::LinearMipmapLinear) | None => {
+            (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR)
+        }
+    };
+    let address_mode = |mode| match mode {
+        WrappingMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+        WrappingMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+        WrappingMode::Repeat => vk::SamplerAddressMode::REPEAT,
+    };
+    SamplerConfig {
+        mag_filter,
+        min_filter,
+        mipmap_mode,
+        address_mode_u: address_mode(sampler.wrap_s()),
+        address_mode_v: address_mode(sampler.wrap_t()),
+        ..SamplerConfig::default()
+    }
+}
+
+/// Infinite glTF perspectives get a far plane a thousand times as distant as the near one.
+fn projection(camera: &gltf::Camera) -> Projection {
+    match camera.projection() {
+        gltf::camera::Projection::Perspective(perspective) => Projection::Perspective {
+            fov_y: cgmath::Rad(perspective.yfov()),
+            near: perspective.znear(),
+            far: perspective.zfar().unwrap_or(perspective.znear() * 1000.0),
+        },
+        gltf::camera::Projection::Orthographic(orthographic) => Projection::Orthographic {
+            height: orthographic.ymag() * 2.0,
+            near: orthographic.znear(),
+            far: orthographic.zfar(),
+        },
+    }
+}
@@ -0,0 +1,92 @@
+use super::{Buffer, Device, Transfer, UploadHandle};
+use ash::vk;
+
+/// A device-local buffer of `T`s, filled through the upload queue.
+pub struct GpuBuffer<T: bytemuck::Pod> {
+    pub buffer: Buffer,
+    pub len: usize,
+    pub upload: UploadHandle,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> GpuBuffer<T> {
+    pub fn new(
+        device: &Device,
+        transfer: &mut Transfer,
+        data: &[T],
+        buffer_usage: vk::BufferUsageFlags,
+    ) -> super::Result<Self> {
+        let bytes = bytemuck::cast_slice::<T, u8>(data);
+        let staging_buffer = Buffer::staging(device, bytes.len())?;
+        let buffer = match staging_buffer.write_arr(device, bytes).and_then(|_| {
+            Buffer::new(
+                device,
+                bytes.len(),
+                vk::BufferUsageFlags::TRANSFER_DST | buffer_usage,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            )
+        }) {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                staging_buffer.destory(device);
+                return Err(error);
+            }
+        };
+        let (dst_stage_mask, dst_access_mask) = first_use(buffer_usage);
+        let upload = match transfer.upload_buffer(
+            device,
+            staging_buffer,
+            &buffer,
+            dst_stage_mask,
+            dst_access_mask,
+        ) {
+            Ok(upload) => upload,
+            Err(error) => {
+                buffer.destory(device);
+                return Err(error);
+            }
+        };
+
+        Ok(Self {
+            buffer,
+            len: data.len(),
+            upload,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        self.buffer.destory(device);
+    }
+}
+
+/// The stages and accesses that may read a buffer with this usage first.
+fn first_use(buffer_usage: vk::BufferUsageFlags) -> (vk::PipelineStageFlags, vk::AccessFlags) {
+    let mut stage_mask = vk::PipelineStageFlags::empty();
+    let mut access_mask = vk::AccessFlags::empty();
+    if buffer_usage.contains(vk::BufferUsageFlags::VERTEX_BUFFER) {
+        stage_mask |= vk::PipelineStageFlags::VERTEX_INPUT;
+        access_mask |= vk::AccessFlags::VERTEX_ATTRIBUTE_READ;
+    }
+    if buffer_usage.contains(vk::BufferUsageFlags::INDEX_BUFFER) {
+        stage_mask |= vk::PipelineStageFlags::VERTEX_INPUT;
+        access_mask |= vk::AccessFlags::INDEX_READ;
+    }
+    if buffer_usage.contains(vk::BufferUsageFlags::UNIFORM_BUFFER) {
+        stage_mask |= vk::PipelineStageFlags::VERTEX_SHADER
+            | vk::PipelineStageFlags::FRAGMENT_SHADER
+            | vk::PipelineStageFlags::COMPUTE_SHADER;
+        access_mask |= vk::AccessFlags::UNIFORM_READ;
+    }
+    if buffer_usage.contains(vk::BufferUsageFlags::STORAGE_BUFFER) {
+        stage_mask |= vk::PipelineStageFlags::VERTEX_SHADER
+            | vk::PipelineStageFlags::FRAGMENT_SHADER
+            | vk::PipelineStageFlags::COMPUTE_SHADER;
+        access_mask |= vk::AccessFlags::SHADER_READ;
+    }
+    if stage_mask.is_empty() {
+        stage_mask = vk::PipelineStageFlags::ALL_COMMANDS;
+        access_mask = vk::AccessFlags::MEMORY_READ;
+    }
+    (stage_mask, access_mask)
+}
@@ -0,0 +1,194 @@
+use super::{Allocation, AllocationStrategy, Device, ResourceKind};
+use ash::vk;
+
+/// A device-local 2D image with a view covering all of its mip levels and array layers.
+pub struct Image {
+    pub image: vk::Image,
+    pub allocation: Allocation,
+    pub view: vk::ImageView,
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+    pub mip_levels: u32,
+    pub samples: vk::SampleCountFlags,
+}
+
+impl Image {
+    pub fn new(
+        device: &Device,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        mip_levels: u32,
+        usage: vk::ImageUsageFlags,
+        samples: vk::SampleCountFlags,
+    ) -> super::Result<Self> {
+        Self::create(
+            device,
+            extent,
+            format,
+            mip_levels,
+            1,
+            vk::ImageViewType::TYPE_2D,
+            usage,
+            samples,
+        )
+    }
+
+    /// A single-sampled array or cube image; cube views take six layers per cube.
+    pub fn with_layers(
+        device: &Device,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        mip_levels: u32,
+        array_layers: u32,
+        view_type: vk::ImageViewType,
+        usage: vk::ImageUsageFlags,
+    ) -> super::Result<Self> {
+        Self::create(
+            device,
+            extent,
+            format,
+            mip_levels,
+            array_layers,
+            view_type,
+            usage,
+            vk::SampleCountFlags::TYPE_1,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create(
+        device: &Device,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        mip_levels: u32,
+        array_layers: u32,
+        view_type: vk::ImageViewType,
+        usage: vk::ImageUsageFlags,
+        samples: vk::SampleCountFlags,
+    ) -> super::Result<Self> {
+        let flags = match view_type {
+            vk::ImageViewType::CUBE | vk::ImageViewType::CUBE_ARRAY => {
+                vk::ImageCreateFlags::CUBE_COMPATIBLE
+            }
+            _ => vk::ImageCreateFlags::empty(),
+        };
+        let image = unsafe {
+            device.device.create_image(
+                &vk::ImageCreateInfo {
+                    flags,
+                    image_type: vk::ImageType::TYPE_2D,
+                    format,
+                    extent: vk::Extent3D {
+                        width: extent.width,
+                        height: extent.height,
+                        depth: 1,
+                    },
+                    mip_levels,
+                    array_layers,
+                    samples,
+                    tiling: vk::ImageTiling::OPTIMAL,
+                    usage,
+                    sharing_mode: vk::SharingMode::EXCLUSIVE,
+                    initial_layout: vk::ImageLayout::UNDEFINED,
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+
+        let memory_requirements = unsafe { device.device.get_image_memory_requirements(image) };
+        let allocation = match device.allocate_memory(
+            memory_requirements,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            ResourceKind::Optimal,
+            AllocationStrategy::FreeList,
+        ) {
+            Ok(allocation) => allocation,
+            Err(error) => {
+                unsafe { device.device.destroy_image(image, None) };
+                return Err(error);
+            }
+        };
+        let view = unsafe {
+            device
+                .device
+                .bind_image_memory(image, allocation.memory, allocation.offset)
+        }
+        .and_then(|_| unsafe {
+            device.device.create_image_view(
+                &vk::ImageViewCreateInfo {
+                    image,
+                    view_type,
+                    format,
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: aspect_mask(format),
+                        base_mip_level: 0,
+                        level_count: mip_levels,
+                        base_array_layer: 0,
+                        layer_count: array_layers,
+                    },
+                    ..Default::default()
+                },
+                None,
+            )
+        });
+        let view = match view {
+            Ok(view) => view,
+            Err(result) => {
+                unsafe { device.device.destroy_image(image, None) };
+                device.free_memory(&allocation);
+                return Err(result.into());
+            }
+        };
+
+        Ok(Self {
+            image,
+            allocation,
+            view,
+            format,
+            extent,
+            mip_levels,
+            samples,
+        })
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe {
+            device.device.destroy_image_view(self.view, None);
+            device.device.destroy_image(self.image, None);
+        }
+        device.free_memory(&self.allocation);
+    }
+}
+
+pub fn aspect_mask(format: vk::Format) -> vk::ImageAspectFlags {
+    match format {
+        vk::Format::D16_UNORM | vk::Format::D32_SFLOAT | vk::Format::X8_D24_UNORM_PACK32 => {
+            vk::ImageAspectFlags::DEPTH
+        }
+        vk::Format::D16_UNORM_S8_UINT
+        | vk::Format::D24_UNORM_S8_UINT
+        | vk::Format::D32_SFLOAT_S8_UINT => {
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        }
+        vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+        _ => vk::ImageAspectFlags::COLOR,
+    }
+}
+
+/// Picks the first candidate usable as a depth/stencil attachment with optimal tiling.
+pub fn find_depth_format(
+    instance: &super::Instance,
+    device: &Device,
+    candidates: &[vk::Format],
+) -> super::Result<vk::Format> {
+    candidates
+        .iter()
+        .copied()
+        .find(|format| {
+            instance
+                .format_features(device.physical_device, *format)
+                .contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
+        })
+        .ok_or_else(|| super::RendererError::NoSupportedFormat(candidates.to_vec()))
+}
@@ -1,37 +1,49 @@
-use super::{Buffer, CommandPool, Device, Instance};
+use super::{Buffer, Device, GpuBuffer, Transfer, UploadHandle};
 use ash::vk;
 
+/// An index element type with a matching `vk::IndexType`.
+pub trait Index: bytemuck::Pod {
+    const INDEX_TYPE: vk::IndexType;
+}
+
+impl Index for u16 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT16;
+}
+
+impl Index for u32 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT32;
+}
+
 pub struct IndexBuffer {
-    pub indices: Vec<u16>,
     pub buffer: Buffer,
+    pub index_type: vk::IndexType,
+    pub index_count: u32,
+    pub upload: UploadHandle,
 }
 
 impl IndexBuffer {
-    pub fn new(
-        instance: &Instance,
+    pub fn new<I: Index>(
         device: &Device,
-        command_pool: &CommandPool,
-        indices: Vec<u16>,
-    ) -> Self {
-        let buffer_size = std::mem::size_of::<u16>() * indices.len();
-        let staging_buffer = Buffer::new(
-            instance,
-            device,
-            buffer_size,
-            vk::BufferUsageFlags::TRANSFER_SRC,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-        );
-        staging_buffer.write_arr(device, &indices);
-        let buffer = Buffer::new(
-            instance,
+        transfer: &mut Transfer,
+        indices: &[I],
+    ) -> super::Result<Self> {
+        let GpuBuffer {
+            buffer,
+            len,
+            upload,
+            ..
+        } = GpuBuffer::new(
             device,
-            buffer_size,
-            vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER,
-            vk::MemoryPropertyFlags::DEVICE_LOCAL,
-        );
-        staging_buffer.copy_to(device, command_pool, &buffer);
-        staging_buffer.destory(device);
-        Self { indices, buffer }
+            transfer,
+            indices,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+        )?;
+        Ok(Self {
+            buffer,
+            index_type: I::INDEX_TYPE,
+            index_count: len as _,
+            upload,
+        })
     }
 
     pub fn destory(&self, device: &Device) {
@@ -5,29 +5,50 @@ pub struct Instance {
 }
 
 impl Instance {
-    pub fn new(entry: &super::Entry, window: &winit::window::Window) -> Self {
-        Self {
-            instance: Self::create_instance(entry, window),
+    pub fn new(
+        entry: &super::Entry,
+        window: Option<&winit::window::Window>,
+    ) -> super::Result<Self> {
+        Ok(Self {
+            instance: Self::create_instance(entry, window)?,
+        })
+    }
+
+    /// The features `format` supports with optimal tiling.
+    pub fn format_features(
+        &self,
+        physical_device: vk::PhysicalDevice,
+        format: vk::Format,
+    ) -> vk::FormatFeatureFlags {
+        unsafe {
+            self.instance
+                .get_physical_device_format_properties(physical_device, format)
         }
+        .optimal_tiling_features
     }
 
-    fn create_instance(entry: &super::Entry, window: &winit::window::Window) -> ash::Instance {
-        let extensions = ash_window::enumerate_required_extensions(window)
-            .unwrap()
-            .to_vec();
+    fn create_instance(
+        entry: &super::Entry,
+        window: Option<&winit::window::Window>,
+    ) -> super::Result<ash::Instance> {
+        let extensions = match window {
+            Some(window) => ash_window::enumerate_required_extensions(window)?.to_vec(),
+            None => Vec::new(),
+        };
         #[cfg(debug_assertions)]
         let mut extensions = extensions;
         #[cfg(debug_assertions)]
         extensions.push(ash::extensions::ext::DebugUtils::name().as_ptr());
+        #[cfg(debug_assertions)]
+        let layers = Self::validation_layers(entry)?;
+        #[cfg(not(debug_assertions))]
+        let layers: Vec<*const std::os::raw::c_char> = Vec::new();
 
         unsafe {
             entry.entry.create_instance(
                 &vk::InstanceCreateInfo {
-                    #[cfg(debug_assertions)]
-                    enabled_layer_count: 1,
-                    #[cfg(debug_assertions)]
-                    pp_enabled_layer_names: [b"VK_LAYER_KHRONOS_validation\0".as_ptr().cast()]
-                        .as_ptr(),
+                    enabled_layer_count: layers.len() as _,
+                    pp_enabled_layer_names: layers.as_ptr(),
                     enabled_extension_count: extensions.len() as _,
                     pp_enabled_extension_names: extensions.as_ptr(),
                     ..Default::default()
@@ -35,7 +56,30 @@ impl Instance {
                 None,
             )
         }
-        .unwrap()
+        .map_err(|result| match result {
+            vk::Result::ERROR_INCOMPATIBLE_DRIVER => super::RendererError::NoSuitablePhysicalDevice,
+            result => result.into(),
+        })
+    }
+
+    #[cfg(debug_assertions)]
+    fn validation_layers(entry: &super::Entry) -> super::Result<Vec<*const std::os::raw::c_char>> {
+        const VALIDATION_LAYER: &[u8] = b"VK_LAYER_KHRONOS_validation\0";
+
+        let available = entry
+            .entry
+            .enumerate_instance_layer_properties()?
+            .iter()
+            .any(|layer| {
+                unsafe { std::ffi::CStr::from_ptr(layer.layer_name.as_ptr()) }.to_bytes_with_nul()
+                    == VALIDATION_LAYER
+            });
+        if available {
+            Ok(vec![VALIDATION_LAYER.as_ptr().cast()])
+        } else {
+            log::warn!("VK_LAYER_KHRONOS_validation is not available, continuing without it");
+            Ok(Vec::new())
+        }
     }
 }
 
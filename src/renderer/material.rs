@@ -0,0 +1,148 @@
+use super::{DescriptorSetLayout, Device, Texture};
+use ash::vk;
+
+/// How a material's alpha is interpreted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    /// Fragments with an alpha below the cutoff are discarded.
+    Mask(f32),
+    Blend,
+}
+
+/// A metallic-roughness material, with texture slots indexing the model's textures.
+///
+/// Textures are sampled with the first texture coordinate set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaterialData {
+    pub name: Option<String>,
+    pub base_color_factor: [f32; 4],
+    pub base_color_texture: Option<usize>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    /// Roughness in the green channel and metalness in the blue one.
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<usize>,
+    pub occlusion_strength: f32,
+    pub emissive_factor: [f32; 3],
+    pub emissive_texture: Option<usize>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+}
+
+impl MaterialData {
+    /// The texture slots holding data rather than color, which must not be sampled as sRGB.
+    pub fn linear_textures(&self) -> impl Iterator<Item = usize> {
+        IntoIterator::into_iter([
+            self.metallic_roughness_texture,
+            self.normal_texture,
+            self.occlusion_texture,
+        ])
+        .flatten()
+    }
+}
+
+impl Default for MaterialData {
+    /// The glTF default material: white, fully metallic and fully rough.
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color_factor: [1.0; 4],
+            base_color_texture: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive_factor: [0.0; 3],
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+}
+
+/// A material on the device, with the descriptor set binding its base color texture.
+pub struct Material {
+    pub data: MaterialData,
+    pub descriptor_set: vk::DescriptorSet,
+}
+
+impl Material {
+    /// Allocates the material's set of `descriptor_set_layout` from `descriptor_pool`.
+    pub fn new(
+        device: &Device,
+        descriptor_pool: vk::DescriptorPool,
+        descriptor_set_layout: &DescriptorSetLayout,
+        data: MaterialData,
+        base_color_texture: &Texture,
+    ) -> super::Result<Self> {
+        let descriptor_set = descriptor_set_layout.allocate(device, descriptor_pool)?;
+        let material = Self {
+            data,
+            descriptor_set,
+        };
+        material.set_base_color_texture(device, descriptor_set_layout, base_color_texture);
+        Ok(material)
+    }
+
+    /// Binds a different base color texture to every image and sampler binding of the set,
+    /// which must not be in use by pending frames.
+    pub fn set_base_color_texture(
+        &self,
+        device: &Device,
+        descriptor_set_layout: &DescriptorSetLayout,
+        texture: &Texture,
+    ) {
+        // Sampled image writes ignore the sampler and sampler writes the view.
+        let image_info = vk::DescriptorImageInfo {
+            sampler: texture.sampler,
+            image_view: texture.image.view,
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        };
+        let image_infos = descriptor_set_layout
+            .bindings
+            .iter()
+            .map(|binding| vec![image_info; binding.descriptor_count as usize])
+            .collect::<Vec<_>>();
+        let writes = descriptor_set_layout
+            .bindings
+            .iter()
+            .zip(&image_infos)
+            .map(|(binding, image_infos)| vk::WriteDescriptorSet {
+                dst_set: self.descriptor_set,
+                dst_binding: binding.binding,
+                dst_array_element: 0,
+                descriptor_count: binding.descriptor_count,
+                descriptor_type: binding.descriptor_type,
+                p_image_info: image_infos.as_ptr(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        unsafe { device.device.update_descriptor_sets(&writes, &[]) };
+    }
+
+    /// Checks that the material set only has bindings the base color texture can fill.
+    pub fn check_set_layout_bindings(
+        bindings: &[vk::DescriptorSetLayoutBinding],
+    ) -> super::Result<()> {
+        match bindings.iter().find(|binding| {
+            !matches!(
+                binding.descriptor_type,
+                vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+                    | vk::DescriptorType::SAMPLED_IMAGE
+                    | vk::DescriptorType::SAMPLER
+            )
+        }) {
+            Some(binding) => Err(super::RendererError::ShaderReflection(format!(
+                "Material binding {} is a {:?}, but materials only bind textures and samplers",
+                binding.binding, binding.descriptor_type
+            ))),
+            None => Ok(()),
+        }
+    }
+}
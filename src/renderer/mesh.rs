@@ -0,0 +1,271 @@
+use super::{Aabb, Device, GpuBuffer, IndexBuffer, MeshVertex, Transfer, UploadHandle};
+use ash::vk;
+
+/// Triangle list indices in the narrowest type the loader could use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    /// Narrows the indices to u16 when every vertex can be addressed with them.
+    pub fn from_u32(indices: Vec<u32>, vertex_count: usize) -> Self {
+        if vertex_count <= u16::MAX as usize + 1 {
+            Self::U16(indices.into_iter().map(|index| index as u16).collect())
+        } else {
+            Self::U32(indices)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn to_u32(&self) -> Vec<u32> {
+        match self {
+            Self::U16(indices) => indices.iter().map(|&index| index as u32).collect(),
+            Self::U32(indices) => indices.clone(),
+        }
+    }
+}
+
+/// Indexed triangles drawn with a single material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrimitiveData {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Indices,
+    /// Index into the model's materials, or `None` for the default material.
+    pub material: Option<usize>,
+}
+
+/// How normals are generated for meshes that don't provide them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NormalGeneration {
+    /// Every triangle gets its own vertices, facing along the triangle's normal.
+    Flat,
+    /// Vertices at the same position share the area-weighted normal of their triangles.
+    #[default]
+    Smooth,
+}
+
+impl PrimitiveData {
+    /// Indexes a triangle list, merging corners with identical attributes into one vertex.
+    pub fn from_triangles<I: IntoIterator<Item = MeshVertex>>(
+        corners: I,
+        material: Option<usize>,
+    ) -> Self {
+        let mut vertices = Vec::new();
+        let mut vertex_indices = std::collections::HashMap::new();
+        let indices = corners
+            .into_iter()
+            .map(|corner| {
+                let key: [u8; std::mem::size_of::<MeshVertex>()] = bytemuck::cast(corner);
+                *vertex_indices.entry(key).or_insert_with(|| {
+                    vertices.push(corner);
+                    vertices.len() as u32 - 1
+                })
+            })
+            .collect();
+        let vertex_count = vertices.len();
+        Self {
+            vertices,
+            indices: Indices::from_u32(indices, vertex_count),
+            material,
+        }
+    }
+
+    pub fn generate_normals(&mut self, generation: NormalGeneration) {
+        match generation {
+            NormalGeneration::Flat => self.generate_flat_normals(),
+            NormalGeneration::Smooth => self.generate_smooth_normals(),
+        }
+    }
+
+    /// Averages the normals of the triangles around each position, weighted by their area.
+    ///
+    /// Vertices split by other attributes, such as texture seams, still get the same normal.
+    pub fn generate_smooth_normals(&mut self) {
+        use cgmath::InnerSpace;
+
+        let position_key = |vertex: &MeshVertex| vertex.position.map(f32::to_bits);
+        let mut normals = std::collections::HashMap::new();
+        for triangle in self.indices.to_u32().chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| self.vertices[triangle[corner] as usize]);
+            let (a_position, b_position, c_position) = (
+                cgmath::Vector3::from(a.position),
+                cgmath::Vector3::from(b.position),
+                cgmath::Vector3::from(c.position),
+            );
+            // The cross product's length is twice the triangle's area.
+            let normal = (b_position - a_position).cross(c_position - a_position);
+            for vertex in [a, b, c] {
+                *normals
+                    .entry(position_key(&vertex))
+                    .or_insert(cgmath::vec3(0.0, 0.0, 0.0)) += normal;
+            }
+        }
+        for vertex in &mut self.vertices {
+            vertex.normal = match normals.get(&position_key(vertex)) {
+                Some(normal) if normal.magnitude2() > 0.0 => normal.normalize().into(),
+                _ => [0.0; 3],
+            };
+        }
+    }
+
+    /// Gives every triangle its own vertices, all facing along the triangle's normal.
+    pub fn generate_flat_normals(&mut self) {
+        let indices = self.indices.to_u32();
+        self.vertices = indices
+            .chunks_exact(3)
+            .flat_map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|corner| self.vertices[triangle[corner] as usize]);
+                let normal = face_normal(a.position, b.position, c.position);
+                [a, b, c].map(|vertex| MeshVertex { normal, ..vertex })
+            })
+            .collect();
+        let indices = (0..self.vertices.len() as u32).collect();
+        self.indices = match self.indices {
+            Indices::U16(_) => Indices::from_u32(indices, self.vertices.len()),
+            Indices::U32(_) => Indices::U32(indices),
+        };
+    }
+}
+
+/// The unit normal of a counter-clockwise triangle, or zero for a degenerate one.
+fn face_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    use cgmath::InnerSpace;
+
+    let (a, b, c) = (
+        cgmath::Vector3::from(a),
+        cgmath::Vector3::from(b),
+        cgmath::Vector3::from(c),
+    );
+    let normal = (b - a).cross(c - a);
+    if normal.magnitude2() > 0.0 {
+        normal.normalize().into()
+    } else {
+        [0.0; 3]
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshData {
+    pub name: Option<String>,
+    pub primitives: Vec<PrimitiveData>,
+}
+
+impl MeshData {
+    /// The box around every vertex of every primitive, or `None` for an empty mesh.
+    pub fn bounds(&self) -> Option<Aabb> {
+        Aabb::from_points(
+            self.primitives
+                .iter()
+                .flat_map(|primitive| &primitive.vertices)
+                .map(|vertex| vertex.position.into()),
+        )
+    }
+}
+
+/// A primitive's vertex and index data on the device.
+pub struct Primitive {
+    pub vertex_buffer: GpuBuffer<MeshVertex>,
+    pub index_buffer: IndexBuffer,
+    pub material: Option<usize>,
+}
+
+impl Primitive {
+    pub fn new(
+        device: &Device,
+        transfer: &mut Transfer,
+        data: &PrimitiveData,
+    ) -> super::Result<Self> {
+        if data.vertices.is_empty() || data.indices.is_empty() {
+            return Err(super::RendererError::EmptyPrimitive {
+                vertices: data.vertices.len(),
+                indices: data.indices.len(),
+            });
+        }
+        let vertex_buffer = GpuBuffer::new(
+            device,
+            transfer,
+            &data.vertices,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+        )?;
+        let index_buffer = match &data.indices {
+            Indices::U16(indices) => IndexBuffer::new(device, transfer, indices),
+            Indices::U32(indices) => IndexBuffer::new(device, transfer, indices),
+        };
+        let index_buffer = match index_buffer {
+            Ok(index_buffer) => index_buffer,
+            Err(error) => {
+                // The vertex buffer's upload may already be recorded.
+                transfer.finish(device);
+                vertex_buffer.destroy(device);
+                return Err(error);
+            }
+        };
+        Ok(Self {
+            vertex_buffer,
+            index_buffer,
+            material: data.material,
+        })
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        self.index_buffer.destory(device);
+        self.vertex_buffer.destroy(device);
+    }
+}
+
+pub struct Mesh {
+    pub name: Option<String>,
+    pub primitives: Vec<Primitive>,
+    pub bounds: Option<Aabb>,
+}
+
+impl Mesh {
+    pub fn new(device: &Device, transfer: &mut Transfer, data: &MeshData) -> super::Result<Self> {
+        let mut primitives = Vec::with_capacity(data.primitives.len());
+        for primitive in &data.primitives {
+            match Primitive::new(device, transfer, primitive) {
+                Ok(primitive) => primitives.push(primitive),
+                Err(error) => {
+                    transfer.finish(device);
+                    primitives
+                        .iter()
+                        .for_each(|primitive| primitive.destroy(device));
+                    return Err(error);
+                }
+            }
+        }
+        Ok(Self {
+            name: data.name.clone(),
+            primitives,
+            bounds: data.bounds(),
+        })
+    }
+
+    /// The uploads that must complete before the mesh can be drawn.
+    pub fn uploads(&self) -> impl Iterator<Item = UploadHandle> + '_ {
+        self.primitives.iter().flat_map(|primitive| {
+            [
+                primitive.vertex_buffer.upload,
+                primitive.index_buffer.upload,
+            ]
+        })
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        self.primitives
+            .iter()
+            .for_each(|primitive| primitive.destroy(device));
+    }
+}
@@ -0,0 +1,601 @@
+use super::{Device, Image, ShaderCompiler, ShaderStage};
+use ash::vk;
+use std::path::Path;
+
+const SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/mipmap.comp");
+
+/// Formats the compute downsample shader can write, with the storage image format qualifier it is
+/// compiled with for each.
+///
+/// These need no extended storage formats. sRGB formats can't be storage images, and writing them
+/// through a UNORM view would need `EXTENDED_USAGE` from Vulkan 1.1, so they get no compute path.
+const COMPUTE_FORMATS: [(vk::Format, &str); 5] = [
+    (vk::Format::R8G8B8A8_UNORM, "rgba8"),
+    (vk::Format::R8G8B8A8_SNORM, "rgba8_snorm"),
+    (vk::Format::R16G16B16A16_SFLOAT, "rgba16f"),
+    (vk::Format::R32_SFLOAT, "r32f"),
+    (vk::Format::R32G32B32A32_SFLOAT, "rgba32f"),
+];
+
+/// How the levels below the uploaded base level of an image are filled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MipGeneration {
+    /// The upload fills every level itself.
+    None,
+    /// Each level is blitted from the one above with linear filtering.
+    Blit,
+    /// Each level is averaged from the one above by a compute shader, for formats that can't
+    /// be filtered linearly.
+    Compute,
+}
+
+impl MipGeneration {
+    /// Picks how mips of `format` can be generated, or `None` if they can't be.
+    pub fn select(
+        instance: &super::Instance,
+        device: &Device,
+        format: vk::Format,
+    ) -> MipGeneration {
+        let features = instance.format_features(device.physical_device, format);
+        if features.contains(
+            vk::FormatFeatureFlags::BLIT_SRC
+                | vk::FormatFeatureFlags::BLIT_DST
+                | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+        ) {
+            MipGeneration::Blit
+        } else if Downsampler::supports(format)
+            && features.contains(
+                vk::FormatFeatureFlags::SAMPLED_IMAGE | vk::FormatFeatureFlags::STORAGE_IMAGE,
+            )
+        {
+            MipGeneration::Compute
+        } else {
+            MipGeneration::None
+        }
+    }
+
+    /// Extra usage the destination image needs for this kind of generation.
+    pub fn image_usage(self) -> vk::ImageUsageFlags {
+        match self {
+            MipGeneration::None => vk::ImageUsageFlags::empty(),
+            MipGeneration::Blit => vk::ImageUsageFlags::TRANSFER_SRC,
+            MipGeneration::Compute => vk::ImageUsageFlags::STORAGE,
+        }
+    }
+}
+
+/// The parts of an image needed to record its mip generation after the image is handed off.
+#[derive(Clone, Copy)]
+pub struct MipChain {
+    pub image: vk::Image,
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+    pub mip_levels: u32,
+}
+
+impl MipChain {
+    pub fn of(image: &Image) -> Self {
+        Self {
+            image: image.image,
+            format: image.format,
+            extent: image.extent,
+            mip_levels: image.mip_levels,
+        }
+    }
+}
+
+/// The number of levels in a full mip chain down to 1x1.
+pub fn mip_levels(extent: vk::Extent2D) -> u32 {
+    32 - extent.width.max(extent.height).max(1).leading_zeros()
+}
+
+fn mip_extent(extent: vk::Extent2D, level: u32) -> vk::Extent2D {
+    vk::Extent2D {
+        width: (extent.width >> level).max(1),
+        height: (extent.height >> level).max(1),
+    }
+}
+
+fn level_range(
+    image: &MipChain,
+    base_mip_level: u32,
+    level_count: u32,
+) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: super::image::aspect_mask(image.format),
+        base_mip_level,
+        level_count,
+        base_array_layer: 0,
+        layer_count: 1,
+    }
+}
+
+/// Fills every level below the base by blitting, leaving the image in `SHADER_READ_ONLY_OPTIMAL`.
+///
+/// All levels must be in `TRANSFER_DST_OPTIMAL` with the base level written by a transfer.
+pub fn record_blits(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: &MipChain,
+    dst_stage_mask: vk::PipelineStageFlags,
+    dst_access_mask: vk::AccessFlags,
+) {
+    let barrier = |level: u32,
+                   src_access_mask: vk::AccessFlags,
+                   dst_access_mask: vk::AccessFlags,
+                   old_layout: vk::ImageLayout,
+                   new_layout: vk::ImageLayout| vk::ImageMemoryBarrier {
+        src_access_mask,
+        dst_access_mask,
+        old_layout,
+        new_layout,
+        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        image: image.image,
+        subresource_range: level_range(image, level, 1),
+        ..Default::default()
+    };
+    let offset = |extent: vk::Extent2D| vk::Offset3D {
+        x: extent.width as _,
+        y: extent.height as _,
+        z: 1,
+    };
+    let layers = |mip_level: u32| vk::ImageSubresourceLayers {
+        aspect_mask: super::image::aspect_mask(image.format),
+        mip_level,
+        base_array_layer: 0,
+        layer_count: 1,
+    };
+
+    for level in 1..image.mip_levels {
+        unsafe {
+            device.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier(
+                    level - 1,
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    vk::AccessFlags::TRANSFER_READ,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                )],
+            );
+            device.device.cmd_blit_image(
+                command_buffer,
+                image.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                image.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[vk::ImageBlit {
+                    src_subresource: layers(level - 1),
+                    src_offsets: [
+                        vk::Offset3D::default(),
+                        offset(mip_extent(image.extent, level - 1)),
+                    ],
+                    dst_subresource: layers(level),
+                    dst_offsets: [
+                        vk::Offset3D::default(),
+                        offset(mip_extent(image.extent, level)),
+                    ],
+                }],
+                vk::Filter::LINEAR,
+            );
+            device.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                dst_stage_mask,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier(
+                    level - 1,
+                    vk::AccessFlags::TRANSFER_READ,
+                    dst_access_mask,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                )],
+            );
+        }
+    }
+    unsafe {
+        device.device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            dst_stage_mask,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[barrier(
+                image.mip_levels - 1,
+                vk::AccessFlags::TRANSFER_WRITE,
+                dst_access_mask,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )],
+        )
+    };
+}
+
+/// The compute pipelines that average one mip level into the next, one per storage format.
+pub struct Downsampler {
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    pipeline_layout: vk::PipelineLayout,
+    pipelines: Vec<(vk::Format, vk::Pipeline)>,
+}
+
+/// Views and descriptor sets used while recording a compute downsample, freed with its batch.
+#[derive(Default)]
+pub struct DownsampleResources {
+    descriptor_pool: vk::DescriptorPool,
+    views: Vec<vk::ImageView>,
+}
+
+impl DownsampleResources {
+    pub fn destroy(&self, device: &Device) {
+        unsafe {
+            self.views
+                .iter()
+                .for_each(|view| device.device.destroy_image_view(*view, None));
+            device
+                .device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
+
+impl Downsampler {
+    /// Whether mips of `format` can be downsampled at all, whatever the device supports.
+    pub fn supports(format: vk::Format) -> bool {
+        COMPUTE_FORMATS
+            .iter()
+            .any(|&(compute_format, _)| compute_format == format)
+    }
+
+    pub fn new(device: &Device) -> super::Result<Self> {
+        let mut compiler = ShaderCompiler::new();
+        let mut shaders = Vec::with_capacity(COMPUTE_FORMATS.len());
+        for &(format, qualifier) in &COMPUTE_FORMATS {
+            compiler
+                .defines
+                .insert("STORAGE_FORMAT".to_owned(), qualifier.to_owned());
+            let shader = compiler.compile_source(
+                include_str!("../../shaders/mipmap.comp"),
+                Path::new(SHADER_PATH),
+                ShaderStage::Compute,
+            )?;
+            shaders.push((format, shader));
+        }
+        let mut downsampler = Self {
+            descriptor_set_layout: vk::DescriptorSetLayout::null(),
+            pipeline_layout: vk::PipelineLayout::null(),
+            pipelines: Vec::new(),
+        };
+        match downsampler.create(device, &shaders) {
+            Ok(()) => Ok(downsampler),
+            Err(error) => {
+                downsampler.destroy(device);
+                Err(error)
+            }
+        }
+    }
+
+    fn create(&mut self, device: &Device, shaders: &[(vk::Format, Vec<u32>)]) -> super::Result<()> {
+        self.descriptor_set_layout = unsafe {
+            device.device.create_descriptor_set_layout(
+                &vk::DescriptorSetLayoutCreateInfo {
+                    binding_count: 2,
+                    p_bindings: [
+                        vk::DescriptorSetLayoutBinding {
+                            binding: 0,
+                            descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
+                            descriptor_count: 1,
+                            stage_flags: vk::ShaderStageFlags::COMPUTE,
+                            ..Default::default()
+                        },
+                        vk::DescriptorSetLayoutBinding {
+                            binding: 1,
+                            descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+                            descriptor_count: 1,
+                            stage_flags: vk::ShaderStageFlags::COMPUTE,
+                            ..Default::default()
+                        },
+                    ]
+                    .as_ptr(),
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+        self.pipeline_layout = unsafe {
+            device.device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo {
+                    set_layout_count: 1,
+                    p_set_layouts: &self.descriptor_set_layout,
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+
+        for (format, shader) in shaders {
+            let shader_module = unsafe {
+                device.device.create_shader_module(
+                    &vk::ShaderModuleCreateInfo {
+                        code_size: std::mem::size_of_val(shader.as_slice()),
+                        p_code: shader.as_ptr(),
+                        ..Default::default()
+                    },
+                    None,
+                )
+            }
+            .map_err(super::RendererError::ShaderModule)?;
+            let pipelines = unsafe {
+                device.device.create_compute_pipelines(
+                    device.pipeline_cache.cache,
+                    &[vk::ComputePipelineCreateInfo {
+                        stage: vk::PipelineShaderStageCreateInfo {
+                            stage: vk::ShaderStageFlags::COMPUTE,
+                            module: shader_module,
+                            p_name: b"main\0".as_ptr().cast(),
+                            ..Default::default()
+                        },
+                        layout: self.pipeline_layout,
+                        ..Default::default()
+                    }],
+                    None,
+                )
+            };
+            unsafe { device.device.destroy_shader_module(shader_module, None) };
+            self.pipelines
+                .push((*format, pipelines.map_err(|(_, result)| result)?[0]));
+        }
+        Ok(())
+    }
+
+    fn pipeline(&self, format: vk::Format) -> super::Result<vk::Pipeline> {
+        self.pipelines
+            .iter()
+            .find(|&&(pipeline_format, _)| pipeline_format == format)
+            .map(|&(_, pipeline)| pipeline)
+            .ok_or(super::RendererError::MipGenerationUnsupported(format))
+    }
+
+    /// Fills every level below the base with a compute pass per level, leaving the image in
+    /// `SHADER_READ_ONLY_OPTIMAL`.
+    ///
+    /// All levels must be in `TRANSFER_DST_OPTIMAL` with the base level written by a transfer.
+    /// The final layout transition releases the image between `queue_family_indices`, which
+    /// are both `QUEUE_FAMILY_IGNORED` when it stays on this queue.
+    pub fn record(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        image: &MipChain,
+        dst_stage_mask: vk::PipelineStageFlags,
+        dst_access_mask: vk::AccessFlags,
+        queue_family_indices: (u32, u32),
+    ) -> super::Result<DownsampleResources> {
+        let mut resources = DownsampleResources::default();
+        match self.record_levels(
+            device,
+            command_buffer,
+            image,
+            dst_stage_mask,
+            dst_access_mask,
+            queue_family_indices,
+            &mut resources,
+        ) {
+            Ok(()) => Ok(resources),
+            Err(error) => {
+                resources.destroy(device);
+                Err(error)
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record_levels(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        image: &MipChain,
+        dst_stage_mask: vk::PipelineStageFlags,
+        dst_access_mask: vk::AccessFlags,
+        queue_family_indices: (u32, u32),
+        resources: &mut DownsampleResources,
+    ) -> super::Result<()> {
+        let pipeline = self.pipeline(image.format)?;
+        let pass_count = image.mip_levels - 1;
+        resources.descriptor_pool = unsafe {
+            device.device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo {
+                    max_sets: pass_count,
+                    pool_size_count: 2,
+                    p_pool_sizes: [
+                        vk::DescriptorPoolSize {
+                            ty: vk::DescriptorType::SAMPLED_IMAGE,
+                            descriptor_count: pass_count,
+                        },
+                        vk::DescriptorPoolSize {
+                            ty: vk::DescriptorType::STORAGE_IMAGE,
+                            descriptor_count: pass_count,
+                        },
+                    ]
+                    .as_ptr(),
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+        for level in 0..image.mip_levels {
+            let view = unsafe {
+                device.device.create_image_view(
+                    &vk::ImageViewCreateInfo {
+                        image: image.image,
+                        view_type: vk::ImageViewType::TYPE_2D,
+                        format: image.format,
+                        subresource_range: level_range(image, level, 1),
+                        ..Default::default()
+                    },
+                    None,
+                )
+            }?;
+            resources.views.push(view);
+        }
+        let set_layouts = vec![self.descriptor_set_layout; pass_count as usize];
+        let descriptor_sets = unsafe {
+            device
+                .device
+                .allocate_descriptor_sets(&vk::DescriptorSetAllocateInfo {
+                    descriptor_pool: resources.descriptor_pool,
+                    descriptor_set_count: pass_count,
+                    p_set_layouts: set_layouts.as_ptr(),
+                    ..Default::default()
+                })
+        }?;
+        let image_infos = resources
+            .views
+            .iter()
+            .map(|view| vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view: *view,
+                image_layout: vk::ImageLayout::GENERAL,
+            })
+            .collect::<Vec<_>>();
+        let writes = descriptor_sets
+            .iter()
+            .enumerate()
+            .flat_map(|(pass, descriptor_set)| {
+                [
+                    vk::WriteDescriptorSet {
+                        dst_set: *descriptor_set,
+                        dst_binding: 0,
+                        descriptor_count: 1,
+                        descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
+                        p_image_info: &image_infos[pass],
+                        ..Default::default()
+                    },
+                    vk::WriteDescriptorSet {
+                        dst_set: *descriptor_set,
+                        dst_binding: 1,
+                        descriptor_count: 1,
+                        descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+                        p_image_info: &image_infos[pass + 1],
+                        ..Default::default()
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+        unsafe { device.device.update_descriptor_sets(&writes, &[]) };
+
+        let layout_barrier =
+            |src_access_mask: vk::AccessFlags,
+             dst_access_mask: vk::AccessFlags,
+             old_layout: vk::ImageLayout,
+             new_layout: vk::ImageLayout| vk::ImageMemoryBarrier {
+                src_access_mask,
+                dst_access_mask,
+                old_layout,
+                new_layout,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                image: image.image,
+                subresource_range: level_range(image, 0, image.mip_levels),
+                ..Default::default()
+            };
+        unsafe {
+            device.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[layout_barrier(
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::ImageLayout::GENERAL,
+                )],
+            );
+            device.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline,
+            );
+        }
+        for (pass, descriptor_set) in descriptor_sets.iter().enumerate() {
+            let extent = mip_extent(image.extent, pass as u32 + 1);
+            unsafe {
+                if pass > 0 {
+                    device.device.cmd_pipeline_barrier(
+                        command_buffer,
+                        vk::PipelineStageFlags::COMPUTE_SHADER,
+                        vk::PipelineStageFlags::COMPUTE_SHADER,
+                        vk::DependencyFlags::empty(),
+                        &[vk::MemoryBarrier {
+                            src_access_mask: vk::AccessFlags::SHADER_WRITE,
+                            dst_access_mask: vk::AccessFlags::SHADER_READ,
+                            ..Default::default()
+                        }],
+                        &[],
+                        &[],
+                    );
+                }
+                device.device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::COMPUTE,
+                    self.pipeline_layout,
+                    0,
+                    &[*descriptor_set],
+                    &[],
+                );
+                device.device.cmd_dispatch(
+                    command_buffer,
+                    extent.width.div_ceil(8),
+                    extent.height.div_ceil(8),
+                    1,
+                );
+            }
+        }
+        unsafe {
+            device.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                dst_stage_mask,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_queue_family_index: queue_family_indices.0,
+                    dst_queue_family_index: queue_family_indices.1,
+                    ..layout_barrier(
+                        vk::AccessFlags::SHADER_WRITE,
+                        dst_access_mask,
+                        vk::ImageLayout::GENERAL,
+                        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    )
+                }],
+            )
+        };
+        Ok(())
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe {
+            self.pipelines
+                .iter()
+                .for_each(|(_, pipeline)| device.device.destroy_pipeline(*pipeline, None));
+            device
+                .device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            device
+                .device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+}
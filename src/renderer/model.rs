@@ -0,0 +1,207 @@
+use super::{
+    gltf_loader, obj_loader, ply_loader, DescriptorSetLayout, Device, Instance, Material,
+    MaterialData, Mesh, MeshData, NormalGeneration, Projection, SamplerConfig, Texture,
+    TextureData, Transfer, UploadHandle,
+};
+use ash::vk;
+
+/// An image paired with the sampler a material reads it through.
+#[derive(Clone, Copy, Debug)]
+pub struct TextureSource {
+    /// Index into the model's images.
+    pub image: usize,
+    pub sampler: SamplerConfig,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeData {
+    pub name: Option<String>,
+    /// The transform relative to the parent node.
+    pub transform: cgmath::Matrix4<f32>,
+    /// Index into the model's meshes.
+    pub mesh: Option<usize>,
+    /// A camera looking down the node's negative Z axis.
+    pub camera: Option<Projection>,
+    /// Indices into the model's nodes.
+    pub children: Vec<usize>,
+}
+
+/// Meshes, materials and the node hierarchy placing them, as read from a model file.
+#[derive(Default)]
+pub struct ModelData {
+    pub meshes: Vec<MeshData>,
+    pub materials: Vec<MaterialData>,
+    pub textures: Vec<TextureSource>,
+    pub images: Vec<TextureData>,
+    pub nodes: Vec<NodeData>,
+    /// The nodes at the top of the displayed scene.
+    pub roots: Vec<usize>,
+}
+
+impl ModelData {
+    /// Loads a model, picking the loader from the file extension.
+    ///
+    /// Meshes without normals get smooth ones, except in glTF files, which ask for flat ones.
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> super::Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("gltf") | Some("glb") => Self::from_gltf(&std::fs::read(path)?, path.parent()),
+            Some("obj") => Self::from_obj(
+                &std::fs::read(path)?,
+                path.parent(),
+                NormalGeneration::default(),
+            ),
+            Some("ply") => Self::from_ply(&std::fs::read(path)?, NormalGeneration::default()),
+            _ => Err(super::RendererError::ModelLoad(format!(
+                "Unrecognized model file extension: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Loads a `.gltf` or `.glb` file's default scene.
+    ///
+    /// External buffers and images are resolved relative to `base_dir`; without one, only
+    /// embedded and data URI resources can be loaded.
+    pub fn from_gltf(bytes: &[u8], base_dir: Option<&std::path::Path>) -> super::Result<Self> {
+        gltf_loader::load(bytes, base_dir)
+    }
+
+    /// Loads a Wavefront `.obj` file with its MTL materials, which are resolved like glTF's
+    /// external resources.
+    pub fn from_obj(
+        bytes: &[u8],
+        base_dir: Option<&std::path::Path>,
+        normals: NormalGeneration,
+    ) -> super::Result<Self> {
+        obj_loader::load(bytes, base_dir, normals)
+    }
+
+    /// Loads an ASCII or binary `.ply` file as a single mesh colored by its vertices.
+    pub fn from_ply(bytes: &[u8], normals: NormalGeneration) -> super::Result<Self> {
+        ply_loader::load(bytes, normals)
+    }
+}
+
+/// A model's meshes, textures and materials on the device, with its nodes alongside.
+pub struct Model {
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub textures: Vec<Texture>,
+    pub nodes: Vec<NodeData>,
+    pub roots: Vec<usize>,
+    pub descriptor_pool: vk::DescriptorPool,
+}
+
+impl Model {
+    /// Materials without a base color texture sample `fallback_texture`, which must outlive
+    /// the model.
+    pub fn new(
+        instance: &Instance,
+        device: &Device,
+        transfer: &mut Transfer,
+        data: &ModelData,
+        material_set_layout: &DescriptorSetLayout,
+        fallback_texture: &Texture,
+    ) -> super::Result<Self> {
+        let mut model = Self {
+            meshes: Vec::with_capacity(data.meshes.len()),
+            materials: Vec::with_capacity(data.materials.len()),
+            textures: Vec::with_capacity(data.textures.len()),
+            nodes: data.nodes.clone(),
+            roots: data.roots.clone(),
+            descriptor_pool: vk::DescriptorPool::null(),
+        };
+        for mesh in &data.meshes {
+            match Mesh::new(device, transfer, mesh) {
+                Ok(mesh) => model.meshes.push(mesh),
+                Err(error) => {
+                    model.destroy_unfinished(device, transfer);
+                    return Err(error);
+                }
+            }
+        }
+        for texture in &data.textures {
+            match Texture::new(
+                instance,
+                device,
+                transfer,
+                &data.images[texture.image],
+                &texture.sampler,
+            ) {
+                Ok(texture) => model.textures.push(texture),
+                Err(error) => {
+                    model.destroy_unfinished(device, transfer);
+                    return Err(error);
+                }
+            }
+        }
+        if let Err(error) =
+            model.create_materials(device, data, material_set_layout, fallback_texture)
+        {
+            model.destroy_unfinished(device, transfer);
+            return Err(error);
+        }
+        Ok(model)
+    }
+
+    fn create_materials(
+        &mut self,
+        device: &Device,
+        data: &ModelData,
+        material_set_layout: &DescriptorSetLayout,
+        fallback_texture: &Texture,
+    ) -> super::Result<()> {
+        if data.materials.is_empty() {
+            return Ok(());
+        }
+        self.descriptor_pool = DescriptorSetLayout::create_pool(
+            device,
+            &[(material_set_layout, data.materials.len() as u32)],
+        )?;
+        for material in &data.materials {
+            let base_color_texture = material
+                .base_color_texture
+                .and_then(|texture| self.textures.get(texture))
+                .unwrap_or(fallback_texture);
+            self.materials.push(Material::new(
+                device,
+                self.descriptor_pool,
+                material_set_layout,
+                material.clone(),
+                base_color_texture,
+            )?);
+        }
+        Ok(())
+    }
+
+    /// The uploads that must complete before the model can be drawn.
+    pub fn uploads(&self) -> impl Iterator<Item = UploadHandle> + '_ {
+        self.meshes
+            .iter()
+            .flat_map(Mesh::uploads)
+            .chain(self.textures.iter().map(|texture| texture.upload))
+    }
+
+    /// Destroys a partly created model once the uploads recorded for it have executed.
+    fn destroy_unfinished(&self, device: &Device, transfer: &mut Transfer) {
+        transfer.finish(device);
+        self.destroy(device);
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe {
+            device
+                .device
+                .destroy_descriptor_pool(self.descriptor_pool, None)
+        };
+        self.textures
+            .iter()
+            .for_each(|texture| texture.destroy(device));
+        self.meshes.iter().for_each(|mesh| mesh.destroy(device));
+    }
+}
@@ -0,0 +1,176 @@
+use super::{
+    AlphaMode, MaterialData, MeshData, MeshVertex, ModelData, NodeData, NormalGeneration,
+    PrimitiveData, SamplerConfig, TextureData, TextureSource,
+};
+use ash::vk;
+
+fn load_error(message: impl std::fmt::Display) -> super::RendererError {
+    super::RendererError::ModelLoad(message.to_string())
+}
+
+/// Reads a Wavefront OBJ file and the MTL libraries it references, one mesh per object.
+///
+/// Polygons are triangulated as fans and points and lines are skipped.
+pub fn load(
+    bytes: &[u8],
+    base_dir: Option<&std::path::Path>,
+    normals: NormalGeneration,
+) -> super::Result<ModelData> {
+    let options = tobj::LoadOptions {
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+        ..Default::default()
+    };
+    let (objects, materials) = tobj::load_obj_buf(&mut &*bytes, &options, |path| match base_dir {
+        Some(base_dir) => tobj::load_mtl(base_dir.join(path)),
+        None => Err(tobj::LoadError::OpenFileFailed),
+    })
+    .map_err(load_error)?;
+    let materials = materials.map_err(|error| load_error(format!("MTL: {}", error)))?;
+
+    let mut model = ModelData::default();
+    let mut textures = std::collections::HashMap::new();
+    for material in &materials {
+        let mut load_texture = |path: Option<&String>, format: vk::Format| match path {
+            Some(path) => texture(&mut model, &mut textures, base_dir, path, format).map(Some),
+            None => Ok(None),
+        };
+        // `norm` and `map_Ke` come from the PBR extension to MTL.
+        let material = MaterialData {
+            base_color_texture: load_texture(
+                material.diffuse_texture.as_ref(),
+                vk::Format::R8G8B8A8_SRGB,
+            )?,
+            normal_texture: load_texture(
+                material
+                    .normal_texture
+                    .as_ref()
+                    .or_else(|| material.unknown_param.get("norm")),
+                vk::Format::R8G8B8A8_UNORM,
+            )?,
+            emissive_texture: load_texture(
+                material.unknown_param.get("map_Ke"),
+                vk::Format::R8G8B8A8_SRGB,
+            )?,
+            ..self::material(material)
+        };
+        model.materials.push(material);
+    }
+
+    for object in &objects {
+        let mesh = &object.mesh;
+        if mesh.indices.is_empty() {
+            continue;
+        }
+        let corners = mesh.indices.iter().enumerate().map(|(corner, &index)| {
+            let index = index as usize;
+            let mut vertex = MeshVertex {
+                position: vec3(&mesh.positions, index),
+                color: [1.0; 4],
+                ..Default::default()
+            };
+            if mesh.vertex_color.len() == mesh.positions.len() {
+                let [red, green, blue] = vec3(&mesh.vertex_color, index);
+                vertex.color = [red, green, blue, 1.0];
+            }
+            if let Some(&normal) = mesh.normal_indices.get(corner) {
+                vertex.normal = vec3(&mesh.normals, normal as usize);
+            }
+            // OBJ puts the texture origin at the bottom left, Vulkan at the top left.
+            if let Some(&tex_coord) = mesh.texcoord_indices.get(corner) {
+                let tex_coord = tex_coord as usize * 2;
+                vertex.tex_coord = [
+                    mesh.texcoords[tex_coord],
+                    1.0 - mesh.texcoords[tex_coord + 1],
+                ];
+            }
+            vertex
+        });
+        let mut primitive = PrimitiveData::from_triangles(corners, mesh.material_id);
+        if mesh.normal_indices.is_empty() {
+            primitive.generate_normals(normals);
+        }
+
+        model.roots.push(model.nodes.len());
+        model.nodes.push(NodeData {
+            name: Some(object.name.clone()),
+            transform: cgmath::SquareMatrix::identity(),
+            mesh: Some(model.meshes.len()),
+            camera: None,
+            children: Vec::new(),
+        });
+        model.meshes.push(MeshData {
+            name: Some(object.name.clone()),
+            primitives: vec![primitive],
+        });
+    }
+    Ok(model)
+}
+
+fn vec3(values: &[f32], index: usize) -> [f32; 3] {
+    [
+        values[index * 3],
+        values[index * 3 + 1],
+        values[index * 3 + 2],
+    ]
+}
+
+/// Loads the image at `path` once, returning the texture sampling it.
+fn texture(
+    model: &mut ModelData,
+    textures: &mut std::collections::HashMap<(String, vk::Format), usize>,
+    base_dir: Option<&std::path::Path>,
+    path: &str,
+    format: vk::Format,
+) -> super::Result<usize> {
+    if let Some(&texture) = textures.get(&(path.to_string(), format)) {
+        return Ok(texture);
+    }
+    let base_dir =
+        base_dir.ok_or_else(|| load_error(format!("No directory to resolve {} against", path)))?;
+    let mut image = TextureData::from_file(base_dir.join(path))?;
+    // Decoded 8-bit images default to sRGB, which would distort normal maps.
+    if image.format == vk::Format::R8G8B8A8_SRGB {
+        image.format = format;
+    }
+    model.textures.push(TextureSource {
+        image: model.images.len(),
+        sampler: SamplerConfig::default(),
+    });
+    model.images.push(image);
+    textures.insert((path.to_string(), format), model.textures.len() - 1);
+    Ok(model.textures.len() - 1)
+}
+
+/// The material's factors; its textures are loaded separately.
+fn material(material: &tobj::Material) -> MaterialData {
+    let scalar = |key: &str| {
+        material
+            .unknown_param
+            .get(key)
+            .and_then(|value| value.trim().parse::<f32>().ok())
+    };
+    let [red, green, blue] = material.diffuse.unwrap_or([1.0; 3]);
+    let alpha = material.dissolve.unwrap_or(1.0);
+    MaterialData {
+        name: Some(material.name.clone()),
+        base_color_factor: [red, green, blue, alpha],
+        // Plain MTL has no metalness, and its Phong exponent maps to a similarly sharp roughness.
+        metallic_factor: scalar("Pm").unwrap_or(0.0),
+        roughness_factor: scalar("Pr")
+            .or_else(|| {
+                material
+                    .shininess
+                    .map(|shininess| (2.0 / (shininess.max(0.0) + 2.0)).sqrt())
+            })
+            .unwrap_or(1.0),
+        emissive_factor: material.emissive.unwrap_or([0.0; 3]),
+        alpha_mode: if alpha < 1.0 {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        },
+        ..Default::default()
+    }
+}
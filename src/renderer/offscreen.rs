@@ -0,0 +1,110 @@
+use super::{Allocation, AllocationStrategy, Device, ResourceKind};
+use ash::vk;
+
+pub struct Offscreen {
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+    pub images: Vec<vk::Image>,
+    pub allocations: Vec<Allocation>,
+    pub image_views: Vec<vk::ImageView>,
+}
+
+impl Offscreen {
+    pub const FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+    pub fn new(device: &Device, extent: vk::Extent2D, image_count: usize) -> super::Result<Self> {
+        let mut offscreen = Self {
+            format: Self::FORMAT,
+            extent,
+            images: Vec::with_capacity(image_count),
+            allocations: Vec::with_capacity(image_count),
+            image_views: Vec::with_capacity(image_count),
+        };
+        match (0..image_count).try_for_each(|_| offscreen.create_image(device)) {
+            Ok(()) => Ok(offscreen),
+            Err(error) => {
+                offscreen.destroy(device);
+                Err(error)
+            }
+        }
+    }
+
+    fn create_image(&mut self, device: &Device) -> super::Result<()> {
+        let image = unsafe {
+            device.device.create_image(
+                &vk::ImageCreateInfo {
+                    image_type: vk::ImageType::TYPE_2D,
+                    format: self.format,
+                    extent: vk::Extent3D {
+                        width: self.extent.width,
+                        height: self.extent.height,
+                        depth: 1,
+                    },
+                    mip_levels: 1,
+                    array_layers: 1,
+                    samples: vk::SampleCountFlags::TYPE_1,
+                    tiling: vk::ImageTiling::OPTIMAL,
+                    usage: vk::ImageUsageFlags::COLOR_ATTACHMENT
+                        | vk::ImageUsageFlags::TRANSFER_SRC,
+                    sharing_mode: vk::SharingMode::EXCLUSIVE,
+                    initial_layout: vk::ImageLayout::UNDEFINED,
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+        self.images.push(image);
+
+        let memory_requirements = unsafe { device.device.get_image_memory_requirements(image) };
+        let allocation = device.allocate_memory(
+            memory_requirements,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            ResourceKind::Optimal,
+            AllocationStrategy::FreeList,
+        )?;
+        let (memory, offset) = (allocation.memory, allocation.offset);
+        self.allocations.push(allocation);
+        unsafe { device.device.bind_image_memory(image, memory, offset) }?;
+
+        let image_view = unsafe {
+            device.device.create_image_view(
+                &vk::ImageViewCreateInfo {
+                    image,
+                    view_type: vk::ImageViewType::TYPE_2D,
+                    format: self.format,
+                    components: vk::ComponentMapping {
+                        r: vk::ComponentSwizzle::IDENTITY,
+                        g: vk::ComponentSwizzle::IDENTITY,
+                        b: vk::ComponentSwizzle::IDENTITY,
+                        a: vk::ComponentSwizzle::IDENTITY,
+                    },
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+        self.image_views.push(image_view);
+        Ok(())
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe {
+            self.image_views
+                .iter()
+                .for_each(|image_view| device.device.destroy_image_view(*image_view, None));
+            self.images
+                .iter()
+                .for_each(|image| device.device.destroy_image(*image, None));
+        }
+        self.allocations
+            .iter()
+            .for_each(|allocation| device.free_memory(allocation));
+    }
+}
@@ -1,145 +1,195 @@
+use super::{DrawConstants, MeshVertex, Shaders, VertexLayout};
 use ash::vk;
 
+const DYNAMIC_STATES: [vk::DynamicState; 2] =
+    [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+
+#[derive(Default)]
 pub struct Pipeline {
     pub layout: vk::PipelineLayout,
     pub pipeline: vk::Pipeline,
+    /// The stages `DrawConstants` are pushed to, empty when no shader reads them.
+    pub push_constant_stages: vk::ShaderStageFlags,
 }
 
 impl Pipeline {
     pub fn new(
-        surface: &super::Surface,
         device: &super::Device,
         render_pass: &super::RenderPass,
-        descriptor_set_layout: &vk::DescriptorSetLayout,
-    ) -> Self {
-        let vertex_shader = include_bytes!("../../shaders/vert.spv");
-        let fragment_shader = include_bytes!("../../shaders/frag.spv");
+        descriptor_set_layouts: &[vk::DescriptorSetLayout],
+        samples: vk::SampleCountFlags,
+        depth_compare_op: vk::CompareOp,
+        shaders: &Shaders,
+    ) -> super::Result<Self> {
+        let vertex_shader = &shaders.vertex;
+        let fragment_shader = &shaders.fragment;
+        let vertex_binding_description = MeshVertex::binding_description(0);
+        let vertex_attribute_descriptions = MeshVertex::attribute_descriptions(0);
+        shaders
+            .reflection
+            .check_vertex_layout(&vertex_attribute_descriptions)?;
+        // The range spans all of `DrawConstants`, as every draw pushes them whole from offset 0.
+        let push_constant_range = match shaders.reflection.push_constants {
+            Some(range) if range.offset != 0 => {
+                return Err(super::RendererError::ShaderReflection(format!(
+                    "The shaders' push constants start at byte {} but draws push them at byte 0",
+                    range.offset
+                )))
+            }
+            Some(range) if range.size as usize <= std::mem::size_of::<DrawConstants>() => {
+                Some(vk::PushConstantRange {
+                    stage_flags: range.stage_flags,
+                    offset: 0,
+                    size: std::mem::size_of::<DrawConstants>() as _,
+                })
+            }
+            Some(range) => {
+                return Err(super::RendererError::ShaderReflection(format!(
+                    "The shaders' push constants take {} bytes but draws only push {}",
+                    range.size,
+                    std::mem::size_of::<DrawConstants>()
+                )))
+            }
+            None => None,
+        };
 
         let vertex_shader_module = unsafe {
             device.device.create_shader_module(
-                #[allow(clippy::cast_ptr_alignment)]
                 &vk::ShaderModuleCreateInfo {
-                    code_size: vertex_shader.len(),
-                    p_code: vertex_shader.as_ptr().cast(),
+                    code_size: std::mem::size_of_val(vertex_shader.as_slice()),
+                    p_code: vertex_shader.as_ptr(),
                     ..Default::default()
                 },
                 None,
             )
         }
-        .unwrap();
+        .map_err(super::RendererError::ShaderModule)?;
 
         let fragment_shader_module = unsafe {
             device.device.create_shader_module(
-                #[allow(clippy::cast_ptr_alignment)]
                 &vk::ShaderModuleCreateInfo {
-                    code_size: fragment_shader.len(),
-                    p_code: fragment_shader.as_ptr().cast(),
+                    code_size: std::mem::size_of_val(fragment_shader.as_slice()),
+                    p_code: fragment_shader.as_ptr(),
                     ..Default::default()
                 },
                 None,
             )
         }
-        .unwrap();
+        .map_err(|result| {
+            unsafe {
+                device
+                    .device
+                    .destroy_shader_module(vertex_shader_module, None)
+            };
+            super::RendererError::ShaderModule(result)
+        })?;
 
         let layout = unsafe {
             device.device.create_pipeline_layout(
                 &vk::PipelineLayoutCreateInfo {
-                    set_layout_count: 1,
-                    p_set_layouts: descriptor_set_layout,
+                    set_layout_count: descriptor_set_layouts.len() as _,
+                    p_set_layouts: descriptor_set_layouts.as_ptr(),
+                    push_constant_range_count: push_constant_range.iter().len() as _,
+                    p_push_constant_ranges: push_constant_range
+                        .as_ref()
+                        .map_or(std::ptr::null(), |range| range),
                     ..Default::default()
                 },
                 None,
             )
-        }
-        .unwrap();
+        };
 
-        let pipeline = unsafe {
-            device.device.create_graphics_pipelines(
-                vk::PipelineCache::null(),
-                &[vk::GraphicsPipelineCreateInfo {
-                    stage_count: 2,
-                    p_stages: [
-                        vk::PipelineShaderStageCreateInfo {
-                            stage: vk::ShaderStageFlags::VERTEX,
-                            module: vertex_shader_module,
-                            p_name: b"main\0".as_ptr().cast(),
+        let pipeline = match layout {
+            Ok(layout) => unsafe {
+                device.device.create_graphics_pipelines(
+                    device.pipeline_cache.cache,
+                    &[vk::GraphicsPipelineCreateInfo {
+                        stage_count: 2,
+                        p_stages: [
+                            vk::PipelineShaderStageCreateInfo {
+                                stage: vk::ShaderStageFlags::VERTEX,
+                                module: vertex_shader_module,
+                                p_name: b"main\0".as_ptr().cast(),
+                                ..Default::default()
+                            },
+                            vk::PipelineShaderStageCreateInfo {
+                                stage: vk::ShaderStageFlags::FRAGMENT,
+                                module: fragment_shader_module,
+                                p_name: b"main\0".as_ptr().cast(),
+                                ..Default::default()
+                            },
+                        ]
+                        .as_ptr(),
+                        p_vertex_input_state: &vk::PipelineVertexInputStateCreateInfo {
+                            vertex_binding_description_count: 1,
+                            p_vertex_binding_descriptions: &vertex_binding_description,
+                            vertex_attribute_description_count: vertex_attribute_descriptions.len()
+                                as _,
+                            p_vertex_attribute_descriptions: vertex_attribute_descriptions.as_ptr(),
                             ..Default::default()
                         },
-                        vk::PipelineShaderStageCreateInfo {
-                            stage: vk::ShaderStageFlags::FRAGMENT,
-                            module: fragment_shader_module,
-                            p_name: b"main\0".as_ptr().cast(),
+                        p_input_assembly_state: &vk::PipelineInputAssemblyStateCreateInfo {
+                            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+                            primitive_restart_enable: vk::FALSE,
                             ..Default::default()
                         },
-                    ]
-                    .as_ptr(),
-                    p_vertex_input_state: &vk::PipelineVertexInputStateCreateInfo {
-                        vertex_binding_description_count: 1,
-                        p_vertex_binding_descriptions: &super::Vertex::binding_description(),
-                        vertex_attribute_description_count: 2,
-                        p_vertex_attribute_descriptions: super::Vertex::attribute_descriptions()
-                            .as_ptr(),
-                        ..Default::default()
-                    },
-                    p_input_assembly_state: &vk::PipelineInputAssemblyStateCreateInfo {
-                        topology: vk::PrimitiveTopology::TRIANGLE_LIST,
-                        primitive_restart_enable: vk::FALSE,
-                        ..Default::default()
-                    },
-                    p_viewport_state: &vk::PipelineViewportStateCreateInfo {
-                        viewport_count: 1,
-                        p_viewports: &vk::Viewport {
-                            x: 0.0,
-                            y: 0.0,
-                            width: surface.capabilities.unwrap().current_extent.width as _,
-                            height: surface.capabilities.unwrap().current_extent.height as _,
-                            min_depth: 0.0,
-                            max_depth: 1.0,
+                        // The viewport and scissor are set per frame, so resizes keep the pipeline.
+                        p_viewport_state: &vk::PipelineViewportStateCreateInfo {
+                            viewport_count: 1,
+                            scissor_count: 1,
+                            ..Default::default()
                         },
-                        scissor_count: 1,
-                        p_scissors: &vk::Rect2D {
-                            offset: vk::Offset2D { x: 0, y: 0 },
-                            extent: surface.capabilities.unwrap().current_extent,
+                        p_rasterization_state: &vk::PipelineRasterizationStateCreateInfo {
+                            depth_clamp_enable: vk::FALSE,
+                            rasterizer_discard_enable: vk::FALSE,
+                            polygon_mode: vk::PolygonMode::FILL,
+                            line_width: 1.0,
+                            cull_mode: vk::CullModeFlags::FRONT,
+                            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+                            depth_bias_enable: vk::FALSE,
+                            ..Default::default()
                         },
-                        ..Default::default()
-                    },
-                    p_rasterization_state: &vk::PipelineRasterizationStateCreateInfo {
-                        depth_clamp_enable: vk::FALSE,
-                        rasterizer_discard_enable: vk::FALSE,
-                        polygon_mode: vk::PolygonMode::FILL,
-                        line_width: 1.0,
-                        cull_mode: vk::CullModeFlags::FRONT,
-                        front_face: vk::FrontFace::COUNTER_CLOCKWISE,
-                        depth_bias_enable: vk::FALSE,
-                        ..Default::default()
-                    },
-                    p_multisample_state: &vk::PipelineMultisampleStateCreateInfo {
-                        sample_shading_enable: vk::FALSE,
-                        rasterization_samples: vk::SampleCountFlags::TYPE_1,
-                        ..Default::default()
-                    },
-                    p_color_blend_state: &vk::PipelineColorBlendStateCreateInfo {
-                        logic_op_enable: vk::FALSE,
-                        logic_op: vk::LogicOp::COPY,
-                        attachment_count: 1,
-                        p_attachments: &vk::PipelineColorBlendAttachmentState {
-                            color_write_mask: vk::ColorComponentFlags::RGBA,
-                            blend_enable: vk::FALSE,
+                        p_multisample_state: &vk::PipelineMultisampleStateCreateInfo {
+                            sample_shading_enable: vk::FALSE,
+                            rasterization_samples: samples,
+                            ..Default::default()
+                        },
+                        p_depth_stencil_state: &vk::PipelineDepthStencilStateCreateInfo {
+                            depth_test_enable: vk::TRUE,
+                            depth_write_enable: vk::TRUE,
+                            depth_compare_op,
+                            depth_bounds_test_enable: vk::FALSE,
+                            stencil_test_enable: vk::FALSE,
+                            ..Default::default()
+                        },
+                        p_color_blend_state: &vk::PipelineColorBlendStateCreateInfo {
+                            logic_op_enable: vk::FALSE,
+                            logic_op: vk::LogicOp::COPY,
+                            attachment_count: 1,
+                            p_attachments: &vk::PipelineColorBlendAttachmentState {
+                                color_write_mask: vk::ColorComponentFlags::RGBA,
+                                blend_enable: vk::FALSE,
+                                ..Default::default()
+                            },
+                            blend_constants: [0.0, 0.0, 0.0, 0.0],
                             ..Default::default()
                         },
-                        blend_constants: [0.0, 0.0, 0.0, 0.0],
+                        p_dynamic_state: &vk::PipelineDynamicStateCreateInfo {
+                            dynamic_state_count: DYNAMIC_STATES.len() as _,
+                            p_dynamic_states: DYNAMIC_STATES.as_ptr(),
+                            ..Default::default()
+                        },
+                        layout,
+                        render_pass: render_pass.render_pass,
+                        subpass: 0,
                         ..Default::default()
-                    },
-                    layout,
-                    render_pass: render_pass.render_pass,
-                    subpass: 0,
-                    ..Default::default()
-                }],
-                None,
-            )
-        }
-        .unwrap()
-        .remove(0);
+                    }],
+                    None,
+                )
+            }
+            .map_err(|(_, result)| result),
+            Err(result) => Err(result),
+        };
 
         unsafe {
             device
@@ -152,7 +202,21 @@ impl Pipeline {
                 .destroy_shader_module(fragment_shader_module, None)
         };
 
-        Self { layout, pipeline }
+        let layout = layout?;
+        let pipeline = match pipeline {
+            Ok(mut pipelines) => pipelines.remove(0),
+            Err(result) => {
+                unsafe { device.device.destroy_pipeline_layout(layout, None) };
+                return Err(result.into());
+            }
+        };
+
+        Ok(Self {
+            layout,
+            pipeline,
+            push_constant_stages: push_constant_range
+                .map_or_else(vk::ShaderStageFlags::empty, |range| range.stage_flags),
+        })
     }
 
     pub fn destroy(&self, device: &super::Device) {
@@ -0,0 +1,116 @@
+use ash::vk;
+use std::convert::TryInto;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"WPLC";
+const VERSION: u32 = 1;
+/// Magic, version, vendor ID, device ID, driver version and pipeline cache UUID.
+const HEADER_SIZE: usize = 4 + 4 * 4 + vk::UUID_SIZE;
+/// Header length, header version, vendor ID, device ID and pipeline cache UUID.
+const VULKAN_HEADER_SIZE: usize = 4 * 4 + vk::UUID_SIZE;
+
+/// A pipeline cache shared by every pipeline created on a device, optionally kept in a file
+/// between runs.
+pub struct PipelineCache {
+    pub cache: vk::PipelineCache,
+    path: Option<PathBuf>,
+    header: [u8; HEADER_SIZE],
+}
+
+impl PipelineCache {
+    /// Creates a cache seeded from `path` when it holds data written by the same device and
+    /// driver. Anything else there is ignored and replaced by `save`.
+    pub fn new(
+        device: &ash::Device,
+        properties: &vk::PhysicalDeviceProperties,
+        path: Option<&Path>,
+    ) -> super::Result<Self> {
+        let header = header(properties);
+        let contents = path.and_then(|path| std::fs::read(path).ok());
+        let initial_data = contents
+            .as_deref()
+            .and_then(|contents| contents.strip_prefix(&header[..]))
+            .filter(|data| is_compatible(data, properties))
+            .unwrap_or_default();
+        if let (Some(path), Some(_)) = (path, &contents) {
+            if initial_data.is_empty() {
+                log::warn!(
+                    "Ignoring pipeline cache {}, which is damaged or was written by another device or driver",
+                    path.display()
+                );
+            }
+        }
+        let cache = unsafe {
+            device.create_pipeline_cache(
+                &vk::PipelineCacheCreateInfo {
+                    initial_data_size: initial_data.len(),
+                    p_initial_data: initial_data.as_ptr() as _,
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+        Ok(Self {
+            cache,
+            path: path.map(Path::to_path_buf),
+            header,
+        })
+    }
+
+    /// Writes the cache back to its file, if it has one.
+    pub fn save(&self, device: &ash::Device) -> super::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let data = unsafe { device.get_pipeline_cache_data(self.cache) }?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Written aside and renamed so that an interrupted save never leaves a truncated cache.
+        let temp_path = path.with_extension("tmp");
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(&self.header)?;
+        file.write_all(&data)?;
+        drop(file);
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe { device.destroy_pipeline_cache(self.cache, None) };
+    }
+}
+
+/// Identifies the device and driver a cache file was written by. Vulkan's own header lacks
+/// the driver version, and drivers don't all reject data from older versions of themselves.
+fn header(properties: &vk::PhysicalDeviceProperties) -> [u8; HEADER_SIZE] {
+    let mut header = [0; HEADER_SIZE];
+    header[..4].copy_from_slice(MAGIC);
+    for (i, value) in IntoIterator::into_iter([
+        VERSION,
+        properties.vendor_id,
+        properties.device_id,
+        properties.driver_version,
+    ])
+    .enumerate()
+    {
+        header[4 + 4 * i..8 + 4 * i].copy_from_slice(&value.to_le_bytes());
+    }
+    header[20..].copy_from_slice(&properties.pipeline_cache_uuid);
+    header
+}
+
+/// Checks the header Vulkan puts in front of cache data against the device's properties.
+fn is_compatible(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> bool {
+    if data.len() < VULKAN_HEADER_SIZE {
+        return false;
+    }
+    let word = |i: usize| u32::from_ne_bytes(data[4 * i..4 * i + 4].try_into().unwrap());
+    word(0) as usize >= VULKAN_HEADER_SIZE
+        && word(1) == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32
+        && word(2) == properties.vendor_id
+        && word(3) == properties.device_id
+        && data[16..VULKAN_HEADER_SIZE] == properties.pipeline_cache_uuid
+}
@@ -0,0 +1,291 @@
+use super::{Indices, MeshData, MeshVertex, ModelData, NodeData, NormalGeneration, PrimitiveData};
+use std::convert::TryInto;
+
+fn load_error(message: impl std::fmt::Display) -> super::RendererError {
+    super::RendererError::ModelLoad(message.to_string())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> super::Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return Err(load_error(format!("Unknown PLY type {}", name))),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// The factor mapping the type's range to 0..1 when it stores a color channel.
+    fn color_scale(self) -> f64 {
+        match self {
+            Self::U8 => 1.0 / u8::MAX as f64,
+            Self::U16 => 1.0 / u16::MAX as f64,
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum PropertyType {
+    Scalar(Scalar),
+    List { count: Scalar, item: Scalar },
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<(String, PropertyType)>,
+}
+
+/// Parses the header, returning the body's encoding and offset with the elements it holds.
+fn parse_header(bytes: &[u8]) -> super::Result<(Encoding, usize, Vec<Element>)> {
+    if !bytes.starts_with(b"ply") {
+        return Err(load_error("Missing the PLY signature"));
+    }
+    let mut encoding = None;
+    let mut elements = Vec::<Element>::new();
+    let mut offset = 0;
+    loop {
+        let end = bytes[offset..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or_else(|| load_error("PLY header without end_header"))?;
+        let line = std::str::from_utf8(&bytes[offset..offset + end])
+            .map_err(|_| load_error("PLY header is not ASCII"))?;
+        offset += end + 1;
+        let words = line.split_ascii_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            ["end_header"] => break,
+            ["ply"] | [] => {}
+            ["comment", ..] | ["obj_info", ..] => {}
+            ["format", format, _version] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::BinaryLittleEndian,
+                    "binary_big_endian" => Encoding::BinaryBigEndian,
+                    _ => return Err(load_error(format!("Unknown PLY format {}", format))),
+                })
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| load_error(format!("Invalid element count {}", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => elements
+                .last_mut()
+                .ok_or_else(|| load_error("PLY property outside an element"))?
+                .properties
+                .push((
+                    name.to_string(),
+                    PropertyType::List {
+                        count: Scalar::parse(count)?,
+                        item: Scalar::parse(item)?,
+                    },
+                )),
+            ["property", scalar, name] => elements
+                .last_mut()
+                .ok_or_else(|| load_error("PLY property outside an element"))?
+                .properties
+                .push((
+                    name.to_string(),
+                    PropertyType::Scalar(Scalar::parse(scalar)?),
+                )),
+            _ => return Err(load_error(format!("Invalid PLY header line: {}", line))),
+        }
+    }
+    let encoding = encoding.ok_or_else(|| load_error("PLY header without a format"))?;
+    Ok((encoding, offset, elements))
+}
+
+/// Reads the values of the body one at a time.
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl Body<'_> {
+    fn read(&mut self, scalar: Scalar) -> super::Result<f64> {
+        match self {
+            Self::Ascii(words) => words
+                .next()
+                .ok_or_else(|| load_error("Truncated PLY body"))?
+                .parse()
+                .map_err(|_| load_error("Invalid number in PLY body")),
+            Self::Binary { bytes, big_endian } => {
+                if bytes.len() < scalar.size() {
+                    return Err(load_error("Truncated PLY body"));
+                }
+                let mut value = [0; 8];
+                value[..scalar.size()].copy_from_slice(&bytes[..scalar.size()]);
+                *bytes = &bytes[scalar.size()..];
+                if *big_endian {
+                    value[..scalar.size()].reverse();
+                }
+                Ok(match scalar {
+                    Scalar::I8 => value[0] as i8 as f64,
+                    Scalar::U8 => value[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([value[0], value[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([value[0], value[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes(value[..4].try_into().unwrap()) as f64,
+                    Scalar::U32 => u32::from_le_bytes(value[..4].try_into().unwrap()) as f64,
+                    Scalar::F32 => f32::from_le_bytes(value[..4].try_into().unwrap()) as f64,
+                    Scalar::F64 => f64::from_le_bytes(value),
+                })
+            }
+        }
+    }
+}
+
+/// Reads an ASCII or binary PLY file's vertices, with optional normals, texture coordinates and
+/// colors, and its faces, triangulated as fans.
+pub fn load(bytes: &[u8], normals: NormalGeneration) -> super::Result<ModelData> {
+    let (encoding, offset, elements) = parse_header(bytes)?;
+    let mut body = match encoding {
+        Encoding::Ascii => Body::Ascii(
+            std::str::from_utf8(&bytes[offset..])
+                .map_err(|_| load_error("PLY body is not ASCII"))?
+                .split_ascii_whitespace(),
+        ),
+        Encoding::BinaryLittleEndian | Encoding::BinaryBigEndian => Body::Binary {
+            bytes: &bytes[offset..],
+            big_endian: encoding == Encoding::BinaryBigEndian,
+        },
+    };
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut has_normals = false;
+    for element in &elements {
+        for _ in 0..element.count {
+            let mut vertex = MeshVertex {
+                color: [1.0; 4],
+                ..Default::default()
+            };
+            for (name, property_type) in &element.properties {
+                let scalar = match *property_type {
+                    PropertyType::Scalar(scalar) => scalar,
+                    PropertyType::List { count, item } => {
+                        let count = body.read(count)? as usize;
+                        let items = (0..count)
+                            .map(|_| body.read(item).map(|index| index as u32))
+                            .collect::<super::Result<Vec<_>>>()?;
+                        if element.name == "face"
+                            && (name == "vertex_indices" || name == "vertex_index")
+                        {
+                            for i in 1..count.saturating_sub(1) {
+                                indices.extend_from_slice(&[items[0], items[i], items[i + 1]]);
+                            }
+                        }
+                        continue;
+                    }
+                };
+                let value = body.read(scalar)?;
+                if element.name != "vertex" {
+                    continue;
+                }
+                // Texture coordinates are flipped to Vulkan's top left origin, like OBJ's.
+                match name.as_str() {
+                    "x" => vertex.position[0] = value as f32,
+                    "y" => vertex.position[1] = value as f32,
+                    "z" => vertex.position[2] = value as f32,
+                    "nx" => vertex.normal[0] = value as f32,
+                    "ny" => vertex.normal[1] = value as f32,
+                    "nz" => {
+                        vertex.normal[2] = value as f32;
+                        has_normals = true;
+                    }
+                    "u" | "s" | "texture_u" | "texture_s" => vertex.tex_coord[0] = value as f32,
+                    "v" | "t" | "texture_v" | "texture_t" => {
+                        vertex.tex_coord[1] = 1.0 - value as f32
+                    }
+                    "red" | "diffuse_red" => {
+                        vertex.color[0] = (value * scalar.color_scale()) as f32
+                    }
+                    "green" | "diffuse_green" => {
+                        vertex.color[1] = (value * scalar.color_scale()) as f32
+                    }
+                    "blue" | "diffuse_blue" => {
+                        vertex.color[2] = (value * scalar.color_scale()) as f32
+                    }
+                    "alpha" => vertex.color[3] = (value * scalar.color_scale()) as f32,
+                    _ => {}
+                }
+            }
+            if element.name == "vertex" {
+                vertices.push(vertex);
+            }
+        }
+    }
+    if let Some(&index) = indices
+        .iter()
+        .find(|&&index| index as usize >= vertices.len())
+    {
+        return Err(load_error(format!(
+            "Face index {} is out of bounds for {} vertices",
+            index,
+            vertices.len()
+        )));
+    }
+
+    let mut model = ModelData::default();
+    if indices.is_empty() {
+        return Ok(model);
+    }
+    let vertex_count = vertices.len();
+    let mut primitive = PrimitiveData {
+        vertices,
+        indices: Indices::from_u32(indices, vertex_count),
+        material: None,
+    };
+    if !has_normals {
+        primitive.generate_normals(normals);
+    }
+    model.meshes.push(MeshData {
+        name: None,
+        primitives: vec![primitive],
+    });
+    model.nodes.push(NodeData {
+        name: None,
+        transform: cgmath::SquareMatrix::identity(),
+        mesh: Some(0),
+        camera: None,
+        children: Vec::new(),
+    });
+    model.roots.push(0);
+    Ok(model)
+}
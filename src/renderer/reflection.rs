@@ -0,0 +1,446 @@
+use super::RendererError;
+use ash::vk;
+
+/// A descriptor binding declared by one or more shader stages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DescriptorBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub count: u32,
+    pub stages: vk::ShaderStageFlags,
+}
+
+/// An input of a vertex shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexInput {
+    pub location: u32,
+    pub format: vk::Format,
+}
+
+/// The resources and inputs SPIR-V modules declare, from which their pipeline layout follows.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderReflection {
+    /// Sorted by set and binding. A sampled image and a sampler sharing a binding become one
+    /// combined image sampler.
+    pub bindings: Vec<DescriptorBinding>,
+    /// At most one range, covering every stage's push constant block.
+    pub push_constants: Option<vk::PushConstantRange>,
+    /// The vertex shader's inputs by location.
+    pub vertex_inputs: Vec<VertexInput>,
+}
+
+impl ShaderReflection {
+    /// Reflects a module with a single entry point.
+    pub fn new(code: &[u32]) -> super::Result<Self> {
+        let bytes: Vec<u8> = code.iter().flat_map(|word| word.to_le_bytes()).collect();
+        let module = naga::front::spv::parse_u8_slice(
+            &bytes,
+            &naga::front::spv::Options {
+                adjust_coordinate_space: false,
+                ..Default::default()
+            },
+        )
+        .map_err(|error| reflection_error(format!("Malformed SPIR-V: {}", error)))?;
+        let entry_point = match module.entry_points.as_slice() {
+            [entry_point] => entry_point,
+            entry_points => {
+                return Err(reflection_error(format!(
+                    "Expected one entry point but found {}",
+                    entry_points.len()
+                )))
+            }
+        };
+        let stages = match entry_point.stage {
+            naga::ShaderStage::Vertex => vk::ShaderStageFlags::VERTEX,
+            naga::ShaderStage::Fragment => vk::ShaderStageFlags::FRAGMENT,
+            naga::ShaderStage::Compute => vk::ShaderStageFlags::COMPUTE,
+        };
+        let mut layouter = naga::proc::Layouter::default();
+        layouter
+            .update(module.to_ctx())
+            .map_err(|error| reflection_error(error.to_string()))?;
+
+        let mut reflection = Self::default();
+        for (_, global) in module.global_variables.iter() {
+            match (global.space, global.binding.as_ref()) {
+                (naga::AddressSpace::PushConstant, _) => {
+                    reflection.push_constants = Some(vk::PushConstantRange {
+                        stage_flags: stages,
+                        offset: 0,
+                        size: layouter[global.ty].size,
+                    });
+                }
+                (space, Some(binding)) => {
+                    let (descriptor_type, count) = descriptor_type(&module, space, global.ty)?;
+                    reflection.add_binding(DescriptorBinding {
+                        set: binding.group,
+                        binding: binding.binding,
+                        descriptor_type,
+                        count,
+                        stages,
+                    })?;
+                }
+                (_, None) => {}
+            }
+        }
+
+        if entry_point.stage == naga::ShaderStage::Vertex {
+            for argument in &entry_point.function.arguments {
+                match &argument.binding {
+                    Some(binding) => reflection.add_vertex_input(&module, binding, argument.ty)?,
+                    None => {
+                        if let naga::TypeInner::Struct { members, .. } =
+                            &module.types[argument.ty].inner
+                        {
+                            for member in members {
+                                if let Some(binding) = &member.binding {
+                                    reflection.add_vertex_input(&module, binding, member.ty)?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            reflection.vertex_inputs.sort_by_key(|input| input.location);
+        }
+        Ok(reflection)
+    }
+
+    /// Combines the reflections of a pipeline's stages.
+    pub fn merge(modules: &[Self]) -> super::Result<Self> {
+        let mut merged = Self::default();
+        for module in modules {
+            for &binding in &module.bindings {
+                merged.add_binding(binding)?;
+            }
+            merged.push_constants = match (merged.push_constants, module.push_constants) {
+                (Some(merged), Some(range)) => Some(vk::PushConstantRange {
+                    stage_flags: merged.stage_flags | range.stage_flags,
+                    offset: 0,
+                    size: merged.size.max(range.size),
+                }),
+                (merged, range) => merged.or(range),
+            };
+            merged.vertex_inputs.extend(&module.vertex_inputs);
+        }
+        Ok(merged)
+    }
+
+    /// The bindings of `set` as a descriptor set layout declares them.
+    pub fn set_layout_bindings(&self, set: u32) -> Vec<vk::DescriptorSetLayoutBinding> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.set == set)
+            .map(|binding| vk::DescriptorSetLayoutBinding {
+                binding: binding.binding,
+                descriptor_type: binding.descriptor_type,
+                descriptor_count: binding.count,
+                stage_flags: binding.stages,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Checks that `attributes` feed every vertex input a value of the type the shader reads.
+    ///
+    /// Component counts may differ, as the input assembler pads or drops components, but
+    /// reading a float attribute as an integer or the other way round is undefined.
+    pub fn check_vertex_layout(
+        &self,
+        attributes: &[vk::VertexInputAttributeDescription],
+    ) -> super::Result<()> {
+        for input in &self.vertex_inputs {
+            let attribute = attributes
+                .iter()
+                .find(|attribute| attribute.location == input.location);
+            let matches = attribute.is_some_and(|attribute| {
+                let layout_type = numeric_type(attribute.format);
+                layout_type.is_some() && layout_type == numeric_type(input.format)
+            });
+            if !matches {
+                return Err(RendererError::VertexLayoutMismatch {
+                    location: input.location,
+                    shader_format: input.format,
+                    layout_format: attribute.map(|attribute| attribute.format),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn add_binding(&mut self, binding: DescriptorBinding) -> super::Result<()> {
+        let existing = self
+            .bindings
+            .iter_mut()
+            .find(|existing| (existing.set, existing.binding) == (binding.set, binding.binding));
+        let existing = match existing {
+            Some(existing) => existing,
+            None => {
+                self.bindings.push(binding);
+                self.bindings
+                    .sort_by_key(|binding| (binding.set, binding.binding));
+                return Ok(());
+            }
+        };
+        let combined = match (existing.descriptor_type, binding.descriptor_type) {
+            (a, b) if a == b => Some(a),
+            (vk::DescriptorType::SAMPLED_IMAGE, vk::DescriptorType::SAMPLER)
+            | (vk::DescriptorType::SAMPLER, vk::DescriptorType::SAMPLED_IMAGE)
+            | (vk::DescriptorType::COMBINED_IMAGE_SAMPLER, vk::DescriptorType::SAMPLED_IMAGE)
+            | (vk::DescriptorType::COMBINED_IMAGE_SAMPLER, vk::DescriptorType::SAMPLER)
+            | (vk::DescriptorType::SAMPLED_IMAGE, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            | (vk::DescriptorType::SAMPLER, vk::DescriptorType::COMBINED_IMAGE_SAMPLER) => {
+                Some(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            }
+            _ => None,
+        };
+        match combined {
+            Some(descriptor_type) if existing.count == binding.count => {
+                existing.descriptor_type = descriptor_type;
+                existing.stages |= binding.stages;
+                Ok(())
+            }
+            _ => Err(reflection_error(format!(
+                "Set {} binding {} is declared as {} {:?} and as {} {:?}",
+                binding.set,
+                binding.binding,
+                existing.count,
+                existing.descriptor_type,
+                binding.count,
+                binding.descriptor_type
+            ))),
+        }
+    }
+
+    fn add_vertex_input(
+        &mut self,
+        module: &naga::Module,
+        binding: &naga::Binding,
+        ty: naga::Handle<naga::Type>,
+    ) -> super::Result<()> {
+        let location = match *binding {
+            naga::Binding::Location { location, .. } => location,
+            naga::Binding::BuiltIn(_) => return Ok(()),
+        };
+        let format = vertex_format(&module.types[ty].inner).ok_or_else(|| {
+            reflection_error(format!(
+                "The vertex input at location {} has an unsupported type",
+                location
+            ))
+        })?;
+        self.vertex_inputs.push(VertexInput { location, format });
+        Ok(())
+    }
+}
+
+/// The descriptor type and count of a resource in `space` of type `ty`.
+fn descriptor_type(
+    module: &naga::Module,
+    space: naga::AddressSpace,
+    ty: naga::Handle<naga::Type>,
+) -> super::Result<(vk::DescriptorType, u32)> {
+    let (ty, count) = match module.types[ty].inner {
+        naga::TypeInner::BindingArray {
+            base,
+            size: naga::ArraySize::Constant(size),
+        } => (base, size.get()),
+        naga::TypeInner::BindingArray { .. } => {
+            return Err(reflection_error(
+                "Binding arrays without a constant size are not supported".to_string(),
+            ))
+        }
+        _ => (ty, 1),
+    };
+    let descriptor_type = match (space, &module.types[ty].inner) {
+        (naga::AddressSpace::Uniform, _) => vk::DescriptorType::UNIFORM_BUFFER,
+        (naga::AddressSpace::Storage { .. }, _) => vk::DescriptorType::STORAGE_BUFFER,
+        (naga::AddressSpace::Handle, naga::TypeInner::Sampler { .. }) => {
+            vk::DescriptorType::SAMPLER
+        }
+        (
+            naga::AddressSpace::Handle,
+            naga::TypeInner::Image {
+                class: naga::ImageClass::Storage { .. },
+                ..
+            },
+        ) => vk::DescriptorType::STORAGE_IMAGE,
+        (naga::AddressSpace::Handle, naga::TypeInner::Image { .. }) => {
+            vk::DescriptorType::SAMPLED_IMAGE
+        }
+        (naga::AddressSpace::Handle, naga::TypeInner::AccelerationStructure) => {
+            vk::DescriptorType::ACCELERATION_STRUCTURE_KHR
+        }
+        (space, inner) => {
+            return Err(reflection_error(format!(
+                "Unsupported resource {:?} in {:?}",
+                inner, space
+            )))
+        }
+    };
+    Ok((descriptor_type, count))
+}
+
+/// The 32-bit format matching a scalar or vector vertex input.
+fn vertex_format(inner: &naga::TypeInner) -> Option<vk::Format> {
+    let (components, scalar) = match *inner {
+        naga::TypeInner::Scalar(scalar) => (1, scalar),
+        naga::TypeInner::Vector { size, scalar } => (size as u8, scalar),
+        _ => return None,
+    };
+    if scalar.width != 4 {
+        return None;
+    }
+    let formats = match scalar.kind {
+        naga::ScalarKind::Float => [
+            vk::Format::R32_SFLOAT,
+            vk::Format::R32G32_SFLOAT,
+            vk::Format::R32G32B32_SFLOAT,
+            vk::Format::R32G32B32A32_SFLOAT,
+        ],
+        naga::ScalarKind::Sint => [
+            vk::Format::R32_SINT,
+            vk::Format::R32G32_SINT,
+            vk::Format::R32G32B32_SINT,
+            vk::Format::R32G32B32A32_SINT,
+        ],
+        naga::ScalarKind::Uint => [
+            vk::Format::R32_UINT,
+            vk::Format::R32G32_UINT,
+            vk::Format::R32G32B32_UINT,
+            vk::Format::R32G32B32A32_UINT,
+        ],
+        _ => return None,
+    };
+    formats.get(usize::from(components) - 1).copied()
+}
+
+#[derive(PartialEq)]
+enum NumericType {
+    Float,
+    Sint,
+    Uint,
+}
+
+/// How a shader sees values of a vertex attribute format; normalized and scaled integers read
+/// as floats. Depth, compressed and 64-bit formats have no numeric type a vertex input can use.
+fn numeric_type(format: vk::Format) -> Option<NumericType> {
+    match format {
+        vk::Format::R8_UNORM
+        | vk::Format::R8_SNORM
+        | vk::Format::R8_USCALED
+        | vk::Format::R8_SSCALED
+        | vk::Format::R8_SRGB
+        | vk::Format::R8G8_UNORM
+        | vk::Format::R8G8_SNORM
+        | vk::Format::R8G8_USCALED
+        | vk::Format::R8G8_SSCALED
+        | vk::Format::R8G8_SRGB
+        | vk::Format::R8G8B8_UNORM
+        | vk::Format::R8G8B8_SNORM
+        | vk::Format::R8G8B8_USCALED
+        | vk::Format::R8G8B8_SSCALED
+        | vk::Format::R8G8B8_SRGB
+        | vk::Format::B8G8R8_UNORM
+        | vk::Format::B8G8R8_SNORM
+        | vk::Format::B8G8R8_USCALED
+        | vk::Format::B8G8R8_SSCALED
+        | vk::Format::B8G8R8_SRGB
+        | vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SNORM
+        | vk::Format::R8G8B8A8_USCALED
+        | vk::Format::R8G8B8A8_SSCALED
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::B8G8R8A8_UNORM
+        | vk::Format::B8G8R8A8_SNORM
+        | vk::Format::B8G8R8A8_USCALED
+        | vk::Format::B8G8R8A8_SSCALED
+        | vk::Format::B8G8R8A8_SRGB
+        | vk::Format::A8B8G8R8_UNORM_PACK32
+        | vk::Format::A8B8G8R8_SNORM_PACK32
+        | vk::Format::A8B8G8R8_USCALED_PACK32
+        | vk::Format::A8B8G8R8_SSCALED_PACK32
+        | vk::Format::A8B8G8R8_SRGB_PACK32
+        | vk::Format::A2R10G10B10_UNORM_PACK32
+        | vk::Format::A2R10G10B10_SNORM_PACK32
+        | vk::Format::A2R10G10B10_USCALED_PACK32
+        | vk::Format::A2R10G10B10_SSCALED_PACK32
+        | vk::Format::A2B10G10R10_UNORM_PACK32
+        | vk::Format::A2B10G10R10_SNORM_PACK32
+        | vk::Format::A2B10G10R10_USCALED_PACK32
+        | vk::Format::A2B10G10R10_SSCALED_PACK32
+        | vk::Format::R16_UNORM
+        | vk::Format::R16_SNORM
+        | vk::Format::R16_USCALED
+        | vk::Format::R16_SSCALED
+        | vk::Format::R16_SFLOAT
+        | vk::Format::R16G16_UNORM
+        | vk::Format::R16G16_SNORM
+        | vk::Format::R16G16_USCALED
+        | vk::Format::R16G16_SSCALED
+        | vk::Format::R16G16_SFLOAT
+        | vk::Format::R16G16B16_UNORM
+        | vk::Format::R16G16B16_SNORM
+        | vk::Format::R16G16B16_USCALED
+        | vk::Format::R16G16B16_SSCALED
+        | vk::Format::R16G16B16_SFLOAT
+        | vk::Format::R16G16B16A16_UNORM
+        | vk::Format::R16G16B16A16_SNORM
+        | vk::Format::R16G16B16A16_USCALED
+        | vk::Format::R16G16B16A16_SSCALED
+        | vk::Format::R16G16B16A16_SFLOAT
+        | vk::Format::R32_SFLOAT
+        | vk::Format::R32G32_SFLOAT
+        | vk::Format::R32G32B32_SFLOAT
+        | vk::Format::R32G32B32A32_SFLOAT
+        | vk::Format::R4G4_UNORM_PACK8
+        | vk::Format::R4G4B4A4_UNORM_PACK16
+        | vk::Format::B4G4R4A4_UNORM_PACK16
+        | vk::Format::R5G6B5_UNORM_PACK16
+        | vk::Format::B5G6R5_UNORM_PACK16
+        | vk::Format::R5G5B5A1_UNORM_PACK16
+        | vk::Format::B5G5R5A1_UNORM_PACK16
+        | vk::Format::A1R5G5B5_UNORM_PACK16
+        | vk::Format::B10G11R11_UFLOAT_PACK32
+        | vk::Format::E5B9G9R9_UFLOAT_PACK32 => Some(NumericType::Float),
+        vk::Format::R8_SINT
+        | vk::Format::R8G8_SINT
+        | vk::Format::R8G8B8_SINT
+        | vk::Format::B8G8R8_SINT
+        | vk::Format::R8G8B8A8_SINT
+        | vk::Format::B8G8R8A8_SINT
+        | vk::Format::A8B8G8R8_SINT_PACK32
+        | vk::Format::A2R10G10B10_SINT_PACK32
+        | vk::Format::A2B10G10R10_SINT_PACK32
+        | vk::Format::R16_SINT
+        | vk::Format::R16G16_SINT
+        | vk::Format::R16G16B16_SINT
+        | vk::Format::R16G16B16A16_SINT
+        | vk::Format::R32_SINT
+        | vk::Format::R32G32_SINT
+        | vk::Format::R32G32B32_SINT
+        | vk::Format::R32G32B32A32_SINT => Some(NumericType::Sint),
+        vk::Format::R8_UINT
+        | vk::Format::R8G8_UINT
+        | vk::Format::R8G8B8_UINT
+        | vk::Format::B8G8R8_UINT
+        | vk::Format::R8G8B8A8_UINT
+        | vk::Format::B8G8R8A8_UINT
+        | vk::Format::A8B8G8R8_UINT_PACK32
+        | vk::Format::A2R10G10B10_UINT_PACK32
+        | vk::Format::A2B10G10R10_UINT_PACK32
+        | vk::Format::R16_UINT
+        | vk::Format::R16G16_UINT
+        | vk::Format::R16G16B16_UINT
+        | vk::Format::R16G16B16A16_UINT
+        | vk::Format::R32_UINT
+        | vk::Format::R32G32_UINT
+        | vk::Format::R32G32B32_UINT
+        | vk::Format::R32G32B32A32_UINT => Some(NumericType::Uint),
+        _ => None,
+    }
+}
+
+fn reflection_error(message: String) -> RendererError {
+    RendererError::ShaderReflection(message)
+}
@@ -1,27 +1,75 @@
 use ash::vk;
 
+#[derive(Default)]
 pub struct RenderPass {
     pub render_pass: vk::RenderPass,
 }
 
 impl RenderPass {
-    pub fn new(surface: &super::Surface, device: &super::Device) -> Self {
-        Self {
+    pub fn new(
+        device: &super::Device,
+        format: vk::Format,
+        final_layout: vk::ImageLayout,
+        depth_format: vk::Format,
+        samples: vk::SampleCountFlags,
+    ) -> super::Result<Self> {
+        let multisampled = samples != vk::SampleCountFlags::TYPE_1;
+        let mut attachments = vec![
+            vk::AttachmentDescription {
+                format,
+                samples,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: if multisampled {
+                    vk::AttachmentStoreOp::DONT_CARE
+                } else {
+                    vk::AttachmentStoreOp::STORE
+                },
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: if multisampled {
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+                } else {
+                    final_layout
+                },
+                ..Default::default()
+            },
+            vk::AttachmentDescription {
+                format: depth_format,
+                samples,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::DONT_CARE,
+                stencil_load_op: vk::AttachmentLoadOp::CLEAR,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                ..Default::default()
+            },
+        ];
+        if multisampled {
+            attachments.push(vk::AttachmentDescription {
+                format,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::DONT_CARE,
+                store_op: vk::AttachmentStoreOp::STORE,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout,
+                ..Default::default()
+            });
+        }
+        let resolve_attachment = vk::AttachmentReference {
+            attachment: 2,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        };
+
+        Ok(Self {
             render_pass: unsafe {
                 device.device.create_render_pass(
                     &vk::RenderPassCreateInfo {
-                        attachment_count: 1,
-                        p_attachments: &vk::AttachmentDescription {
-                            format: surface.format.unwrap().format,
-                            samples: vk::SampleCountFlags::TYPE_1,
-                            load_op: vk::AttachmentLoadOp::CLEAR,
-                            store_op: vk::AttachmentStoreOp::STORE,
-                            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-                            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-                            initial_layout: vk::ImageLayout::UNDEFINED,
-                            final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
-                            ..Default::default()
-                        },
+                        attachment_count: attachments.len() as _,
+                        p_attachments: attachments.as_ptr(),
                         subpass_count: 1,
                         p_subpasses: &vk::SubpassDescription {
                             color_attachment_count: 1,
@@ -29,24 +77,36 @@ impl RenderPass {
                                 attachment: 0,
                                 layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
                             },
+                            p_resolve_attachments: if multisampled {
+                                &resolve_attachment
+                            } else {
+                                std::ptr::null()
+                            },
+                            p_depth_stencil_attachment: &vk::AttachmentReference {
+                                attachment: 1,
+                                layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                            },
                             ..Default::default()
                         },
                         dependency_count: 1,
                         p_dependencies: &vk::SubpassDependency {
                             src_subpass: vk::SUBPASS_EXTERNAL,
                             dst_subpass: 0,
-                            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-                            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-                            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+                            src_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
                             ..Default::default()
                         },
                         ..Default::default()
                     },
                     None,
                 )
-            }
-            .unwrap(),
-        }
+            }?,
+        })
     }
 
     pub fn destroy(&self, device: &super::Device) {
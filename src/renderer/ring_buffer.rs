@@ -0,0 +1,88 @@
+use super::{Buffer, Device};
+use ash::vk;
+
+/// Transient per-frame data sub-allocated from one persistently mapped buffer.
+///
+/// Every frame owns a fixed region that `begin_frame` rewinds, so anything pushed for a frame
+/// must not be read by the GPU after that frame's slot comes around again.
+pub struct RingBuffer {
+    pub buffer: Buffer,
+    pub frame_size: usize,
+    alignment: usize,
+    frame_start: usize,
+    head: usize,
+}
+
+impl RingBuffer {
+    pub fn new(
+        device: &Device,
+        frame_size: usize,
+        frame_count: usize,
+        buffer_usage: vk::BufferUsageFlags,
+    ) -> super::Result<Self> {
+        let mut alignment = 1;
+        if buffer_usage.contains(vk::BufferUsageFlags::UNIFORM_BUFFER) {
+            alignment = alignment.max(device.limits.min_uniform_buffer_offset_alignment as usize);
+        }
+        if buffer_usage.contains(vk::BufferUsageFlags::STORAGE_BUFFER) {
+            alignment = alignment.max(device.limits.min_storage_buffer_offset_alignment as usize);
+        }
+        if buffer_usage.contains(vk::BufferUsageFlags::TRANSFER_SRC) {
+            alignment = alignment.max(device.limits.optimal_buffer_copy_offset_alignment as usize);
+        }
+        let frame_size = frame_size.div_ceil(alignment) * alignment;
+        let buffer = Buffer::new(
+            device,
+            frame_size * frame_count,
+            buffer_usage,
+            vk::MemoryPropertyFlags::HOST_VISIBLE,
+        )?;
+        Ok(Self {
+            buffer,
+            frame_size,
+            alignment,
+            frame_start: 0,
+            head: 0,
+        })
+    }
+
+    pub fn frame_offset(&self, frame: usize) -> usize {
+        frame * self.frame_size
+    }
+
+    pub fn begin_frame(&mut self, frame: usize) {
+        self.frame_start = self.frame_offset(frame);
+        self.head = self.frame_start;
+    }
+
+    /// Copies `value` into the current frame and returns its offset, usable as a dynamic offset.
+    pub fn push<T>(&mut self, value: &T) -> super::Result<u32> {
+        self.push_slice(std::slice::from_ref(value))
+    }
+
+    pub fn push_slice<T>(&mut self, data: &[T]) -> super::Result<u32> {
+        let size = std::mem::size_of_val(data);
+        let alignment = self.alignment.max(std::mem::align_of::<T>());
+        let offset = self.head.div_ceil(alignment) * alignment;
+        if offset + size > self.frame_start + self.frame_size {
+            return Err(super::RendererError::RingBufferFull {
+                requested: size,
+                frame_size: self.frame_size,
+            });
+        }
+        let ptr = self.buffer.mapped_ptr()?;
+        unsafe { data.as_ptr().copy_to(ptr.add(offset).cast(), data.len()) };
+        self.head = offset + size;
+        Ok(offset as _)
+    }
+
+    /// Flushes everything pushed since `begin_frame`.
+    pub fn flush(&self, device: &Device) -> super::Result<()> {
+        self.buffer
+            .flush(device, self.frame_start, self.head - self.frame_start)
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        self.buffer.destory(device);
+    }
+}
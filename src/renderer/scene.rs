@@ -0,0 +1,257 @@
+use super::{Aabb, Device, DrawList, Material, Model, Projection, UploadHandle};
+use cgmath::{Matrix4, SquareMatrix};
+
+/// A punctual light shining down its node's negative Z axis, as in `KHR_lights_punctual`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Light {
+    Directional {
+        color: [f32; 3],
+        /// Illuminance in lux.
+        intensity: f32,
+    },
+    Point {
+        color: [f32; 3],
+        /// Luminous intensity in candela.
+        intensity: f32,
+        /// The distance the light's influence ends at, or `None` for an unbounded one.
+        range: Option<f32>,
+    },
+    Spot {
+        color: [f32; 3],
+        intensity: f32,
+        range: Option<f32>,
+        inner_cone_angle: cgmath::Rad<f32>,
+        outer_cone_angle: cgmath::Rad<f32>,
+    },
+}
+
+/// Identifies a node within its scene.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// A mesh of one of the scene's models.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MeshInstance {
+    /// Index into the scene's models.
+    pub model: usize,
+    /// Index into the model's meshes.
+    pub mesh: usize,
+}
+
+pub struct Node {
+    pub name: Option<String>,
+    pub mesh: Option<MeshInstance>,
+    pub light: Option<Light>,
+    pub camera: Option<Projection>,
+    transform: Matrix4<f32>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world_transform: Matrix4<f32>,
+    bounds: Option<Aabb>,
+}
+
+impl Node {
+    /// The transform relative to the parent node.
+    pub fn transform(&self) -> Matrix4<f32> {
+        self.transform
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// The transform relative to the scene, as of the last `Scene::update`.
+    pub fn world_transform(&self) -> Matrix4<f32> {
+        self.world_transform
+    }
+
+    /// The world space box around the node's mesh and its descendants' meshes, as of the last
+    /// `Scene::update`.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+}
+
+/// A hierarchy of nodes placing meshes, lights and cameras, which owns the models it draws.
+///
+/// World transforms and bounds are derived from the local transforms by `update`.
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<Node>,
+    roots: Vec<NodeId>,
+    models: Vec<Model>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an empty node under `parent`, or at the top of the scene without one.
+    pub fn add_node(&mut self, parent: Option<NodeId>, transform: Matrix4<f32>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            name: None,
+            mesh: None,
+            light: None,
+            camera: None,
+            transform,
+            parent,
+            children: Vec::new(),
+            world_transform: transform,
+            bounds: None,
+        });
+        match parent {
+            Some(parent) => self.nodes[parent.0].children.push(id),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    /// Takes ownership of `model` and instantiates its node hierarchy under a new node, which
+    /// is returned to place the whole model.
+    pub fn add_model(&mut self, model: Model, parent: Option<NodeId>) -> NodeId {
+        let root = self.add_node(parent, Matrix4::identity());
+        let model_index = self.models.len();
+        for &node in &model.roots {
+            self.add_model_node(&model, model_index, node, root);
+        }
+        self.models.push(model);
+        root
+    }
+
+    fn add_model_node(&mut self, model: &Model, model_index: usize, node: usize, parent: NodeId) {
+        let data = &model.nodes[node];
+        let id = self.add_node(Some(parent), data.transform);
+        let added = &mut self.nodes[id.0];
+        added.name = data.name.clone();
+        added.camera = data.camera;
+        added.mesh = data.mesh.map(|mesh| MeshInstance {
+            model: model_index,
+            mesh,
+        });
+        for &child in &data.children {
+            self.add_model_node(model, model_index, child, id);
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Matrix4<f32>) {
+        self.nodes[id.0].transform = transform;
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn models(&self) -> &[Model] {
+        &self.models
+    }
+
+    /// Finds the first node with `name`, in the order the nodes were added.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|node| node.name.as_deref() == Some(name))
+            .map(NodeId)
+    }
+
+    /// Propagates transforms down the tree and bounds back up it.
+    pub fn update(&mut self) {
+        for root in self.roots.clone() {
+            self.update_node(root, Matrix4::identity());
+        }
+    }
+
+    fn update_node(&mut self, id: NodeId, parent_transform: Matrix4<f32>) -> Option<Aabb> {
+        let node = &mut self.nodes[id.0];
+        node.world_transform = parent_transform * node.transform;
+        let world_transform = node.world_transform;
+        let mut bounds = node
+            .mesh
+            .and_then(|instance| self.models[instance.model].meshes[instance.mesh].bounds)
+            .map(|bounds| bounds.transformed(&world_transform));
+        for child in self.nodes[id.0].children.clone() {
+            if let Some(child_bounds) = self.update_node(child, world_transform) {
+                bounds = Some(match bounds {
+                    Some(bounds) => bounds.union(&child_bounds),
+                    None => child_bounds,
+                });
+            }
+        }
+        self.nodes[id.0].bounds = bounds;
+        bounds
+    }
+
+    /// The scene's lights with their world transforms.
+    pub fn lights(&self) -> impl Iterator<Item = (&Light, Matrix4<f32>)> {
+        self.nodes.iter().filter_map(|node| {
+            node.light
+                .as_ref()
+                .map(|light| (light, node.world_transform))
+        })
+    }
+
+    /// The scene's cameras with their world transforms.
+    pub fn cameras(&self) -> impl Iterator<Item = (&Projection, Matrix4<f32>)> {
+        self.nodes.iter().filter_map(|node| {
+            node.camera
+                .as_ref()
+                .map(|camera| (camera, node.world_transform))
+        })
+    }
+
+    /// Adds every mesh to `draw_list` with its primitives' materials, skipping subtrees whose
+    /// bounds fall outside `view_projection`'s frustum when one is given.
+    pub fn draw<'a>(
+        &'a self,
+        draw_list: &mut DrawList<'a>,
+        default_material: &Material,
+        view_projection: Option<&Matrix4<f32>>,
+    ) {
+        let mut stack = self.roots.clone();
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id.0];
+            match (node.bounds, view_projection) {
+                (Some(bounds), Some(view_projection))
+                    if !bounds.intersects_frustum(view_projection) =>
+                {
+                    continue
+                }
+                (None, _) => continue,
+                _ => {}
+            }
+            if let Some(instance) = node.mesh {
+                let model = &self.models[instance.model];
+                for primitive in &model.meshes[instance.mesh].primitives {
+                    let material = primitive
+                        .material
+                        .and_then(|material| model.materials.get(material))
+                        .unwrap_or(default_material);
+                    draw_list.draw_primitive(primitive, material, node.world_transform);
+                }
+            }
+            stack.extend(node.children.iter().rev());
+        }
+    }
+
+    /// The uploads that must complete before the scene can be drawn.
+    pub fn uploads(&self) -> impl Iterator<Item = UploadHandle> + '_ {
+        self.models.iter().flat_map(Model::uploads)
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        self.models.iter().for_each(|model| model.destroy(device));
+    }
+}
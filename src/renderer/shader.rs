@@ -0,0 +1,376 @@
+use super::{RendererError, ShaderReflection};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const VERTEX_SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/shader.vert");
+const FRAGMENT_SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/shader.frag");
+
+/// Bumped whenever the compiler's output changes for the same input, orphaning cached binaries.
+const CACHE_VERSION: u32 = 1;
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+    /// The stage the conventional `.vert`, `.frag` or `.comp` extension names.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "vert" => Some(Self::Vertex),
+            "frag" => Some(Self::Fragment),
+            "comp" => Some(Self::Compute),
+            _ => None,
+        }
+    }
+
+    fn naga(self) -> naga::ShaderStage {
+        match self {
+            Self::Vertex => naga::ShaderStage::Vertex,
+            Self::Fragment => naga::ShaderStage::Fragment,
+            Self::Compute => naga::ShaderStage::Compute,
+        }
+    }
+}
+
+/// The SPIR-V of the renderer's own pipeline.
+pub struct Shaders {
+    pub vertex: Vec<u32>,
+    pub fragment: Vec<u32>,
+    /// The sources and the files they include, which a change to any of invalidates the code.
+    pub files: Vec<PathBuf>,
+    pub reflection: ShaderReflection,
+}
+
+impl Shaders {
+    /// Compiles the shaders embedded in the binary, named by their paths in the source tree.
+    pub fn builtin(compiler: &ShaderCompiler) -> super::Result<Self> {
+        Self::compile(
+            compiler,
+            include_str!("../../shaders/shader.vert"),
+            include_str!("../../shaders/shader.frag"),
+        )
+    }
+
+    /// Compiles the shaders as they are in the source tree now.
+    pub fn load(compiler: &ShaderCompiler) -> super::Result<Self> {
+        let read = |path| {
+            std::fs::read_to_string(path)
+                .map_err(|error| compile_error(format!("{}: {}", path, error)))
+        };
+        Self::compile(
+            compiler,
+            &read(VERTEX_SHADER_PATH)?,
+            &read(FRAGMENT_SHADER_PATH)?,
+        )
+    }
+
+    fn compile(
+        compiler: &ShaderCompiler,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> super::Result<Self> {
+        let (vertex, mut files) = compiler.compile_source_with_files(
+            vertex_source,
+            Path::new(VERTEX_SHADER_PATH),
+            ShaderStage::Vertex,
+        )?;
+        let (fragment, fragment_files) = compiler.compile_source_with_files(
+            fragment_source,
+            Path::new(FRAGMENT_SHADER_PATH),
+            ShaderStage::Fragment,
+        )?;
+        files.extend(fragment_files);
+        files.sort();
+        files.dedup();
+        let reflection = ShaderReflection::merge(&[
+            ShaderReflection::new(&vertex)?,
+            ShaderReflection::new(&fragment)?,
+        ])?;
+        Ok(Self {
+            vertex,
+            fragment,
+            files,
+            reflection,
+        })
+    }
+}
+
+/// Compiles Vulkan GLSL to SPIR-V, expanding `#include "file"` directives first.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderCompiler {
+    /// Directories searched for included files not found next to the file including them.
+    pub include_dirs: Vec<PathBuf>,
+    /// Macros defined ahead of every shader's source.
+    pub defines: BTreeMap<String, String>,
+    /// Where compiled binaries are kept between runs, named by a hash of the preprocessed
+    /// source. `None` compiles every time.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl ShaderCompiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn compile_file(&self, path: &Path, stage: ShaderStage) -> super::Result<Vec<u32>> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| compile_error(format!("{}: {}", path.display(), error)))?;
+        self.compile_source(&source, path, stage)
+    }
+
+    /// Compiles `source` as if read from `path`, which names it in errors and anchors relative
+    /// includes.
+    pub fn compile_source(
+        &self,
+        source: &str,
+        path: &Path,
+        stage: ShaderStage,
+    ) -> super::Result<Vec<u32>> {
+        self.compile_source_with_files(source, path, stage)
+            .map(|(code, _)| code)
+    }
+
+    /// Compiles like `compile_source`, also returning `path` and every file it includes.
+    pub(super) fn compile_source_with_files(
+        &self,
+        source: &str,
+        path: &Path,
+        stage: ShaderStage,
+    ) -> super::Result<(Vec<u32>, Vec<PathBuf>)> {
+        let mut expanded = Expanded::default();
+        expanded.include(source, path, &self.include_dirs, &mut Vec::new())?;
+
+        let cache_path = self.cache_dir.as_ref().map(|cache_dir| {
+            cache_dir.join(format!(
+                "{:016x}.spv",
+                self.cache_key(&expanded.source, stage)
+            ))
+        });
+        if let Some(code) = cache_path.as_deref().and_then(read_cached) {
+            return Ok((code, expanded.files));
+        }
+
+        let code = self.compile_expanded(&expanded, stage)?;
+        if let Some(cache_path) = cache_path {
+            // A missing cache only costs time, so failing to fill it isn't an error.
+            let _ = write_cached(&cache_path, &code);
+        }
+        Ok((code, expanded.files))
+    }
+
+    fn compile_expanded(&self, expanded: &Expanded, stage: ShaderStage) -> super::Result<Vec<u32>> {
+        let options = naga::front::glsl::Options {
+            stage: stage.naga(),
+            defines: self
+                .defines
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        };
+        let module = naga::front::glsl::Frontend::default()
+            .parse(&options, &expanded.source)
+            .map_err(|errors| {
+                compile_error(
+                    errors
+                        .errors
+                        .iter()
+                        .map(|error| expanded.describe(error.meta, &error.kind))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+            })?;
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .map_err(|error| {
+            let span = error
+                .spans()
+                .next()
+                .map(|(span, _)| *span)
+                .unwrap_or_default();
+            compile_error(expanded.describe(span, error.as_inner()))
+        })?;
+        naga::back::spv::write_vec(
+            &module,
+            &info,
+            &naga::back::spv::Options {
+                // The sources already target Vulkan's clip space, which naga would otherwise
+                // flip them into.
+                flags: naga::back::spv::WriterFlags::LABEL_VARYINGS
+                    | naga::back::spv::WriterFlags::CLAMP_FRAG_DEPTH,
+                ..Default::default()
+            },
+            Some(&naga::back::spv::PipelineOptions {
+                shader_stage: stage.naga(),
+                entry_point: "main".to_string(),
+            }),
+        )
+        .map_err(|error| compile_error(format!("{}: {}", expanded.files[0].display(), error)))
+    }
+
+    fn cache_key(&self, source: &str, stage: ShaderStage) -> u64 {
+        let mut hash = Fnv1a::default();
+        hash.write(&CACHE_VERSION.to_le_bytes());
+        hash.write(format!("{:?}", stage).as_bytes());
+        for (name, value) in &self.defines {
+            hash.write(name.as_bytes());
+            hash.write(&[0]);
+            hash.write(value.as_bytes());
+            hash.write(&[0]);
+        }
+        hash.write(source.as_bytes());
+        hash.0
+    }
+}
+
+/// A source with its includes pasted in, remembering where each line came from.
+#[derive(Default)]
+struct Expanded {
+    source: String,
+    files: Vec<PathBuf>,
+    /// The file index and 1-based line number of every line in `source`.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Expanded {
+    fn include(
+        &mut self,
+        source: &str,
+        path: &Path,
+        include_dirs: &[PathBuf],
+        stack: &mut Vec<PathBuf>,
+    ) -> super::Result<()> {
+        let file = self.files.len();
+        self.files.push(path.to_path_buf());
+        // Different relative paths may lead to the same file.
+        stack.push(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let included = match parse_include(line) {
+                Some(included) => included,
+                None => {
+                    self.source.push_str(line);
+                    self.source.push('\n');
+                    self.lines.push((file, line_number));
+                    continue;
+                }
+            };
+            let location = || format!("{}:{}", path.display(), line_number);
+            let included = included
+                .map_err(|message| compile_error(format!("{}: {}", location(), message)))?;
+            let included_path = resolve_include(path, included, include_dirs).ok_or_else(|| {
+                compile_error(format!(
+                    "{}: can't find included file {}",
+                    location(),
+                    included
+                ))
+            })?;
+            if stack.contains(&included_path.canonicalize()?) {
+                return Err(compile_error(format!(
+                    "{}: {} includes itself",
+                    location(),
+                    included_path.display()
+                )));
+            }
+            let included_source = std::fs::read_to_string(&included_path).map_err(|error| {
+                compile_error(format!(
+                    "{}: {}: {}",
+                    location(),
+                    included_path.display(),
+                    error
+                ))
+            })?;
+            self.include(&included_source, &included_path, include_dirs, stack)?;
+        }
+        stack.pop();
+        Ok(())
+    }
+
+    /// Formats `message` with the file, line and column `span` points to.
+    fn describe(&self, span: naga::Span, message: &dyn std::fmt::Display) -> String {
+        if !span.is_defined() {
+            return format!("{}: {}", self.files[0].display(), message);
+        }
+        let location = span.location(&self.source);
+        match self.lines.get(location.line_number as usize - 1) {
+            Some(&(file, line)) => format!(
+                "{}:{}:{}: {}",
+                self.files[file].display(),
+                line,
+                location.line_position,
+                message
+            ),
+            None => format!("{}: {}", self.files[0].display(), message),
+        }
+    }
+}
+
+/// The file an `#include` line names, `None` for other lines, or an error for a malformed one.
+fn parse_include(line: &str) -> Option<Result<&str, &'static str>> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let argument = directive.strip_prefix("include")?.trim();
+    let name = match argument.chars().next() {
+        Some('"') => argument[1..].strip_suffix('"'),
+        Some('<') => argument[1..].strip_suffix('>'),
+        _ => None,
+    };
+    Some(name.ok_or("expected #include \"file\" or #include <file>"))
+}
+
+/// Looks for `name` next to `including`, then in each of `include_dirs`.
+fn resolve_include(including: &Path, name: &str, include_dirs: &[PathBuf]) -> Option<PathBuf> {
+    including
+        .parent()
+        .into_iter()
+        .chain(include_dirs.iter().map(PathBuf::as_path))
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+fn read_cached(path: &Path) -> Option<Vec<u32>> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.len() % 4 != 0 {
+        return None;
+    }
+    let code: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+    (code.first() == Some(&SPIRV_MAGIC)).then_some(code)
+}
+
+fn write_cached(path: &Path, code: &[u32]) -> std::io::Result<()> {
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    let bytes: Vec<u8> = code.iter().flat_map(|word| word.to_le_bytes()).collect();
+    // Renaming a finished file into place keeps concurrent readers from seeing half of it.
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&temporary, bytes)?;
+    std::fs::rename(&temporary, path)
+}
+
+fn compile_error(message: String) -> RendererError {
+    RendererError::ShaderCompile(message)
+}
+
+/// The 64-bit FNV-1a hash, which unlike `std`'s hashers is stable across Rust releases.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Notices edits to shader files by polling their modification times and sizes.
+pub struct ShaderWatcher {
+    /// The least time between two polls; polling sooner reports no changes.
+    pub interval: Duration,
+    files: Vec<(PathBuf, Option<(SystemTime, u64)>)>,
+    last_poll: Option<Instant>,
+}
+
+impl ShaderWatcher {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        let mut watcher = Self {
+            interval: Duration::from_millis(250),
+            files: Vec::new(),
+            last_poll: None,
+        };
+        watcher.watch(files);
+        watcher
+    }
+
+    /// Watches `files` from now on, keeping the state of those already watched so that edits
+    /// made in between are still reported.
+    pub fn watch(&mut self, files: Vec<PathBuf>) {
+        self.files = files
+            .into_iter()
+            .map(|file| {
+                let state = match self.files.iter().find(|(watched, _)| *watched == file) {
+                    Some(&(_, state)) => state,
+                    None => file_state(&file),
+                };
+                (file, state)
+            })
+            .collect();
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(file, _)| file.as_path())
+    }
+
+    /// The files written since the last poll.
+    ///
+    /// A file that disappears isn't reported until it comes back, since editors often replace
+    /// a file by deleting it first.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        if let Some(last_poll) = self.last_poll {
+            if now.duration_since(last_poll) < self.interval {
+                return Vec::new();
+            }
+        }
+        self.last_poll = Some(now);
+
+        let mut changed = Vec::new();
+        for (file, state) in &mut self.files {
+            let current = file_state(file);
+            if current.is_some() && current != *state {
+                changed.push(file.clone());
+            }
+            *state = current;
+        }
+        changed
+    }
+}
+
+fn file_state(file: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(file).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
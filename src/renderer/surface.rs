@@ -12,48 +12,67 @@ impl Surface {
         window: &winit::window::Window,
         entry: &super::Entry,
         instance: &super::Instance,
-    ) -> Self {
+    ) -> super::Result<Self> {
         let loader = ash::extensions::khr::Surface::new(&entry.entry, &instance.instance);
         let surface =
-            unsafe { ash_window::create_surface(&entry.entry, &instance.instance, window, None) }
-                .unwrap();
-        Self {
+            unsafe { ash_window::create_surface(&entry.entry, &instance.instance, window, None) }?;
+        Ok(Self {
             loader,
             surface,
             capabilities: None,
             format: None,
-        }
+        })
     }
 
-    pub fn is_supported(&mut self, physical_device: &vk::PhysicalDevice) -> bool {
-        let res = unsafe {
+    pub fn supports_queue_family(
+        &self,
+        physical_device: &vk::PhysicalDevice,
+        queue_family_index: u32,
+    ) -> super::Result<bool> {
+        Ok(unsafe {
+            self.loader.get_physical_device_surface_support(
+                *physical_device,
+                queue_family_index,
+                self.surface,
+            )
+        }?)
+    }
+
+    pub fn has_formats_and_present_modes(
+        &self,
+        physical_device: &vk::PhysicalDevice,
+    ) -> super::Result<bool> {
+        let formats = unsafe {
+            self.loader
+                .get_physical_device_surface_formats(*physical_device, self.surface)
+        }?;
+        let present_modes = unsafe {
             self.loader
-                .get_physical_device_surface_support(*physical_device, 0, self.surface)
-        }
-        .unwrap();
-        if res {
-            self.update_format_and_capabilities(physical_device);
-        }
-        res
+                .get_physical_device_surface_present_modes(*physical_device, self.surface)
+        }?;
+        Ok(!formats.is_empty() && !present_modes.is_empty())
     }
 
-    pub fn update_format_and_capabilities(&mut self, physical_device: &vk::PhysicalDevice) {
-        self.capabilities = Some(
-            unsafe {
-                self.loader
-                    .get_physical_device_surface_capabilities(*physical_device, self.surface)
-            }
-            .unwrap(),
-        );
-        self.format = Some(self.get_surface_format(physical_device));
+    pub fn update_format_and_capabilities(
+        &mut self,
+        physical_device: &vk::PhysicalDevice,
+    ) -> super::Result<()> {
+        self.capabilities = Some(unsafe {
+            self.loader
+                .get_physical_device_surface_capabilities(*physical_device, self.surface)
+        }?);
+        self.format = Some(self.get_surface_format(physical_device)?);
+        Ok(())
     }
 
-    fn get_surface_format(&self, physical_device: &vk::PhysicalDevice) -> vk::SurfaceFormatKHR {
+    fn get_surface_format(
+        &self,
+        physical_device: &vk::PhysicalDevice,
+    ) -> super::Result<vk::SurfaceFormatKHR> {
         let mut surface_formats = unsafe {
             self.loader
                 .get_physical_device_surface_formats(*physical_device, self.surface)
-        }
-        .unwrap();
+        }?;
 
         let surface_format: vk::SurfaceFormatKHR = match surface_formats
             .iter()
@@ -64,17 +83,20 @@ impl Surface {
                     && surface_format.format.eq(&vk::Format::B8G8R8A8_SRGB)
             })
             .collect::<Vec<_>>()
-            .get(0)
+            .first()
         {
             Some(surface_format) => **surface_format,
+            None if surface_formats.is_empty() => {
+                return Err(super::RendererError::NoSuitablePhysicalDevice)
+            }
             None => {
                 let surface_format = surface_formats.remove(0);
-                println!("Using a fallback surface format: {:?}", &surface_format);
+                log::warn!("Using a fallback surface format: {:?}", &surface_format);
                 surface_format
             }
         };
 
-        surface_format
+        Ok(surface_format)
     }
 }
 
@@ -5,15 +5,38 @@ pub struct Swapchain {
     pub swapchain: vk::SwapchainKHR,
     pub images: Vec<vk::Image>,
     pub image_views: Vec<vk::ImageView>,
+    pub image_usage: vk::ImageUsageFlags,
 }
 
 impl Swapchain {
+    /// Creates a swapchain for the surface's current extent. Passing the swapchain it replaces
+    /// as `old_swapchain` lets presentation carry on from it during a resize; the caller still
+    /// destroys it.
     pub fn new(
         instance: &super::Instance,
         surface: &super::Surface,
         device: &super::Device,
-    ) -> Self {
+        old_swapchain: vk::SwapchainKHR,
+    ) -> super::Result<Self> {
         let loader = ash::extensions::khr::Swapchain::new(&instance.instance, &device.device);
+        let image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | (surface.capabilities.unwrap().supported_usage_flags
+                & vk::ImageUsageFlags::TRANSFER_SRC);
+        let present_mode = unsafe {
+            surface
+                .loader
+                .get_physical_device_surface_present_modes(device.physical_device, surface.surface)
+        }?
+        .into_iter()
+        .find(|present_mode| *present_mode == vk::PresentModeKHR::MAILBOX)
+        .unwrap_or(vk::PresentModeKHR::FIFO);
+        let queue_family_indices = [device.queue_family_index, device.present_queue_family_index];
+        let (image_sharing_mode, queue_family_index_count) =
+            if device.queue_family_index == device.present_queue_family_index {
+                (vk::SharingMode::EXCLUSIVE, 0)
+            } else {
+                (vk::SharingMode::CONCURRENT, 2)
+            };
         let swapchain = unsafe {
             loader.create_swapchain(
                 &vk::SwapchainCreateInfoKHR {
@@ -23,65 +46,56 @@ impl Swapchain {
                     image_color_space: surface.format.unwrap().color_space,
                     image_extent: surface.capabilities.unwrap().current_extent,
                     image_array_layers: 1,
-                    image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
-                    image_sharing_mode: vk::SharingMode::EXCLUSIVE,
+                    image_usage,
+                    image_sharing_mode,
+                    queue_family_index_count,
+                    p_queue_family_indices: queue_family_indices.as_ptr(),
                     pre_transform: surface.capabilities.unwrap().current_transform,
                     composite_alpha: surface.capabilities.unwrap().supported_composite_alpha,
-                    present_mode: surface
-                        .loader
-                        .get_physical_device_surface_present_modes(
-                            device.physical_device,
-                            surface.surface,
-                        )
-                        .unwrap()
-                        .into_iter()
-                        .find(|present_mode| *present_mode == vk::PresentModeKHR::MAILBOX)
-                        .unwrap_or(vk::PresentModeKHR::FIFO),
+                    present_mode,
                     clipped: vk::TRUE,
+                    old_swapchain,
                     ..Default::default()
                 },
                 None,
             )
-        }
-        .unwrap();
-        let images = unsafe { loader.get_swapchain_images(swapchain) }.unwrap();
+        }?;
+        let images = unsafe { loader.get_swapchain_images(swapchain) }?;
         let image_views = images
             .iter()
-            .map(|image| {
-                unsafe {
-                    device.device.create_image_view(
-                        &vk::ImageViewCreateInfo {
-                            image: *image,
-                            view_type: vk::ImageViewType::TYPE_2D,
-                            format: surface.format.unwrap().format,
-                            components: vk::ComponentMapping {
-                                r: vk::ComponentSwizzle::IDENTITY,
-                                g: vk::ComponentSwizzle::IDENTITY,
-                                b: vk::ComponentSwizzle::IDENTITY,
-                                a: vk::ComponentSwizzle::IDENTITY,
-                            },
-                            subresource_range: vk::ImageSubresourceRange {
-                                aspect_mask: vk::ImageAspectFlags::COLOR,
-                                base_mip_level: 0,
-                                level_count: 1,
-                                base_array_layer: 0,
-                                layer_count: 1,
-                            },
-                            ..Default::default()
+            .map(|image| unsafe {
+                device.device.create_image_view(
+                    &vk::ImageViewCreateInfo {
+                        image: *image,
+                        view_type: vk::ImageViewType::TYPE_2D,
+                        format: surface.format.unwrap().format,
+                        components: vk::ComponentMapping {
+                            r: vk::ComponentSwizzle::IDENTITY,
+                            g: vk::ComponentSwizzle::IDENTITY,
+                            b: vk::ComponentSwizzle::IDENTITY,
+                            a: vk::ComponentSwizzle::IDENTITY,
                         },
-                        None,
-                    )
-                }
-                .unwrap()
+                        subresource_range: vk::ImageSubresourceRange {
+                            aspect_mask: vk::ImageAspectFlags::COLOR,
+                            base_mip_level: 0,
+                            level_count: 1,
+                            base_array_layer: 0,
+                            layer_count: 1,
+                        },
+                        ..Default::default()
+                    },
+                    None,
+                )
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Self {
+        Ok(Self {
             loader,
             swapchain,
             images,
             image_views,
-        }
+            image_usage,
+        })
     }
 
     pub fn destroy(&self, device: &super::Device) {
@@ -8,38 +8,34 @@ pub struct SyncObjects {
 }
 
 impl SyncObjects {
-    pub fn new(device: &super::Device, swapchain: &super::Swapchain) -> Self {
-        let mut images_in_flight = Vec::with_capacity(swapchain.images.len());
-        images_in_flight.resize(swapchain.images.len(), None);
+    pub fn new(device: &super::Device, image_count: usize) -> super::Result<Self> {
+        let mut images_in_flight = Vec::with_capacity(image_count);
+        images_in_flight.resize(image_count, None);
 
-        Self {
+        Ok(Self {
             image_availabe_semaphores: [
                 unsafe {
                     device
                         .device
                         .create_semaphore(&vk::SemaphoreCreateInfo::default(), None)
-                }
-                .unwrap(),
+                }?,
                 unsafe {
                     device
                         .device
                         .create_semaphore(&vk::SemaphoreCreateInfo::default(), None)
-                }
-                .unwrap(),
+                }?,
             ],
             render_finished_semaphores: [
                 unsafe {
                     device
                         .device
                         .create_semaphore(&vk::SemaphoreCreateInfo::default(), None)
-                }
-                .unwrap(),
+                }?,
                 unsafe {
                     device
                         .device
                         .create_semaphore(&vk::SemaphoreCreateInfo::default(), None)
-                }
-                .unwrap(),
+                }?,
             ],
             in_flight_fences: [
                 unsafe {
@@ -50,8 +46,7 @@ impl SyncObjects {
                         },
                         None,
                     )
-                }
-                .unwrap(),
+                }?,
                 unsafe {
                     device.device.create_fence(
                         &vk::FenceCreateInfo {
@@ -60,11 +55,10 @@ impl SyncObjects {
                         },
                         None,
                     )
-                }
-                .unwrap(),
+                }?,
             ],
             images_in_flight,
-        }
+        })
     }
 
     pub fn destroy(&self, device: &super::Device) {
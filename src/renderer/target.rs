@@ -0,0 +1,63 @@
+use super::{Device, Offscreen, Surface, Swapchain};
+use ash::vk;
+
+pub enum Target {
+    Window {
+        surface: Surface,
+        swapchain: Swapchain,
+    },
+    Headless(Offscreen),
+}
+
+impl Target {
+    pub fn extent(&self) -> vk::Extent2D {
+        match self {
+            Self::Window { surface, .. } => surface.capabilities.unwrap().current_extent,
+            Self::Headless(offscreen) => offscreen.extent,
+        }
+    }
+
+    pub fn format(&self) -> vk::Format {
+        match self {
+            Self::Window { surface, .. } => surface.format.unwrap().format,
+            Self::Headless(offscreen) => offscreen.format,
+        }
+    }
+
+    pub fn final_layout(&self) -> vk::ImageLayout {
+        match self {
+            Self::Window { .. } => vk::ImageLayout::PRESENT_SRC_KHR,
+            Self::Headless(_) => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        }
+    }
+
+    pub fn supports_capture(&self) -> bool {
+        match self {
+            Self::Window { swapchain, .. } => swapchain
+                .image_usage
+                .contains(vk::ImageUsageFlags::TRANSFER_SRC),
+            Self::Headless(_) => true,
+        }
+    }
+
+    pub fn images(&self) -> &[vk::Image] {
+        match self {
+            Self::Window { swapchain, .. } => &swapchain.images,
+            Self::Headless(offscreen) => &offscreen.images,
+        }
+    }
+
+    pub fn image_views(&self) -> &[vk::ImageView] {
+        match self {
+            Self::Window { swapchain, .. } => &swapchain.image_views,
+            Self::Headless(offscreen) => &offscreen.image_views,
+        }
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        match self {
+            Self::Window { swapchain, .. } => swapchain.destroy(device),
+            Self::Headless(offscreen) => offscreen.destroy(device),
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use super::{compressed, container, Buffer, Device, Image, MipGeneration, Transfer, UploadHandle};
+use ash::vk;
+
+/// Level offsets are aligned so every copy starts on a texel block and a 4 byte boundary.
+const LEVEL_ALIGNMENT: usize = 16;
+
+/// Texel data of a texture, ready to be uploaded.
+///
+/// `pixels` holds the mip levels back to back, each containing all array layers in order.
+/// Cube maps store their six faces as consecutive layers.
+pub struct TextureData {
+    pub width: u32,
+    pub height: u32,
+    pub format: vk::Format,
+    pub array_layers: u32,
+    pub cube: bool,
+    pub level_offsets: Vec<usize>,
+    pub pixels: Vec<u8>,
+}
+
+impl TextureData {
+    /// sRGB-encoded RGBA8 pixels.
+    pub fn from_rgba8(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+        Self {
+            width,
+            height,
+            format: vk::Format::R8G8B8A8_SRGB,
+            array_layers: 1,
+            cube: false,
+            level_offsets: vec![0],
+            pixels,
+        }
+    }
+
+    /// Linear RGBA32F pixels, e.g. for HDR images or data textures.
+    pub fn from_rgba32f(width: u32, height: u32, pixels: &[f32]) -> Self {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+        Self {
+            width,
+            height,
+            format: vk::Format::R32G32B32A32_SFLOAT,
+            array_layers: 1,
+            cube: false,
+            level_offsets: vec![0],
+            pixels: bytemuck::cast_slice(pixels).to_vec(),
+        }
+    }
+
+    /// Packs prebuilt mip levels, each holding every array layer, of a format in
+    /// `texel_block`'s table.
+    pub fn from_levels(
+        width: u32,
+        height: u32,
+        format: vk::Format,
+        array_layers: u32,
+        cube: bool,
+        levels: &[&[u8]],
+    ) -> super::Result<Self> {
+        let mut data = Self {
+            width,
+            height,
+            format,
+            array_layers,
+            cube,
+            level_offsets: Vec::with_capacity(levels.len()),
+            pixels: Vec::new(),
+        };
+        for (level, bytes) in levels.iter().enumerate() {
+            let size = data.level_size(level as u32)?;
+            if bytes.len() < size {
+                return Err(super::RendererError::ImageDecode(format!(
+                    "Mip level {} has {} bytes, expected {}",
+                    level,
+                    bytes.len(),
+                    size
+                )));
+            }
+            data.pixels
+                .resize(data.pixels.len().next_multiple_of(LEVEL_ALIGNMENT), 0);
+            data.level_offsets.push(data.pixels.len());
+            data.pixels.extend_from_slice(&bytes[..size]);
+        }
+        Ok(data)
+    }
+
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> super::Result<Self> {
+        Self::from_memory(&std::fs::read(path)?)
+    }
+
+    /// Decodes a PNG, JPEG, KTX2 or DDS image, picking the decoder from the file signature.
+    pub fn from_memory(bytes: &[u8]) -> super::Result<Self> {
+        if bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+            Self::decode_png(bytes)
+        } else if bytes.starts_with(&[0xff, 0xd8]) {
+            Self::decode_jpeg(bytes)
+        } else if bytes.starts_with(&container::KTX2_IDENTIFIER) {
+            container::read_ktx2(bytes)
+        } else if bytes.starts_with(b"DDS ") {
+            container::read_dds(bytes)
+        } else {
+            Err(super::RendererError::ImageDecode(
+                "Unrecognized image signature".to_string(),
+            ))
+        }
+    }
+
+    fn decode_png(bytes: &[u8]) -> super::Result<Self> {
+        let decode_error = |error: png::DecodingError| {
+            super::RendererError::ImageDecode(format!("Invalid PNG: {}", error))
+        };
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(
+            png::Transformations::EXPAND
+                | png::Transformations::STRIP_16
+                | png::Transformations::ALPHA,
+        );
+        let mut reader = decoder.read_info().map_err(decode_error)?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).map_err(decode_error)?;
+        pixels.truncate(info.buffer_size());
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => pixels,
+            png::ColorType::GrayscaleAlpha => pixels
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect(),
+            color_type => {
+                return Err(super::RendererError::ImageDecode(format!(
+                    "Unsupported PNG color type: {:?}",
+                    color_type
+                )))
+            }
+        };
+        Ok(Self::from_rgba8(info.width, info.height, pixels))
+    }
+
+    fn decode_jpeg(bytes: &[u8]) -> super::Result<Self> {
+        let mut decoder = jpeg_decoder::Decoder::new(bytes);
+        let pixels = decoder.decode().map_err(|error| {
+            super::RendererError::ImageDecode(format!("Invalid JPEG: {}", error))
+        })?;
+        let info = decoder.info().unwrap();
+        let pixels = match info.pixel_format {
+            jpeg_decoder::PixelFormat::L8 => pixels
+                .iter()
+                .flat_map(|luminance| [*luminance, *luminance, *luminance, 255])
+                .collect(),
+            jpeg_decoder::PixelFormat::L16 => pixels
+                .chunks_exact(2)
+                .map(|luminance| (u16::from_ne_bytes([luminance[0], luminance[1]]) >> 8) as u8)
+                .flat_map(|luminance| [luminance, luminance, luminance, 255])
+                .collect(),
+            jpeg_decoder::PixelFormat::RGB24 => pixels
+                .chunks_exact(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+                .collect(),
+            // The decoder hands out inverted CMYK, so each channel is already 255 - ink.
+            jpeg_decoder::PixelFormat::CMYK32 => pixels
+                .chunks_exact(4)
+                .flat_map(|pixel| {
+                    let key = pixel[3] as u32;
+                    let channel = |value: u8| (value as u32 * key / 255) as u8;
+                    [channel(pixel[0]), channel(pixel[1]), channel(pixel[2]), 255]
+                })
+                .collect(),
+        };
+        Ok(Self::from_rgba8(info.width as _, info.height as _, pixels))
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.level_offsets.len() as _
+    }
+
+    pub fn level_extent(&self, level: u32) -> vk::Extent2D {
+        vk::Extent2D {
+            width: (self.width >> level).max(1),
+            height: (self.height >> level).max(1),
+        }
+    }
+
+    /// The size of one mip level across all array layers.
+    pub fn level_size(&self, level: u32) -> super::Result<usize> {
+        let block = compressed::texel_block(self.format)
+            .ok_or_else(|| super::RendererError::NoSupportedFormat(vec![self.format]))?;
+        let extent = self.level_extent(level);
+        Ok(block.image_size(extent.width, extent.height) * self.array_layers as usize)
+    }
+
+    fn level_data(&self, level: u32) -> super::Result<&[u8]> {
+        let offset = self.level_offsets[level as usize];
+        Ok(&self.pixels[offset..offset + self.level_size(level)?])
+    }
+
+    /// Decompresses every level and layer to RGBA8, or half floats for BC6H, for devices that
+    /// can't sample the format.
+    pub fn decompressed(&self) -> super::Result<Self> {
+        let format = compressed::decompressed_format(self.format)
+            .ok_or(super::RendererError::DecompressionUnsupported(self.format))?;
+        let levels = (0..self.mip_levels())
+            .map(|level| {
+                let extent = self.level_extent(level);
+                let data = self.level_data(level)?;
+                let layer_size = data.len() / self.array_layers as usize;
+                Ok(data
+                    .chunks_exact(layer_size)
+                    .flat_map(|layer| {
+                        compressed::decompress(self.format, extent.width, extent.height, layer)
+                    })
+                    .collect::<Vec<_>>())
+            })
+            .collect::<super::Result<Vec<_>>>()?;
+        Self::from_levels(
+            self.width,
+            self.height,
+            format,
+            self.array_layers,
+            self.cube,
+            &levels.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn view_type(&self) -> vk::ImageViewType {
+        match (self.cube, self.array_layers) {
+            (true, 6) => vk::ImageViewType::CUBE,
+            (true, _) => vk::ImageViewType::CUBE_ARRAY,
+            (false, 1) => vk::ImageViewType::TYPE_2D,
+            (false, _) => vk::ImageViewType::TYPE_2D_ARRAY,
+        }
+    }
+}
+
+/// How a texture is filtered and addressed when sampled.
+#[derive(Clone, Copy, Debug)]
+pub struct SamplerConfig {
+    pub mag_filter: vk::Filter,
+    pub min_filter: vk::Filter,
+    pub mipmap_mode: vk::SamplerMipmapMode,
+    pub address_mode_u: vk::SamplerAddressMode,
+    pub address_mode_v: vk::SamplerAddressMode,
+    pub address_mode_w: vk::SamplerAddressMode,
+    pub mip_lod_bias: f32,
+    /// Clamped to the device limit; 1.0 or less, or a device without the feature, disables it.
+    pub max_anisotropy: f32,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode_u: vk::SamplerAddressMode::REPEAT,
+            address_mode_v: vk::SamplerAddressMode::REPEAT,
+            address_mode_w: vk::SamplerAddressMode::REPEAT,
+            mip_lod_bias: 0.0,
+            max_anisotropy: 16.0,
+        }
+    }
+}
+
+/// A sampled image with a full mip chain, where the format allows generating one, and the
+/// sampler it is bound with.
+pub struct Texture {
+    pub image: Image,
+    pub sampler: vk::Sampler,
+    pub upload: UploadHandle,
+}
+
+impl Texture {
+    /// Uploads `data`, decompressing it first if the device can't sample its format.
+    pub fn new(
+        instance: &super::Instance,
+        device: &Device,
+        transfer: &mut Transfer,
+        data: &TextureData,
+        sampler_config: &SamplerConfig,
+    ) -> super::Result<Self> {
+        let decompressed;
+        let data = if instance
+            .format_features(device.physical_device, data.format)
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE)
+        {
+            data
+        } else {
+            decompressed = data.decompressed()?;
+            &decompressed
+        };
+        let extent = vk::Extent2D {
+            width: data.width,
+            height: data.height,
+        };
+        // Generated mips only cover single-layer images that ship just their base level.
+        let mip_generation = if data.mip_levels() == 1 && data.array_layers == 1 {
+            MipGeneration::select(instance, device, data.format)
+        } else {
+            MipGeneration::None
+        };
+        let mip_levels = match mip_generation {
+            MipGeneration::None => data.mip_levels(),
+            _ => super::mipmap::mip_levels(extent),
+        };
+        let regions = (0..data.mip_levels())
+            .map(|level| {
+                let level_extent = data.level_extent(level);
+                vk::BufferImageCopy {
+                    buffer_offset: data.level_offsets[level as usize] as _,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: level,
+                        base_array_layer: 0,
+                        layer_count: data.array_layers,
+                    },
+                    image_extent: vk::Extent3D {
+                        width: level_extent.width,
+                        height: level_extent.height,
+                        depth: 1,
+                    },
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+        let staging_buffer = Buffer::staging(device, data.pixels.len())?;
+        let image = match staging_buffer
+            .write_arr(device, &data.pixels)
+            .and_then(|_| {
+                Image::with_layers(
+                    device,
+                    extent,
+                    data.format,
+                    mip_levels,
+                    data.array_layers,
+                    data.view_type(),
+                    vk::ImageUsageFlags::TRANSFER_DST
+                        | vk::ImageUsageFlags::SAMPLED
+                        | mip_generation.image_usage(),
+                )
+            }) {
+            Ok(image) => image,
+            Err(error) => {
+                staging_buffer.destory(device);
+                return Err(error);
+            }
+        };
+        // Linear filtering of a format without support for it is undefined, so fall back.
+        let mut sampler_config = *sampler_config;
+        if !instance
+            .format_features(device.physical_device, data.format)
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
+        {
+            sampler_config.mag_filter = vk::Filter::NEAREST;
+            sampler_config.min_filter = vk::Filter::NEAREST;
+            sampler_config.mipmap_mode = vk::SamplerMipmapMode::NEAREST;
+        }
+        let sampler = match create_sampler(device, &sampler_config, mip_levels) {
+            Ok(sampler) => sampler,
+            Err(error) => {
+                staging_buffer.destory(device);
+                image.destroy(device);
+                return Err(error);
+            }
+        };
+        let upload =
+            match transfer.upload_image(device, staging_buffer, &image, &regions, mip_generation) {
+                Ok(upload) => upload,
+                Err(error) => {
+                    unsafe { device.device.destroy_sampler(sampler, None) };
+                    image.destroy(device);
+                    return Err(error);
+                }
+            };
+
+        Ok(Self {
+            image,
+            sampler,
+            upload,
+        })
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe { device.device.destroy_sampler(self.sampler, None) };
+        self.image.destroy(device);
+    }
+}
+
+fn create_sampler(
+    device: &Device,
+    config: &SamplerConfig,
+    mip_levels: u32,
+) -> super::Result<vk::Sampler> {
+    let max_anisotropy = config
+        .max_anisotropy
+        .min(device.limits.max_sampler_anisotropy);
+    let anisotropy_enable = device.features.sampler_anisotropy == vk::TRUE && max_anisotropy > 1.0;
+    Ok(unsafe {
+        device.device.create_sampler(
+            &vk::SamplerCreateInfo {
+                mag_filter: config.mag_filter,
+                min_filter: config.min_filter,
+                mipmap_mode: config.mipmap_mode,
+                address_mode_u: config.address_mode_u,
+                address_mode_v: config.address_mode_v,
+                address_mode_w: config.address_mode_w,
+                mip_lod_bias: config.mip_lod_bias,
+                anisotropy_enable: anisotropy_enable as _,
+                max_anisotropy: if anisotropy_enable {
+                    max_anisotropy
+                } else {
+                    1.0
+                },
+                compare_op: vk::CompareOp::ALWAYS,
+                min_lod: 0.0,
+                max_lod: mip_levels as f32,
+                border_color: vk::BorderColor::INT_OPAQUE_BLACK,
+                ..Default::default()
+            },
+            None,
+        )
+    }?)
+}
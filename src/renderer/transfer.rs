@@ -0,0 +1,807 @@
+use super::mipmap::{self, DownsampleResources, Downsampler, MipChain};
+use super::{Buffer, CommandPool, Device, Image, MipGeneration};
+use ash::vk;
+
+/// A batch is submitted early once its staging buffers reach this size.
+const MAX_BATCH_STAGING_BYTES: usize = 64 * 1024 * 1024;
+
+/// Where uploaded images are first read; they may be sampled by any shader stage.
+const IMAGE_DST_STAGE_MASK: vk::PipelineStageFlags = vk::PipelineStageFlags::from_raw(
+    vk::PipelineStageFlags::VERTEX_SHADER.as_raw()
+        | vk::PipelineStageFlags::FRAGMENT_SHADER.as_raw()
+        | vk::PipelineStageFlags::COMPUTE_SHADER.as_raw(),
+);
+
+/// Identifies the batch an upload was recorded into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UploadHandle(u64);
+
+enum Destination {
+    Buffer(vk::Buffer),
+    /// Images end up in `SHADER_READ_ONLY_OPTIMAL`.
+    Image(MipChain, MipGeneration),
+}
+
+struct Upload {
+    destination: Destination,
+    dst_stage_mask: vk::PipelineStageFlags,
+    dst_access_mask: vk::AccessFlags,
+}
+
+impl Upload {
+    /// Whether levels of the destination are still written after the copy.
+    fn generates_mips(&self) -> bool {
+        matches!(self.destination, Destination::Image(_, generation) if generation != MipGeneration::None)
+    }
+
+    /// Whether the destination's mips are generated on the dedicated compute queue.
+    fn on_compute_queue(&self, device: &Device) -> bool {
+        device.has_dedicated_compute_queue()
+            && matches!(
+                self.destination,
+                Destination::Image(_, MipGeneration::Compute)
+            )
+    }
+}
+
+/// Barriers on upload destinations recorded together.
+#[derive(Default)]
+struct Barriers {
+    buffers: Vec<vk::BufferMemoryBarrier>,
+    images: Vec<vk::ImageMemoryBarrier>,
+}
+
+impl Barriers {
+    /// Adds a barrier on the upload's destination; `layouts` only apply to images.
+    fn push(
+        &mut self,
+        upload: &Upload,
+        (src_access_mask, dst_access_mask): (vk::AccessFlags, vk::AccessFlags),
+        (old_layout, new_layout): (vk::ImageLayout, vk::ImageLayout),
+        (src_queue_family_index, dst_queue_family_index): (u32, u32),
+    ) {
+        match upload.destination {
+            Destination::Buffer(buffer) => self.buffers.push(vk::BufferMemoryBarrier {
+                src_access_mask,
+                dst_access_mask,
+                src_queue_family_index,
+                dst_queue_family_index,
+                buffer,
+                offset: 0,
+                size: vk::WHOLE_SIZE,
+                ..Default::default()
+            }),
+            Destination::Image(chain, _) => self.images.push(vk::ImageMemoryBarrier {
+                src_access_mask,
+                dst_access_mask,
+                old_layout,
+                new_layout,
+                src_queue_family_index,
+                dst_queue_family_index,
+                image: chain.image,
+                subresource_range: whole_image(super::image::aspect_mask(chain.format)),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn record(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+    ) {
+        if self.buffers.is_empty() && self.images.is_empty() {
+            return;
+        }
+        unsafe {
+            device.device.cmd_pipeline_barrier(
+                command_buffer,
+                src_stage_mask,
+                dst_stage_mask,
+                vk::DependencyFlags::empty(),
+                &[],
+                &self.buffers,
+                &self.images,
+            )
+        };
+    }
+}
+
+struct Batch {
+    id: u64,
+    fence: vk::Fence,
+    semaphores: Vec<vk::Semaphore>,
+    transfer_command_buffer: vk::CommandBuffer,
+    compute_command_buffer: vk::CommandBuffer,
+    acquire_command_buffer: vk::CommandBuffer,
+    /// Queues already running part of the batch that the fence doesn't cover, since the
+    /// submissions that follow on other queues may fail.
+    submitted_queues: Vec<vk::Queue>,
+    staging_buffers: Vec<Buffer>,
+    uploads: Vec<Upload>,
+    downsample_resources: Vec<DownsampleResources>,
+}
+
+/// Batches uploads into one command buffer per submission and tracks them with fences.
+///
+/// When the device has a dedicated transfer queue, copies run there and ownership of each
+/// destination is released to the graphics queue, which acquires it after a semaphore
+/// handoff. Nothing waits on the host: `collect` frees staging buffers of finished batches.
+///
+/// Blitted mip levels are filled on the graphics queue, since blits need graphics support.
+/// With a dedicated compute queue, images whose mips are downsampled by compute pass through
+/// it on their way to the graphics queue, so the passes overlap with rendering.
+pub struct Transfer {
+    transfer_command_pool: CommandPool,
+    compute_command_pool: Option<CommandPool>,
+    acquire_command_pool: CommandPool,
+    recording: Option<Batch>,
+    pending: Vec<Batch>,
+    next_id: u64,
+    downsampler: Option<Downsampler>,
+}
+
+impl Transfer {
+    pub fn new(device: &Device) -> super::Result<Self> {
+        let transfer_command_pool = CommandPool::new(device, device.transfer_queue_family_index)?;
+        let compute_command_pool = if device.has_dedicated_compute_queue() {
+            match CommandPool::new(device, device.compute_queue_family_index) {
+                Ok(command_pool) => Some(command_pool),
+                Err(error) => {
+                    transfer_command_pool.destroy(device);
+                    return Err(error);
+                }
+            }
+        } else {
+            None
+        };
+        let acquire_command_pool = match CommandPool::new(device, device.queue_family_index) {
+            Ok(command_pool) => command_pool,
+            Err(error) => {
+                transfer_command_pool.destroy(device);
+                if let Some(command_pool) = compute_command_pool {
+                    command_pool.destroy(device);
+                }
+                return Err(error);
+            }
+        };
+        Ok(Self {
+            transfer_command_pool,
+            compute_command_pool,
+            acquire_command_pool,
+            recording: None,
+            pending: Vec::new(),
+            next_id: 0,
+            downsampler: None,
+        })
+    }
+
+    /// Records a copy of `staging_buffer` into `dst_buffer` that becomes visible to
+    /// `dst_access_mask` at `dst_stage_mask` on the graphics queue.
+    ///
+    /// The copy is submitted with the rest of its batch by the next `submit`.
+    pub fn upload_buffer(
+        &mut self,
+        device: &Device,
+        staging_buffer: Buffer,
+        dst_buffer: &Buffer,
+        dst_stage_mask: vk::PipelineStageFlags,
+        dst_access_mask: vk::AccessFlags,
+    ) -> super::Result<UploadHandle> {
+        let batch = self.recording_batch(device, &staging_buffer)?;
+        unsafe {
+            device.device.cmd_copy_buffer(
+                batch.transfer_command_buffer,
+                staging_buffer.buffer,
+                dst_buffer.buffer,
+                &[vk::BufferCopy {
+                    size: staging_buffer.size as _,
+                    ..Default::default()
+                }],
+            )
+        };
+        self.push_upload(
+            device,
+            staging_buffer,
+            Upload {
+                destination: Destination::Buffer(dst_buffer.buffer),
+                dst_stage_mask,
+                dst_access_mask,
+            },
+        )
+    }
+
+    /// Records copies of `staging_buffer` into `dst_image`, which afterwards is in
+    /// `SHADER_READ_ONLY_OPTIMAL` and ready to be sampled.
+    ///
+    /// Every level of the image is transitioned, so `regions` must fill all of them unless
+    /// `mip_generation` derives the levels below the base from it.
+    pub fn upload_image(
+        &mut self,
+        device: &Device,
+        staging_buffer: Buffer,
+        dst_image: &Image,
+        regions: &[vk::BufferImageCopy],
+        mip_generation: MipGeneration,
+    ) -> super::Result<UploadHandle> {
+        let mip_generation = if dst_image.mip_levels > 1 {
+            mip_generation
+        } else {
+            MipGeneration::None
+        };
+        if mip_generation == MipGeneration::Compute && !Downsampler::supports(dst_image.format) {
+            staging_buffer.destory(device);
+            return Err(super::RendererError::MipGenerationUnsupported(
+                dst_image.format,
+            ));
+        }
+        if mip_generation == MipGeneration::Compute && self.downsampler.is_none() {
+            match Downsampler::new(device) {
+                Ok(downsampler) => self.downsampler = Some(downsampler),
+                Err(error) => {
+                    staging_buffer.destory(device);
+                    return Err(error);
+                }
+            }
+        }
+        let aspect_mask = super::image::aspect_mask(dst_image.format);
+        let batch = self.recording_batch(device, &staging_buffer)?;
+        unsafe {
+            device.device.cmd_pipeline_barrier(
+                batch.transfer_command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    old_layout: vk::ImageLayout::UNDEFINED,
+                    new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    image: dst_image.image,
+                    subresource_range: whole_image(aspect_mask),
+                    ..Default::default()
+                }],
+            );
+            device.device.cmd_copy_buffer_to_image(
+                batch.transfer_command_buffer,
+                staging_buffer.buffer,
+                dst_image.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                regions,
+            );
+        }
+        self.push_upload(
+            device,
+            staging_buffer,
+            Upload {
+                destination: Destination::Image(MipChain::of(dst_image), mip_generation),
+                dst_stage_mask: IMAGE_DST_STAGE_MASK,
+                dst_access_mask: vk::AccessFlags::SHADER_READ,
+            },
+        )
+    }
+
+    /// The batch being recorded, beginning a new one if needed.
+    ///
+    /// `staging_buffer` is destroyed if that fails, since the caller hands it over.
+    fn recording_batch(
+        &mut self,
+        device: &Device,
+        staging_buffer: &Buffer,
+    ) -> super::Result<&mut Batch> {
+        if self.recording.is_none() {
+            match self.begin_batch(device) {
+                Ok(batch) => self.recording = Some(batch),
+                Err(error) => {
+                    staging_buffer.destory(device);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(self.recording.as_mut().unwrap())
+    }
+
+    fn push_upload(
+        &mut self,
+        device: &Device,
+        staging_buffer: Buffer,
+        upload: Upload,
+    ) -> super::Result<UploadHandle> {
+        let batch = self.recording.as_mut().unwrap();
+        batch.staging_buffers.push(staging_buffer);
+        batch.uploads.push(upload);
+        let handle = UploadHandle(batch.id);
+
+        let staging_bytes = batch
+            .staging_buffers
+            .iter()
+            .map(|staging_buffer| staging_buffer.size)
+            .sum::<usize>();
+        if staging_bytes >= MAX_BATCH_STAGING_BYTES {
+            self.submit(device)?;
+        }
+        Ok(handle)
+    }
+
+    fn begin_batch(&mut self, device: &Device) -> super::Result<Batch> {
+        let mut batch = Batch {
+            id: self.next_id,
+            fence: vk::Fence::null(),
+            semaphores: Vec::new(),
+            transfer_command_buffer: vk::CommandBuffer::null(),
+            compute_command_buffer: vk::CommandBuffer::null(),
+            acquire_command_buffer: vk::CommandBuffer::null(),
+            submitted_queues: Vec::new(),
+            staging_buffers: Vec::new(),
+            uploads: Vec::new(),
+            downsample_resources: Vec::new(),
+        };
+        let result = Self::allocate_command_buffer(device, &self.transfer_command_pool).and_then(
+            |command_buffer| {
+                batch.transfer_command_buffer = command_buffer;
+                Self::begin_command_buffer(device, command_buffer)
+            },
+        );
+        match result {
+            Ok(()) => {
+                self.next_id += 1;
+                Ok(batch)
+            }
+            Err(error) => {
+                self.free(device, batch);
+                Err(error)
+            }
+        }
+    }
+
+    /// Submits every upload recorded since the last submission.
+    pub fn submit(&mut self, device: &Device) -> super::Result<()> {
+        let mut batch = match self.recording.take() {
+            Some(batch) => batch,
+            None => return Ok(()),
+        };
+        match self.submit_batch(device, &mut batch) {
+            Ok(()) => {
+                self.pending.push(batch);
+                Ok(())
+            }
+            Err(error) => {
+                // The fence only covers the last submission, so wait for the earlier ones to stop
+                // using the batch's resources directly.
+                for queue in &batch.submitted_queues {
+                    let _ = unsafe { device.device.queue_wait_idle(*queue) };
+                }
+                self.free(device, batch);
+                Err(error)
+            }
+        }
+    }
+
+    fn submit_batch(&self, device: &Device, batch: &mut Batch) -> super::Result<()> {
+        let ownership_transfer = device.has_dedicated_transfer_queue();
+        let compute_pass = batch
+            .uploads
+            .iter()
+            .any(|upload| upload.on_compute_queue(device));
+        let dst_stage_mask = batch
+            .uploads
+            .iter()
+            .fold(vk::PipelineStageFlags::empty(), |stage_mask, upload| {
+                stage_mask | upload.dst_stage_mask
+            });
+        // Mip generation continues from the base level's transfer write on the graphics queue.
+        let acquire_stage_mask = if batch
+            .uploads
+            .iter()
+            .any(|upload| upload.generates_mips() && !upload.on_compute_queue(device))
+        {
+            dst_stage_mask | vk::PipelineStageFlags::TRANSFER
+        } else {
+            dst_stage_mask
+        };
+
+        let mut transfer_barriers = Barriers::default();
+        let mut release_barriers = Barriers::default();
+        let mut compute_acquire_barriers = Barriers::default();
+        let mut acquire_barriers = Barriers::default();
+        for upload in &batch.uploads {
+            // Images that still get mips stay in `TRANSFER_DST_OPTIMAL` for their generation.
+            let (new_layout, dst_access_mask) = if upload.generates_mips() {
+                (
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::TRANSFER_WRITE,
+                )
+            } else {
+                (
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    upload.dst_access_mask,
+                )
+            };
+            let layouts = (vk::ImageLayout::TRANSFER_DST_OPTIMAL, new_layout);
+            let queue_family_indices = (
+                device.transfer_queue_family_index,
+                if upload.on_compute_queue(device) {
+                    device.compute_queue_family_index
+                } else {
+                    device.queue_family_index
+                },
+            );
+            // The layout transition is part of both halves of an ownership transfer.
+            if queue_family_indices.0 != queue_family_indices.1 {
+                release_barriers.push(
+                    upload,
+                    (vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::empty()),
+                    layouts,
+                    queue_family_indices,
+                );
+                if upload.on_compute_queue(device) {
+                    &mut compute_acquire_barriers
+                } else {
+                    &mut acquire_barriers
+                }
+                .push(
+                    upload,
+                    (vk::AccessFlags::empty(), dst_access_mask),
+                    layouts,
+                    queue_family_indices,
+                );
+            } else if !upload.generates_mips() {
+                transfer_barriers.push(
+                    upload,
+                    (vk::AccessFlags::TRANSFER_WRITE, dst_access_mask),
+                    layouts,
+                    (vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED),
+                );
+            }
+            // The downsampler releases its images in their final layout.
+            if upload.on_compute_queue(device) {
+                acquire_barriers.push(
+                    upload,
+                    (vk::AccessFlags::empty(), upload.dst_access_mask),
+                    (
+                        vk::ImageLayout::GENERAL,
+                        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    ),
+                    (device.compute_queue_family_index, device.queue_family_index),
+                );
+            }
+        }
+
+        batch.fence = unsafe {
+            device
+                .device
+                .create_fence(&vk::FenceCreateInfo::default(), None)
+        }?;
+        release_barriers.record(
+            device,
+            batch.transfer_command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        );
+        transfer_barriers.record(
+            device,
+            batch.transfer_command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            dst_stage_mask,
+        );
+        if !ownership_transfer {
+            self.record_mip_generation(device, batch.transfer_command_buffer, batch, false)?;
+        }
+        unsafe {
+            device
+                .device
+                .end_command_buffer(batch.transfer_command_buffer)
+        }?;
+
+        let compute_semaphore = if compute_pass {
+            Some(Self::create_semaphore(device, batch)?)
+        } else {
+            None
+        };
+        let acquire_semaphore = if ownership_transfer {
+            Some(Self::create_semaphore(device, batch)?)
+        } else {
+            None
+        };
+        let signal_semaphores = compute_semaphore
+            .into_iter()
+            .chain(acquire_semaphore)
+            .collect::<Vec<_>>();
+        let last_submission = signal_semaphores.is_empty();
+        unsafe {
+            device.device.queue_submit(
+                device.transfer_queue,
+                &[vk::SubmitInfo {
+                    command_buffer_count: 1,
+                    p_command_buffers: &batch.transfer_command_buffer,
+                    signal_semaphore_count: signal_semaphores.len() as _,
+                    p_signal_semaphores: signal_semaphores.as_ptr(),
+                    ..Default::default()
+                }],
+                if last_submission {
+                    batch.fence
+                } else {
+                    vk::Fence::null()
+                },
+            )
+        }?;
+        if last_submission {
+            return Ok(());
+        }
+        batch.submitted_queues.push(device.transfer_queue);
+
+        let mut wait_semaphores = acquire_semaphore.into_iter().collect::<Vec<_>>();
+        if let Some(compute_semaphore) = compute_semaphore {
+            let compute_stage_mask = vk::PipelineStageFlags::TRANSFER;
+            let signal_semaphore = Self::create_semaphore(device, batch)?;
+            batch.compute_command_buffer =
+                Self::allocate_command_buffer(device, self.compute_command_pool.as_ref().unwrap())?;
+            Self::begin_command_buffer(device, batch.compute_command_buffer)?;
+            compute_acquire_barriers.record(
+                device,
+                batch.compute_command_buffer,
+                compute_stage_mask,
+                compute_stage_mask,
+            );
+            self.record_mip_generation(device, batch.compute_command_buffer, batch, true)?;
+            unsafe {
+                device
+                    .device
+                    .end_command_buffer(batch.compute_command_buffer)?;
+                device.device.queue_submit(
+                    device.compute_queue,
+                    &[vk::SubmitInfo {
+                        wait_semaphore_count: 1,
+                        p_wait_semaphores: &compute_semaphore,
+                        p_wait_dst_stage_mask: &compute_stage_mask,
+                        command_buffer_count: 1,
+                        p_command_buffers: &batch.compute_command_buffer,
+                        signal_semaphore_count: 1,
+                        p_signal_semaphores: &signal_semaphore,
+                        ..Default::default()
+                    }],
+                    vk::Fence::null(),
+                )?;
+            }
+            batch.submitted_queues.push(device.compute_queue);
+            wait_semaphores.push(signal_semaphore);
+        }
+
+        batch.acquire_command_buffer =
+            Self::allocate_command_buffer(device, &self.acquire_command_pool)?;
+        Self::begin_command_buffer(device, batch.acquire_command_buffer)?;
+        acquire_barriers.record(
+            device,
+            batch.acquire_command_buffer,
+            acquire_stage_mask,
+            acquire_stage_mask,
+        );
+        if ownership_transfer {
+            self.record_mip_generation(device, batch.acquire_command_buffer, batch, false)?;
+        }
+        let wait_dst_stage_masks = vec![acquire_stage_mask; wait_semaphores.len()];
+        unsafe {
+            device
+                .device
+                .end_command_buffer(batch.acquire_command_buffer)?;
+            device.device.queue_submit(
+                device.queue,
+                &[vk::SubmitInfo {
+                    wait_semaphore_count: wait_semaphores.len() as _,
+                    p_wait_semaphores: wait_semaphores.as_ptr(),
+                    p_wait_dst_stage_mask: wait_dst_stage_masks.as_ptr(),
+                    command_buffer_count: 1,
+                    p_command_buffers: &batch.acquire_command_buffer,
+                    ..Default::default()
+                }],
+                batch.fence,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Creates a semaphore that is destroyed with the batch.
+    fn create_semaphore(device: &Device, batch: &mut Batch) -> super::Result<vk::Semaphore> {
+        let semaphore = unsafe {
+            device
+                .device
+                .create_semaphore(&vk::SemaphoreCreateInfo::default(), None)
+        }?;
+        batch.semaphores.push(semaphore);
+        Ok(semaphore)
+    }
+
+    fn begin_command_buffer(
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+    ) -> super::Result<()> {
+        Ok(unsafe {
+            device.device.begin_command_buffer(
+                command_buffer,
+                &vk::CommandBufferBeginInfo {
+                    flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                    ..Default::default()
+                },
+            )
+        }?)
+    }
+
+    /// Records generation of the missing mip levels of every image whose generation runs on
+    /// the dedicated compute queue, or of every other image.
+    ///
+    /// Images finished on the compute queue are released to the graphics queue.
+    fn record_mip_generation(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        batch: &mut Batch,
+        compute_queue: bool,
+    ) -> super::Result<()> {
+        for upload in &batch.uploads {
+            if upload.on_compute_queue(device) != compute_queue {
+                continue;
+            }
+            match upload.destination {
+                Destination::Image(chain, MipGeneration::Blit) => mipmap::record_blits(
+                    device,
+                    command_buffer,
+                    &chain,
+                    upload.dst_stage_mask,
+                    upload.dst_access_mask,
+                ),
+                Destination::Image(chain, MipGeneration::Compute) => {
+                    let resources = if compute_queue {
+                        self.downsampler.as_ref().unwrap().record(
+                            device,
+                            command_buffer,
+                            &chain,
+                            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                            vk::AccessFlags::empty(),
+                            (device.compute_queue_family_index, device.queue_family_index),
+                        )
+                    } else {
+                        self.downsampler.as_ref().unwrap().record(
+                            device,
+                            command_buffer,
+                            &chain,
+                            upload.dst_stage_mask,
+                            upload.dst_access_mask,
+                            (vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED),
+                        )
+                    }?;
+                    batch.downsample_resources.push(resources);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn allocate_command_buffer(
+        device: &Device,
+        command_pool: &CommandPool,
+    ) -> super::Result<vk::CommandBuffer> {
+        Ok(unsafe {
+            device
+                .device
+                .allocate_command_buffers(&vk::CommandBufferAllocateInfo {
+                    level: vk::CommandBufferLevel::PRIMARY,
+                    command_pool: command_pool.command_pool,
+                    command_buffer_count: 1,
+                    ..Default::default()
+                })
+        }?
+        .remove(0))
+    }
+
+    /// Submits the batch being recorded and blocks until every batch has executed, so that
+    /// destinations of recorded uploads can be destroyed. This runs on error paths that can't
+    /// report a second failure, so if waiting fails it waits for the whole device instead.
+    pub fn finish(&mut self, device: &Device) {
+        let result = self.submit(device).and_then(|()| {
+            let fences = self
+                .pending
+                .iter()
+                .map(|batch| batch.fence)
+                .collect::<Vec<_>>();
+            if !fences.is_empty() {
+                unsafe { device.device.wait_for_fences(&fences, true, u64::MAX) }?;
+            }
+            self.collect(device)
+        });
+        if result.is_err() {
+            let _ = unsafe { device.device.device_wait_idle() };
+        }
+    }
+
+    /// Whether the upload's batch has been submitted and has finished executing.
+    pub fn is_complete(&self, handle: UploadHandle) -> bool {
+        self.recording
+            .iter()
+            .chain(self.pending.iter())
+            .all(|batch| batch.id != handle.0)
+    }
+
+    /// Releases the staging resources of every finished batch.
+    pub fn collect(&mut self, device: &Device) -> super::Result<()> {
+        let mut i = 0;
+        while i < self.pending.len() {
+            if unsafe { device.device.get_fence_status(self.pending[i].fence) }? {
+                let batch = self.pending.swap_remove(i);
+                self.free(device, batch);
+            } else {
+                i += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn free(&self, device: &Device, batch: Batch) {
+        unsafe {
+            if batch.transfer_command_buffer != vk::CommandBuffer::null() {
+                device.device.free_command_buffers(
+                    self.transfer_command_pool.command_pool,
+                    &[batch.transfer_command_buffer],
+                );
+            }
+            if let Some(compute_command_pool) = &self.compute_command_pool {
+                if batch.compute_command_buffer != vk::CommandBuffer::null() {
+                    device.device.free_command_buffers(
+                        compute_command_pool.command_pool,
+                        &[batch.compute_command_buffer],
+                    );
+                }
+            }
+            if batch.acquire_command_buffer != vk::CommandBuffer::null() {
+                device.device.free_command_buffers(
+                    self.acquire_command_pool.command_pool,
+                    &[batch.acquire_command_buffer],
+                );
+            }
+            batch
+                .semaphores
+                .iter()
+                .for_each(|semaphore| device.device.destroy_semaphore(*semaphore, None));
+            device.device.destroy_fence(batch.fence, None);
+        }
+        batch
+            .staging_buffers
+            .iter()
+            .for_each(|staging_buffer| staging_buffer.destory(device));
+        batch
+            .downsample_resources
+            .iter()
+            .for_each(|resources| resources.destroy(device));
+    }
+
+    pub fn destroy(&mut self, device: &Device) {
+        self.recording
+            .take()
+            .into_iter()
+            .chain(self.pending.drain(..).collect::<Vec<_>>())
+            .for_each(|batch| self.free(device, batch));
+        if let Some(downsampler) = self.downsampler.take() {
+            downsampler.destroy(device);
+        }
+        self.transfer_command_pool.destroy(device);
+        if let Some(command_pool) = &self.compute_command_pool {
+            command_pool.destroy(device);
+        }
+        self.acquire_command_pool.destroy(device);
+    }
+}
+
+fn whole_image(aspect_mask: vk::ImageAspectFlags) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask,
+        base_mip_level: 0,
+        level_count: vk::REMAINING_MIP_LEVELS,
+        base_array_layer: 0,
+        layer_count: vk::REMAINING_ARRAY_LAYERS,
+    }
+}
@@ -1,38 +1,140 @@
 use ash::vk;
 
-#[repr(C)]
-pub struct Vertex {
-    position: cgmath::Vector2<f32>,
-    color: cgmath::Vector3<f32>,
+/// A vertex field type with a matching `vk::Format`.
+///
+/// Integer fields are read by shaders as integers (`uint`/`uvec4`); wrap them in `Unorm` to read
+/// them as floats normalized to `0.0..=1.0` instead.
+pub trait VertexFormat {
+    const FORMAT: vk::Format;
+}
+
+impl VertexFormat for f32 {
+    const FORMAT: vk::Format = vk::Format::R32_SFLOAT;
+}
+
+impl VertexFormat for [f32; 2] {
+    const FORMAT: vk::Format = vk::Format::R32G32_SFLOAT;
+}
+
+impl VertexFormat for [f32; 3] {
+    const FORMAT: vk::Format = vk::Format::R32G32B32_SFLOAT;
+}
+
+impl VertexFormat for [f32; 4] {
+    const FORMAT: vk::Format = vk::Format::R32G32B32A32_SFLOAT;
 }
 
-impl Vertex {
-    pub fn new(position: cgmath::Vector2<f32>, color: cgmath::Vector3<f32>) -> Self {
-        Self { position, color }
+impl VertexFormat for u32 {
+    const FORMAT: vk::Format = vk::Format::R32_UINT;
+}
+
+impl VertexFormat for [u32; 4] {
+    const FORMAT: vk::Format = vk::Format::R32G32B32A32_UINT;
+}
+
+impl VertexFormat for [u16; 4] {
+    const FORMAT: vk::Format = vk::Format::R16G16B16A16_UINT;
+}
+
+impl VertexFormat for [u8; 4] {
+    const FORMAT: vk::Format = vk::Format::R8G8B8A8_UINT;
+}
+
+/// An integer vertex field that shaders read normalized, e.g. an 8-bit color.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Unorm<T>(pub T);
+
+unsafe impl<T: bytemuck::Zeroable> bytemuck::Zeroable for Unorm<T> {}
+unsafe impl<T: bytemuck::Pod> bytemuck::Pod for Unorm<T> {}
+
+impl VertexFormat for Unorm<[u16; 4]> {
+    const FORMAT: vk::Format = vk::Format::R16G16B16A16_UNORM;
+}
+
+impl VertexFormat for Unorm<[u8; 4]> {
+    const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub offset: u32,
+    pub format: vk::Format,
+}
+
+impl VertexAttribute {
+    /// The attribute for a field at `offset`, with the format taken from the field's type.
+    pub fn of<V, F: VertexFormat>(offset: usize, _field: fn(&V) -> &F) -> Self {
+        Self {
+            offset: offset as _,
+            format: F::FORMAT,
+        }
     }
+}
 
-    pub fn binding_description() -> vk::VertexInputBindingDescription {
+/// Describes how a vertex struct is laid out for the input assembler.
+///
+/// Implement it with `vertex_layout!`, which lists the fields in shader location order.
+pub trait VertexLayout: bytemuck::Pod {
+    fn attributes() -> Vec<VertexAttribute>;
+
+    fn binding_description(binding: u32) -> vk::VertexInputBindingDescription {
         vk::VertexInputBindingDescription {
-            binding: 0,
+            binding,
             stride: std::mem::size_of::<Self>() as _,
             input_rate: vk::VertexInputRate::VERTEX,
         }
     }
 
-    pub fn attribute_descriptions() -> [vk::VertexInputAttributeDescription; 2] {
-        [
-            vk::VertexInputAttributeDescription {
-                binding: 0,
-                location: 0,
-                format: vk::Format::R32G32_SFLOAT,
-                offset: memoffset::offset_of!(Vertex, position) as _,
-            },
-            vk::VertexInputAttributeDescription {
-                binding: 0,
-                location: 1,
-                format: vk::Format::R32G32B32_SFLOAT,
-                offset: memoffset::offset_of!(Vertex, color) as _,
-            },
-        ]
+    fn attribute_descriptions(binding: u32) -> Vec<vk::VertexInputAttributeDescription> {
+        Self::attributes()
+            .iter()
+            .enumerate()
+            .map(
+                |(location, attribute)| vk::VertexInputAttributeDescription {
+                    binding,
+                    location: location as _,
+                    format: attribute.format,
+                    offset: attribute.offset,
+                },
+            )
+            .collect()
     }
 }
+
+/// Implements `VertexLayout` for a `#[repr(C)]` struct, assigning locations in field order.
+#[macro_export]
+macro_rules! vertex_layout {
+    ($vertex:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::renderer::VertexLayout for $vertex {
+            fn attributes() -> Vec<$crate::renderer::VertexAttribute> {
+                vec![$(
+                    $crate::renderer::VertexAttribute::of(
+                        ::core::mem::offset_of!($vertex, $field),
+                        |vertex: &$vertex| &vertex.$field,
+                    )
+                ),*]
+            }
+        }
+    };
+}
+
+/// A vertex of a loaded mesh, carrying every attribute the model loaders read.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MeshVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    /// The tangent in `xyz` and the bitangent's handedness in `w`; zero when the file has none.
+    pub tangent: [f32; 4],
+    pub tex_coord: [f32; 2],
+    pub color: [f32; 4],
+}
+
+crate::vertex_layout!(MeshVertex {
+    position,
+    normal,
+    tangent,
+    tex_coord,
+    color
+});
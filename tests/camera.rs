@@ -0,0 +1,229 @@
+mod common;
+
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Matrix4, Point2, Point3, Vector3, Vector4};
+use wild::renderer::{
+    Camera, CameraController, FlyController, OrbitController, PanZoomController, Projection,
+};
+use winit::event::{
+    DeviceId, ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+    TouchPhase, VirtualKeyCode, WindowEvent,
+};
+
+const STEP: std::time::Duration = std::time::Duration::from_millis(500);
+
+fn device_id() -> DeviceId {
+    unsafe { DeviceId::dummy() }
+}
+
+#[allow(deprecated)]
+fn key(key: VirtualKeyCode, state: ElementState) -> WindowEvent<'static> {
+    WindowEvent::KeyboardInput {
+        device_id: device_id(),
+        input: KeyboardInput {
+            scancode: 0,
+            state,
+            virtual_keycode: Some(key),
+            modifiers: ModifiersState::empty(),
+        },
+        is_synthetic: false,
+    }
+}
+
+#[allow(deprecated)]
+fn button(button: MouseButton, state: ElementState) -> WindowEvent<'static> {
+    WindowEvent::MouseInput {
+        device_id: device_id(),
+        state,
+        button,
+        modifiers: ModifiersState::empty(),
+    }
+}
+
+#[allow(deprecated)]
+fn cursor(x: f64, y: f64) -> WindowEvent<'static> {
+    WindowEvent::CursorMoved {
+        device_id: device_id(),
+        position: winit::dpi::PhysicalPosition::new(x, y),
+        modifiers: ModifiersState::empty(),
+    }
+}
+
+#[allow(deprecated)]
+fn scroll(lines: f32) -> WindowEvent<'static> {
+    WindowEvent::MouseWheel {
+        device_id: device_id(),
+        delta: MouseScrollDelta::LineDelta(0.0, lines),
+        phase: TouchPhase::Moved,
+        modifiers: ModifiersState::empty(),
+    }
+}
+
+fn depth(projection: &Matrix4<f32>, z: f32) -> f32 {
+    let clip = projection * Vector4::new(0.0, 0.0, z, 1.0);
+    clip.z / clip.w
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-4,
+        "{} is not close to {}",
+        actual,
+        expected
+    );
+}
+
+fn assert_points_close(actual: Point3<f32>, expected: Point3<f32>) {
+    assert!(
+        (actual - expected).magnitude() < 1e-4,
+        "{:?} is not close to {:?}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn projections_map_near_and_far_to_the_depth_range() {
+    let perspective = Projection::Perspective {
+        fov_y: Deg(60.0).into(),
+        near: 0.5,
+        far: 50.0,
+    };
+    let orthographic = Projection::Orthographic {
+        height: 4.0,
+        near: 1.0,
+        far: 9.0,
+    };
+    for (projection, near, far) in [(perspective, 0.5, 50.0), (orthographic, 1.0, 9.0)] {
+        let standard = projection.matrix(2.0, false);
+        assert_close(depth(&standard, -near), 0.0);
+        assert_close(depth(&standard, -far), 1.0);
+        let reversed = projection.matrix(2.0, true);
+        assert_close(depth(&reversed, -near), 1.0);
+        assert_close(depth(&reversed, -far), 0.0);
+    }
+
+    // The orthographic box is `height` tall and as wide as the aspect ratio makes it.
+    let corner = orthographic.matrix(2.0, false) * Vector4::new(4.0, 2.0, -1.0, 1.0);
+    assert_close(corner.x, 1.0);
+    assert_close(corner.y, 1.0);
+}
+
+#[test]
+fn cameras_look_where_they_are_pointed() {
+    let eye = Point3::new(2.0, 2.0, 2.0);
+    let mut camera = Camera::new(Projection::Perspective {
+        fov_y: Deg(45.0).into(),
+        near: 0.1,
+        far: 10.0,
+    });
+    camera.look_at(eye, Point3::new(0.0, 0.0, 0.0), Vector3::unit_z());
+    let expected = Matrix4::look_at_rh(eye, Point3::new(0.0, 0.0, 0.0), Vector3::unit_z());
+    let view = camera.view();
+    for column in 0..4 {
+        assert!((view[column] - expected[column]).magnitude() < 1e-5);
+    }
+    assert!((camera.forward() - Vector3::new(-1.0, -1.0, -1.0).normalize()).magnitude() < 1e-5);
+
+    let placed =
+        Camera::from_transform(&Matrix4::from_translation(eye.to_vec()), camera.projection);
+    assert_eq!(placed.position, eye);
+    assert!((placed.forward() - -Vector3::unit_z()).magnitude() < 1e-5);
+}
+
+#[test]
+fn orbiting_keeps_the_distance_to_the_target() {
+    let target = Point3::new(1.0, 0.0, 0.0);
+    let mut controller =
+        OrbitController::new(Point3::new(1.0, -4.0, 0.0), target, Vector3::unit_z());
+    let mut camera = Camera::new(Projection::Perspective {
+        fov_y: Deg(45.0).into(),
+        near: 0.1,
+        far: 10.0,
+    });
+    controller.update(&mut camera, STEP);
+    assert_points_close(camera.position, Point3::new(1.0, -4.0, 0.0));
+
+    // Moving the cursor without a button held leaves the view alone.
+    assert!(controller.handle_event(&cursor(0.0, 0.0)));
+    controller.handle_event(&cursor(100.0, 0.0));
+    controller.update(&mut camera, STEP);
+    assert_points_close(camera.position, Point3::new(1.0, -4.0, 0.0));
+
+    controller.handle_event(&button(MouseButton::Left, ElementState::Pressed));
+    controller.handle_event(&cursor(200.0, 50.0));
+    controller.update(&mut camera, STEP);
+    assert_close((camera.position - target).magnitude(), 4.0);
+    assert!(camera.position.z > 0.0);
+    assert!((camera.forward() - (target - camera.position).normalize()).magnitude() < 1e-5);
+
+    controller.handle_event(&button(MouseButton::Left, ElementState::Released));
+    controller.handle_event(&scroll(2.0));
+    controller.update(&mut camera, STEP);
+    assert_close((camera.position - target).magnitude(), 4.0 * 0.81);
+}
+
+#[test]
+fn flying_moves_along_the_view_while_keys_are_held() {
+    let mut controller = FlyController::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Vector3::unit_z(),
+    );
+    let mut camera = Camera::new(Projection::Perspective {
+        fov_y: Deg(45.0).into(),
+        near: 0.1,
+        far: 10.0,
+    });
+    controller.handle_event(&key(VirtualKeyCode::W, ElementState::Pressed));
+    controller.update(&mut camera, STEP);
+    assert_points_close(camera.position, Point3::new(0.0, 1.0, 0.0));
+
+    controller.handle_event(&key(VirtualKeyCode::W, ElementState::Released));
+    controller.handle_event(&key(VirtualKeyCode::D, ElementState::Pressed));
+    controller.update(&mut camera, STEP);
+    assert_points_close(camera.position, Point3::new(1.0, 1.0, 0.0));
+
+    // Losing focus releases every key, as the release would never arrive.
+    controller.handle_event(&WindowEvent::Focused(false));
+    controller.update(&mut camera, STEP);
+    assert_points_close(camera.position, Point3::new(1.0, 1.0, 0.0));
+}
+
+#[test]
+fn pan_zoom_drags_the_plane_with_the_cursor() {
+    let mut controller = PanZoomController::new(Point2::new(0.0, 0.0), 2.0, 200);
+    let mut camera = Camera::new(Projection::Perspective {
+        fov_y: Deg(45.0).into(),
+        near: 0.1,
+        far: 10.0,
+    });
+    controller.handle_event(&cursor(0.0, 0.0));
+    controller.handle_event(&button(MouseButton::Left, ElementState::Pressed));
+    controller.handle_event(&cursor(100.0, 0.0));
+    controller.update(&mut camera, STEP);
+    assert_close(camera.position.x, -1.0);
+    assert_close(camera.position.y, 0.0);
+    assert!((camera.forward() - -Vector3::unit_z()).magnitude() < 1e-5);
+
+    controller.handle_event(&scroll(1.0));
+    controller.update(&mut camera, STEP);
+    match camera.projection {
+        Projection::Orthographic { height, .. } => assert_close(height, 1.8),
+        projection => panic!("expected an orthographic projection, not {:?}", projection),
+    }
+}
+
+#[test]
+fn reverse_z_renders_the_same_image() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let standard = common::render_frames(&mut renderer, 1);
+    let mut camera = *renderer.camera();
+    camera.reverse_z = true;
+    renderer.set_camera(camera).unwrap();
+    renderer.set_clock(wild::renderer::Clock::fixed(std::time::Duration::ZERO));
+    let reversed = renderer.capture_frame().unwrap();
+    assert_eq!(standard.pixels, reversed.pixels);
+}
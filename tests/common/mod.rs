@@ -0,0 +1,138 @@
+#![allow(dead_code)]
+
+use wild::renderer::{Clock, Frame, Renderer, RendererConfig, RendererError};
+
+pub const WIDTH: u32 = 256;
+pub const HEIGHT: u32 = 256;
+pub const FRAME_STEP: std::time::Duration = std::time::Duration::from_millis(16);
+
+/// Returns a headless renderer, or `None` when no Vulkan driver is installed.
+///
+/// Set `WILD_REQUIRE_VULKAN=1` in CI to turn a missing driver into a failure instead.
+pub fn headless_renderer() -> Option<Renderer> {
+    headless_renderer_with_config(&RendererConfig::default())
+}
+
+/// Like `headless_renderer`, configured by `config`.
+pub fn headless_renderer_with_config(config: &RendererConfig) -> Option<Renderer> {
+    let mut renderer = match Renderer::new_headless_with_config(WIDTH, HEIGHT, config) {
+        Ok(renderer) => renderer,
+        Err(error @ RendererError::LoaderMissing(_))
+        | Err(error @ RendererError::NoSuitablePhysicalDevice) => {
+            assert!(
+                std::env::var_os("WILD_REQUIRE_VULKAN").is_none(),
+                "WILD_REQUIRE_VULKAN is set but no Vulkan driver is available: {}",
+                error
+            );
+            eprintln!("Skipping: {}", error);
+            return None;
+        }
+        Err(error) => panic!("Failed to create a headless renderer: {}", error),
+    };
+    renderer.set_clock(Clock::fixed(FRAME_STEP));
+    Some(renderer)
+}
+
+/// Renders `frames` frames with the fixed clock and captures the last one.
+pub fn render_frames(renderer: &mut Renderer, frames: u32) -> Frame {
+    (1..frames).for_each(|_| renderer.render().unwrap());
+    renderer.capture_frame().unwrap()
+}
+
+/// Encodes 8-bit pixels of `color_type` as a PNG file.
+pub fn encode_png(width: u32, height: u32, color_type: png::ColorType, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(color_type);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(data)
+        .unwrap();
+    bytes
+}
+
+/// Compares `frame` against `tests/golden/<name>.png`.
+///
+/// A pixel matches when no channel differs by more than `tolerance`. On failure the captured
+/// frame and an amplified diff image are written next to the test binary's temp directory.
+/// Run with `WILD_BLESS=1` to (re)write the reference image instead.
+pub fn assert_golden(name: &str, frame: &Frame, tolerance: u8, max_mismatched_pixels: usize) {
+    let reference_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.png", name));
+    let output_dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    if std::env::var_os("WILD_BLESS").is_some() {
+        std::fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+        frame.save_png(&reference_path).unwrap();
+        return;
+    }
+
+    let reference = match Frame::load_png(&reference_path) {
+        Ok(reference) => reference,
+        Err(error) => {
+            let actual_path = output_dir.join(format!("{}.actual.png", name));
+            frame.save_png(&actual_path).unwrap();
+            panic!(
+                "Failed to load {}: {} (captured frame written to {}, rerun with WILD_BLESS=1 to accept it)",
+                reference_path.display(),
+                error,
+                actual_path.display()
+            );
+        }
+    };
+    assert_eq!(
+        (reference.width, reference.height),
+        (frame.width, frame.height),
+        "{} has a different size than the captured frame",
+        reference_path.display()
+    );
+
+    let diff = Frame {
+        width: frame.width,
+        height: frame.height,
+        pixels: frame
+            .pixels
+            .chunks_exact(4)
+            .zip(reference.pixels.chunks_exact(4))
+            .flat_map(|(actual, expected)| {
+                let difference = actual
+                    .iter()
+                    .zip(expected)
+                    .map(|(actual, expected)| actual.abs_diff(*expected))
+                    .max()
+                    .unwrap();
+                let value = if difference > tolerance {
+                    255
+                } else {
+                    difference
+                };
+                [value, 0, 0, 255]
+            })
+            .collect(),
+    };
+    let mismatched_pixels = diff
+        .pixels
+        .chunks_exact(4)
+        .filter(|pixel| pixel[0] == 255)
+        .count();
+
+    if mismatched_pixels > max_mismatched_pixels {
+        let actual_path = output_dir.join(format!("{}.actual.png", name));
+        let diff_path = output_dir.join(format!("{}.diff.png", name));
+        frame.save_png(&actual_path).unwrap();
+        diff.save_png(&diff_path).unwrap();
+        panic!(
+            "{} pixels differ from {} by more than {} (actual: {}, diff: {})",
+            mismatched_pixels,
+            reference_path.display(),
+            tolerance,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use wild::renderer::{Indices, MeshData, MeshVertex, ModelData, PrimitiveData};
+
+/// A white quad around the origin in the plane the default view looks down on.
+fn quad_model() -> ModelData {
+    let corner = |x, y| MeshVertex {
+        position: [x, y, 0.0],
+        normal: [0.0, 0.0, 1.0],
+        color: [1.0; 4],
+        ..Default::default()
+    };
+    ModelData {
+        meshes: vec![MeshData {
+            name: None,
+            primitives: vec![PrimitiveData {
+                vertices: vec![
+                    corner(-0.5, -0.5),
+                    corner(0.5, -0.5),
+                    corner(0.5, 0.5),
+                    corner(-0.5, 0.5),
+                ],
+                indices: Indices::U16(vec![0, 1, 2, 2, 3, 0]),
+                material: None,
+            }],
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn empty_draw_lists_only_clear() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let draw_list = renderer.begin_frame();
+    assert!(draw_list.is_empty());
+    let frame = renderer.end_frame_and_capture(draw_list).unwrap();
+    assert!(frame
+        .pixels
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0, 0, 0, 255]));
+}
+
+#[test]
+fn meshes_are_drawn_with_their_transforms() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let model = renderer.load_model(&quad_model()).unwrap();
+    while !renderer.is_model_ready(&model) {
+        renderer.render().unwrap();
+    }
+
+    let mut draw_list = renderer.begin_frame();
+    draw_list.draw(
+        &model.meshes[0],
+        renderer.default_material(),
+        cgmath::SquareMatrix::identity(),
+    );
+    assert_eq!(draw_list.len(), 1);
+    let frame = renderer.end_frame_and_capture(draw_list).unwrap();
+    assert_eq!(frame.pixel(frame.width / 2, frame.height / 2), [255; 4]);
+
+    // Moved past the far plane, the quad is clipped away.
+    let mut draw_list = renderer.begin_frame();
+    draw_list.draw(
+        &model.meshes[0],
+        renderer.default_material(),
+        cgmath::Matrix4::from_translation(cgmath::vec3(0.0, 0.0, -100.0)),
+    );
+    let frame = renderer.end_frame_and_capture(draw_list).unwrap();
+    assert_eq!(
+        frame.pixel(frame.width / 2, frame.height / 2),
+        [0, 0, 0, 255]
+    );
+
+    renderer.destroy_model(model).unwrap();
+}
@@ -0,0 +1,21 @@
+mod common;
+
+#[test]
+fn quad_first_frame() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let frame = common::render_frames(&mut renderer, 1);
+    common::assert_golden("quad_first_frame", &frame, 2, 16);
+}
+
+#[test]
+fn quad_rotated() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let frame = common::render_frames(&mut renderer, 30);
+    common::assert_golden("quad_rotated", &frame, 2, 16);
+}
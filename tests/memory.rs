@@ -0,0 +1,18 @@
+mod common;
+
+#[test]
+fn resources_share_memory_blocks() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    common::render_frames(&mut renderer, 2);
+    let statistics = renderer.memory_statistics();
+    assert!(statistics.allocation_count >= 4, "{:?}", statistics);
+    assert!(
+        statistics.block_count < statistics.allocation_count,
+        "{:?}",
+        statistics
+    );
+    assert!(statistics.allocated_bytes <= statistics.block_bytes);
+}
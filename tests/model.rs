@@ -0,0 +1,371 @@
+mod common;
+
+use ash::vk;
+use base64::Engine;
+use wild::renderer::{
+    AlphaMode, Indices, MeshData, MeshVertex, ModelData, NormalGeneration, PrimitiveData,
+    RendererError,
+};
+
+/// A red triangle facing +Z with u32 indices and no normals, under a translated root node.
+const TRIANGLE_GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [
+        { "name": "root", "translation": [1, 2, 3], "children": [1] },
+        { "name": "child", "mesh": 0 }
+    ],
+    "meshes": [{
+        "name": "triangle",
+        "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }]
+    }],
+    "materials": [{
+        "name": "painted",
+        "pbrMetallicRoughness": {
+            "baseColorFactor": [1, 0, 0, 1],
+            "baseColorTexture": { "index": 0 },
+            "metallicFactor": 0.25,
+            "roughnessFactor": 0.75,
+            "metallicRoughnessTexture": { "index": 1 }
+        },
+        "alphaMode": "MASK",
+        "alphaCutoff": 0.25,
+        "doubleSided": true
+    }],
+    "textures": [{ "source": 0, "sampler": 0 }, { "source": 1 }],
+    "samplers": [{ "magFilter": 9728, "minFilter": 9984, "wrapS": 33071 }],
+    "images": [{ "uri": "IMAGE_URI" }, { "uri": "IMAGE_URI" }],
+    "buffers": [BUFFER],
+    "bufferViews": [
+        { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+        { "buffer": 0, "byteOffset": 36, "byteLength": 12 }
+    ],
+    "accessors": [
+        {
+            "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+            "min": [0, 0, 0], "max": [1, 1, 0]
+        },
+        { "bufferView": 1, "componentType": 5125, "count": 3, "type": "SCALAR" }
+    ]
+}"#;
+
+fn triangle_buffer() -> Vec<u8> {
+    let positions: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+    let indices: [u32; 3] = [0, 1, 2];
+    [
+        bytemuck::cast_slice::<f32, u8>(&positions),
+        bytemuck::cast_slice::<u32, u8>(&indices),
+    ]
+    .concat()
+}
+
+fn triangle_json(buffer: &str) -> String {
+    let png = common::encode_png(1, 1, png::ColorType::Rgba, &[255; 4]);
+    let image_uri = format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(png)
+    );
+    TRIANGLE_GLTF
+        .replace("IMAGE_URI", &image_uri)
+        .replace("BUFFER", buffer)
+}
+
+/// Packs a JSON and a binary chunk into a `.glb` container.
+fn encode_glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let mut json = json.as_bytes().to_vec();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut bin = bin.to_vec();
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(length);
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2_u32.to_le_bytes());
+    glb.extend_from_slice(&(length as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&bin);
+    glb
+}
+
+fn assert_triangle(model: &ModelData) {
+    assert_eq!(model.meshes.len(), 1);
+    assert_eq!(model.meshes[0].name.as_deref(), Some("triangle"));
+    let primitive = &model.meshes[0].primitives[0];
+    assert_eq!(primitive.material, Some(0));
+    assert_eq!(primitive.vertices.len(), 3);
+    assert!(primitive
+        .vertices
+        .iter()
+        .all(|vertex| vertex.normal == [0.0, 0.0, 1.0] && vertex.color == [1.0; 4]));
+    assert_eq!(primitive.indices, Indices::U32(vec![0, 1, 2]));
+
+    assert_eq!(model.roots, [0]);
+    assert_eq!(model.nodes[0].children, [1]);
+    assert_eq!(model.nodes[1].mesh, Some(0));
+    assert_eq!(
+        model.nodes[0].transform,
+        cgmath::Matrix4::from_translation(cgmath::vec3(1.0, 2.0, 3.0))
+    );
+}
+
+#[test]
+fn glb_models_are_read() {
+    let bin = triangle_buffer();
+    let json = triangle_json(&format!(r#"{{ "byteLength": {} }}"#, bin.len()));
+    let model = ModelData::from_gltf(&encode_glb(&json, &bin), None).unwrap();
+    assert_triangle(&model);
+
+    let material = &model.materials[0];
+    assert_eq!(material.name.as_deref(), Some("painted"));
+    assert_eq!(material.base_color_factor, [1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(
+        (material.metallic_factor, material.roughness_factor),
+        (0.25, 0.75)
+    );
+    assert_eq!(material.alpha_mode, AlphaMode::Mask(0.25));
+    assert!(material.double_sided);
+
+    let sampler = model.textures[0].sampler;
+    assert_eq!(sampler.mag_filter, vk::Filter::NEAREST);
+    assert_eq!(sampler.mipmap_mode, vk::SamplerMipmapMode::NEAREST);
+    assert_eq!(
+        sampler.address_mode_u,
+        vk::SamplerAddressMode::CLAMP_TO_EDGE
+    );
+    assert_eq!(sampler.address_mode_v, vk::SamplerAddressMode::REPEAT);
+    // Color stays sRGB while the metallic-roughness data is read linearly.
+    assert_eq!(model.images[0].format, vk::Format::R8G8B8A8_SRGB);
+    assert_eq!(model.images[1].format, vk::Format::R8G8B8A8_UNORM);
+}
+
+#[test]
+fn external_buffers_resolve_relative_to_the_file() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("external model");
+    std::fs::create_dir_all(&dir).unwrap();
+    let bin = triangle_buffer();
+    std::fs::write(dir.join("triangle data.bin"), &bin).unwrap();
+    let json = triangle_json(&format!(
+        r#"{{ "uri": "triangle%20data.bin", "byteLength": {} }}"#,
+        bin.len()
+    ));
+    std::fs::write(dir.join("triangle.gltf"), json).unwrap();
+
+    assert_triangle(&ModelData::from_file(dir.join("triangle.gltf")).unwrap());
+}
+
+#[test]
+fn unresolvable_buffers_are_rejected() {
+    let json = triangle_json(r#"{ "uri": "missing.bin", "byteLength": 48 }"#);
+    assert!(matches!(
+        ModelData::from_gltf(json.as_bytes(), None),
+        Err(RendererError::ModelLoad(_))
+    ));
+}
+
+#[test]
+fn models_upload_and_can_be_destroyed() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let bin = triangle_buffer();
+    let json = triangle_json(&format!(r#"{{ "byteLength": {} }}"#, bin.len()));
+    let data = ModelData::from_gltf(&encode_glb(&json, &bin), None).unwrap();
+    let model = renderer.load_model(&data).unwrap();
+    assert_eq!(model.meshes[0].primitives.len(), 1);
+    assert_eq!(model.textures.len(), 2);
+    common::render_frames(&mut renderer, 1);
+    renderer.render().unwrap();
+    assert!(renderer.is_model_ready(&model));
+    renderer.destroy_model(model).unwrap();
+}
+
+#[test]
+fn empty_primitives_are_rejected() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let data = ModelData {
+        meshes: vec![MeshData {
+            name: None,
+            primitives: vec![PrimitiveData {
+                vertices: vec![MeshVertex::default(); 3],
+                indices: Indices::U16(Vec::new()),
+                material: None,
+            }],
+        }],
+        ..Default::default()
+    };
+    assert!(matches!(
+        renderer.load_model(&data),
+        Err(RendererError::EmptyPrimitive {
+            vertices: 3,
+            indices: 0
+        })
+    ));
+}
+
+/// A unit quad split along an edge that has two texture coordinates, like a texture seam.
+const QUAD_OBJ: &str = "mtllib quad.mtl
+o quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vt 0.5 0.5
+usemtl painted
+f 1/1 2/2 3/3
+f 1/5 3/3 4/4
+";
+
+const QUAD_MTL: &str = "newmtl painted
+Kd 0.5 0.25 1
+d 0.5
+Ns 198
+Ke 1 2 3
+map_Kd white.png
+norm white.png
+";
+
+fn write_quad_obj() -> std::path::PathBuf {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("obj model");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("quad.obj"), QUAD_OBJ).unwrap();
+    std::fs::write(dir.join("quad.mtl"), QUAD_MTL).unwrap();
+    let png = common::encode_png(1, 1, png::ColorType::Rgba, &[255; 4]);
+    std::fs::write(dir.join("white.png"), png).unwrap();
+    dir.join("quad.obj")
+}
+
+#[test]
+fn obj_corners_are_deduplicated() {
+    let model = ModelData::from_file(write_quad_obj()).unwrap();
+    assert_eq!((model.meshes.len(), model.roots.len()), (1, 1));
+    let primitive = &model.meshes[0].primitives[0];
+    assert_eq!(primitive.material, Some(0));
+    // Corner 1 appears with two texture coordinates, so only vertex 3 is shared.
+    assert_eq!(primitive.vertices.len(), 5);
+    assert_eq!(primitive.indices, Indices::U16(vec![0, 1, 2, 3, 2, 4]));
+    assert_eq!(primitive.vertices[0].tex_coord, [0.0, 1.0]);
+    assert!(primitive
+        .vertices
+        .iter()
+        .all(|vertex| vertex.normal == [0.0, 0.0, 1.0]));
+
+    let material = &model.materials[0];
+    assert_eq!(material.base_color_factor, [0.5, 0.25, 1.0, 0.5]);
+    assert_eq!(material.alpha_mode, AlphaMode::Blend);
+    assert_eq!(material.roughness_factor, 0.1);
+    assert_eq!(material.emissive_factor, [1.0, 2.0, 3.0]);
+    // The same file is loaded once per color space it is read in.
+    let base_color = model.textures[material.base_color_texture.unwrap()].image;
+    let normal = model.textures[material.normal_texture.unwrap()].image;
+    assert_eq!(model.images[base_color].format, vk::Format::R8G8B8A8_SRGB);
+    assert_eq!(model.images[normal].format, vk::Format::R8G8B8A8_UNORM);
+}
+
+#[test]
+fn obj_normals_can_be_flat() {
+    let model = ModelData::from_obj(
+        QUAD_OBJ.replace("mtllib quad.mtl\n", "").as_bytes(),
+        None,
+        NormalGeneration::Flat,
+    )
+    .unwrap();
+    let primitive = &model.meshes[0].primitives[0];
+    assert_eq!(primitive.vertices.len(), 6);
+    assert_eq!(primitive.material, None);
+}
+
+const QUAD_PLY_HEADER: &str = "ply
+format FORMAT 1.0
+comment a unit quad with one colored corner
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+fn assert_quad_ply(model: &ModelData) {
+    let primitive = &model.meshes[0].primitives[0];
+    assert_eq!(primitive.vertices.len(), 4);
+    assert_eq!(primitive.indices, Indices::U16(vec![0, 1, 2, 0, 2, 3]));
+    assert_eq!(primitive.vertices[2].position, [1.0, 1.0, 0.0]);
+    assert_eq!(primitive.vertices[1].color, [1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(primitive.vertices[0].color, [1.0; 4]);
+    assert!(primitive
+        .vertices
+        .iter()
+        .all(|vertex| vertex.normal == [0.0, 0.0, 1.0]));
+}
+
+#[test]
+fn ascii_ply_is_read() {
+    let ply = QUAD_PLY_HEADER.replace("FORMAT", "ascii")
+        + "0 0 0 255 255 255\n1 0 0 255 0 0\n1 1 0 255 255 255\n0 1 0 255 255 255\n4 0 1 2 3\n";
+    assert_quad_ply(&ModelData::from_ply(ply.as_bytes(), NormalGeneration::Smooth).unwrap());
+}
+
+#[test]
+fn binary_ply_is_read() {
+    let positions: [[f32; 3]; 4] = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+    ];
+    let colors = [[255; 3], [255, 0, 0], [255; 3], [255; 3]];
+    for big_endian in [false, true] {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut ply = QUAD_PLY_HEADER.replace("FORMAT", format).into_bytes();
+        let push_word = |word: [u8; 4], ply: &mut Vec<u8>| {
+            ply.extend(if big_endian {
+                [word[3], word[2], word[1], word[0]]
+            } else {
+                word
+            })
+        };
+        for (position, color) in positions.iter().zip(&colors) {
+            for coordinate in position {
+                push_word(coordinate.to_le_bytes(), &mut ply);
+            }
+            ply.extend_from_slice(color);
+        }
+        ply.push(4);
+        for index in 0..4_i32 {
+            push_word(index.to_le_bytes(), &mut ply);
+        }
+        assert_quad_ply(&ModelData::from_ply(&ply, NormalGeneration::Smooth).unwrap());
+    }
+}
+
+#[test]
+fn ply_faces_must_reference_vertices() {
+    let ply = QUAD_PLY_HEADER
+        .replace("FORMAT", "ascii")
+        .replace("vertex 4", "vertex 1")
+        + "0 0 0 255 255 255\n4 0 1 2 3\n";
+    assert!(matches!(
+        ModelData::from_ply(ply.as_bytes(), NormalGeneration::Smooth),
+        Err(RendererError::ModelLoad(_))
+    ));
+}
@@ -0,0 +1,22 @@
+mod common;
+
+#[test]
+fn sample_count_can_change_at_runtime() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    assert_eq!(renderer.msaa_samples(), 1);
+    let single_sampled = common::render_frames(&mut renderer, 1);
+
+    renderer.set_msaa_samples(4).unwrap();
+    let samples = renderer.msaa_samples();
+    assert!(samples.is_power_of_two() && (1..=4).contains(&samples));
+    let multisampled = common::render_frames(&mut renderer, 1);
+    assert_eq!(multisampled.width, single_sampled.width);
+    assert_eq!(multisampled.height, single_sampled.height);
+
+    renderer.set_msaa_samples(1).unwrap();
+    assert_eq!(renderer.msaa_samples(), 1);
+    common::render_frames(&mut renderer, 1);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use std::path::PathBuf;
+use wild::renderer::RendererConfig;
+
+fn cache_path(name: &str) -> PathBuf {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("pipeline_cache");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.bin", name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn config(path: &std::path::Path) -> RendererConfig {
+    RendererConfig {
+        pipeline_cache_path: Some(path.to_path_buf()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn pipeline_caches_are_saved_on_shutdown_and_reused() {
+    let path = cache_path("pipeline_caches_are_saved_on_shutdown_and_reused");
+    let mut renderer = match common::headless_renderer_with_config(&config(&path)) {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let before = common::render_frames(&mut renderer, 2);
+    drop(renderer);
+    let saved = std::fs::read(&path).unwrap();
+    assert!(saved.starts_with(b"WPLC"));
+
+    let mut renderer = common::headless_renderer_with_config(&config(&path)).unwrap();
+    let after = common::render_frames(&mut renderer, 2);
+    assert_eq!(before.pixels, after.pixels);
+}
+
+#[test]
+fn foreign_pipeline_caches_are_replaced() {
+    let path = cache_path("foreign_pipeline_caches_are_replaced");
+    std::fs::write(&path, [0xab; 64]).unwrap();
+    let renderer = match common::headless_renderer_with_config(&config(&path)) {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    drop(renderer);
+    assert!(std::fs::read(&path).unwrap().starts_with(b"WPLC"));
+}
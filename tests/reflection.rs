@@ -0,0 +1,196 @@
+use ash::vk;
+use std::path::Path;
+use wild::renderer::{
+    Material, MeshVertex, RendererError, ShaderCompiler, ShaderReflection, ShaderStage,
+    VertexLayout,
+};
+
+fn reflect(source: &str, stage: ShaderStage) -> ShaderReflection {
+    let code = ShaderCompiler::new()
+        .compile_source(source, Path::new("test.glsl"), stage)
+        .unwrap();
+    ShaderReflection::new(&code).unwrap()
+}
+
+fn reflect_file(name: &str) -> ShaderReflection {
+    reflect_file_with(ShaderCompiler::new(), name)
+}
+
+fn reflect_file_with(compiler: ShaderCompiler, name: &str) -> ShaderReflection {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("shaders")
+        .join(name);
+    let code = compiler
+        .compile_file(&path, ShaderStage::from_path(&path).unwrap())
+        .unwrap();
+    ShaderReflection::new(&code).unwrap()
+}
+
+#[test]
+fn builtin_shaders_declare_their_interface() {
+    let vertex = reflect_file("shader.vert");
+    assert_eq!(vertex.bindings.len(), 1);
+    assert_eq!((vertex.bindings[0].set, vertex.bindings[0].binding), (0, 0));
+    assert_eq!(
+        vertex.bindings[0].descriptor_type,
+        vk::DescriptorType::UNIFORM_BUFFER
+    );
+    let push_constants = vertex.push_constants.unwrap();
+    assert_eq!(push_constants.stage_flags, vk::ShaderStageFlags::VERTEX);
+    assert_eq!(push_constants.size, 80);
+    let formats: Vec<_> = vertex
+        .vertex_inputs
+        .iter()
+        .map(|input| (input.location, input.format))
+        .collect();
+    assert_eq!(
+        formats,
+        [
+            (0, vk::Format::R32G32B32_SFLOAT),
+            (1, vk::Format::R32G32B32_SFLOAT),
+            (2, vk::Format::R32G32B32A32_SFLOAT),
+            (3, vk::Format::R32G32_SFLOAT),
+            (4, vk::Format::R32G32B32A32_SFLOAT),
+        ]
+    );
+
+    let fragment = reflect_file("shader.frag");
+    let descriptor_types: Vec<_> = fragment
+        .bindings
+        .iter()
+        .map(|binding| (binding.set, binding.binding, binding.descriptor_type))
+        .collect();
+    assert_eq!(
+        descriptor_types,
+        [
+            (1, 0, vk::DescriptorType::SAMPLED_IMAGE),
+            (1, 1, vk::DescriptorType::SAMPLER),
+        ]
+    );
+    assert!(fragment.vertex_inputs.is_empty());
+
+    for storage_format in ["rgba8", "rgba8_snorm", "rgba16f", "r32f", "rgba32f"] {
+        let mut compiler = ShaderCompiler::new();
+        compiler
+            .defines
+            .insert("STORAGE_FORMAT".to_owned(), storage_format.to_owned());
+        let downsample = reflect_file_with(compiler, "mipmap.comp");
+        let descriptor_types: Vec<_> = downsample
+            .bindings
+            .iter()
+            .map(|binding| (binding.set, binding.binding, binding.descriptor_type))
+            .collect();
+        assert_eq!(
+            descriptor_types,
+            [
+                (0, 0, vk::DescriptorType::SAMPLED_IMAGE),
+                (0, 1, vk::DescriptorType::STORAGE_IMAGE),
+            ]
+        );
+    }
+
+    let merged = ShaderReflection::merge(&[vertex, fragment]).unwrap();
+    let material_bindings = merged.set_layout_bindings(1);
+    assert_eq!(material_bindings.len(), 2);
+    assert!(material_bindings
+        .iter()
+        .all(|binding| binding.stage_flags == vk::ShaderStageFlags::FRAGMENT));
+    merged
+        .check_vertex_layout(&MeshVertex::attribute_descriptions(0))
+        .unwrap();
+}
+
+#[test]
+fn stages_must_agree_on_shared_bindings() {
+    let vertex = reflect(
+        "#version 450\n\
+         layout(set = 0, binding = 0) uniform Frame { mat4 view; } frame;\n\
+         void main() { gl_Position = frame.view[0]; }\n",
+        ShaderStage::Vertex,
+    );
+    let fragment = reflect(
+        "#version 450\n\
+         layout(set = 0, binding = 0) buffer Frame { vec4 color; } frame;\n\
+         layout(location = 0) out vec4 outColor;\n\
+         void main() { outColor = frame.color; }\n",
+        ShaderStage::Fragment,
+    );
+    match ShaderReflection::merge(&[vertex, fragment]) {
+        Err(RendererError::ShaderReflection(message)) => {
+            assert!(message.contains("binding 0"), "{}", message)
+        }
+        result => panic!("Expected a reflection error, got {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn vertex_layouts_must_match_the_inputs() {
+    let vertex = reflect(
+        "#version 450\n\
+         layout(location = 0) in vec3 inPosition;\n\
+         layout(location = 1) in uvec4 inJoints;\n\
+         void main() { gl_Position = vec4(inPosition, float(inJoints.x)); }\n",
+        ShaderStage::Vertex,
+    );
+    let attribute = |location, format| vk::VertexInputAttributeDescription {
+        location,
+        binding: 0,
+        format,
+        offset: 0,
+    };
+
+    // Component counts may differ as long as the numeric types agree.
+    vertex
+        .check_vertex_layout(&[
+            attribute(0, vk::Format::R32G32_SFLOAT),
+            attribute(1, vk::Format::R16G16B16A16_UINT),
+        ])
+        .unwrap();
+    // sRGB formats read as floats, and packed integer formats as integers.
+    vertex
+        .check_vertex_layout(&[
+            attribute(0, vk::Format::R8G8B8A8_SRGB),
+            attribute(1, vk::Format::A2B10G10R10_UINT_PACK32),
+        ])
+        .unwrap();
+
+    match vertex.check_vertex_layout(&[
+        attribute(0, vk::Format::R32G32B32_SFLOAT),
+        attribute(1, vk::Format::R8G8B8A8_UNORM),
+    ]) {
+        Err(RendererError::VertexLayoutMismatch {
+            location: 1,
+            shader_format: vk::Format::R32G32B32A32_UINT,
+            layout_format: Some(vk::Format::R8G8B8A8_UNORM),
+        }) => {}
+        result => panic!("Expected a vertex layout mismatch, got {:?}", result),
+    }
+
+    let missing = vertex
+        .check_vertex_layout(&[attribute(0, vk::Format::R32G32B32_SFLOAT)])
+        .unwrap_err();
+    assert!(missing.to_string().contains("no attribute"), "{}", missing);
+}
+
+#[test]
+fn material_sets_only_bind_textures_and_samplers() {
+    let fragment = reflect(
+        "#version 450\n\
+         layout(set = 1, binding = 0) uniform texture2D baseColor;\n\
+         layout(set = 1, binding = 1) uniform sampler baseColorSampler;\n\
+         layout(set = 1, binding = 2) uniform Factors { vec4 color; } factors;\n\
+         layout(location = 0) out vec4 outColor;\n\
+         void main() {\n\
+             outColor = factors.color * texture(sampler2D(baseColor, baseColorSampler), vec2(0.0));\n\
+         }\n",
+        ShaderStage::Fragment,
+    );
+    let bindings = fragment.set_layout_bindings(1);
+    Material::check_set_layout_bindings(&bindings[..2]).unwrap();
+    match Material::check_set_layout_bindings(&bindings) {
+        Err(RendererError::ShaderReflection(message)) => {
+            assert!(message.contains("binding 2"), "{}", message)
+        }
+        result => panic!("Expected a reflection error, got {:?}", result),
+    }
+}
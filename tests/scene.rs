@@ -0,0 +1,135 @@
+mod common;
+
+use cgmath::{Matrix4, Point3, SquareMatrix};
+use wild::renderer::{
+    Aabb, Indices, Light, MeshData, MeshVertex, ModelData, NodeData, PrimitiveData, Scene,
+};
+
+fn unit_cube() -> Aabb {
+    Aabb {
+        min: Point3::new(-1.0, -1.0, -1.0),
+        max: Point3::new(1.0, 1.0, 1.0),
+    }
+}
+
+#[test]
+fn aabbs_grow_to_hold_transformed_corners() {
+    assert_eq!(Aabb::from_points(Vec::new()), None);
+    let aabb = Aabb::from_points(vec![
+        Point3::new(1.0, -2.0, 0.5),
+        Point3::new(-1.0, 3.0, 0.0),
+    ])
+    .unwrap();
+    assert_eq!(aabb.min, Point3::new(-1.0, -2.0, 0.0));
+    assert_eq!(aabb.max, Point3::new(1.0, 3.0, 0.5));
+
+    let moved = unit_cube().transformed(&Matrix4::from_translation(cgmath::vec3(2.0, 0.0, 0.0)));
+    assert_eq!(moved.min, Point3::new(1.0, -1.0, -1.0));
+    assert_eq!(moved.union(&unit_cube()).max, Point3::new(3.0, 1.0, 1.0));
+
+    // A rotated box is bounded by its rotated corners.
+    let rotated = unit_cube().transformed(&Matrix4::from_angle_z(cgmath::Deg(45.0)));
+    assert!((rotated.max.x - 2_f32.sqrt()).abs() < 1e-5);
+}
+
+#[test]
+fn boxes_outside_the_frustum_are_rejected() {
+    let view_projection = cgmath::perspective(cgmath::Deg(90.0), 1.0, 0.1, 10.0)
+        * Matrix4::look_at_rh(
+            Point3::new(0.0, 0.0, 5.0),
+            Point3::new(0.0, 0.0, 0.0),
+            cgmath::vec3(0.0, 1.0, 0.0),
+        );
+    let at = |x, y, z| unit_cube().transformed(&Matrix4::from_translation(cgmath::vec3(x, y, z)));
+    assert!(at(0.0, 0.0, 0.0).intersects_frustum(&view_projection));
+    assert!(!at(0.0, 0.0, 10.0).intersects_frustum(&view_projection));
+    assert!(!at(0.0, 0.0, -10.0).intersects_frustum(&view_projection));
+    assert!(!at(20.0, 0.0, 0.0).intersects_frustum(&view_projection));
+}
+
+#[test]
+fn world_transforms_compose_down_the_tree() {
+    let mut scene = Scene::new();
+    let parent = scene.add_node(None, Matrix4::from_translation(cgmath::vec3(1.0, 0.0, 0.0)));
+    let child = scene.add_node(Some(parent), Matrix4::from_scale(2.0));
+    scene.node_mut(child).light = Some(Light::Point {
+        color: [1.0; 3],
+        intensity: 10.0,
+        range: None,
+    });
+    scene.update();
+    assert_eq!(scene.roots(), [parent]);
+    assert_eq!(scene.node(parent).children(), [child]);
+    assert_eq!(scene.node(child).parent(), Some(parent));
+    assert_eq!(
+        scene.node(child).world_transform(),
+        Matrix4::from_translation(cgmath::vec3(1.0, 0.0, 0.0)) * Matrix4::from_scale(2.0)
+    );
+
+    scene.set_transform(parent, Matrix4::identity());
+    scene.update();
+    let (_, transform) = scene.lights().next().unwrap();
+    assert_eq!(transform, Matrix4::from_scale(2.0));
+    assert_eq!(scene.node(child).bounds(), None);
+}
+
+#[test]
+fn bounds_propagate_up_from_meshes() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let vertex = |x, y, z| MeshVertex {
+        position: [x, y, z],
+        ..Default::default()
+    };
+    let data = ModelData {
+        meshes: vec![MeshData {
+            name: None,
+            primitives: vec![PrimitiveData {
+                vertices: vec![
+                    vertex(0.0, 0.0, 0.0),
+                    vertex(1.0, 0.0, 0.0),
+                    vertex(0.0, 1.0, 0.0),
+                ],
+                indices: Indices::U16(vec![0, 1, 2]),
+                material: None,
+            }],
+        }],
+        nodes: vec![
+            NodeData {
+                name: Some("base".to_string()),
+                transform: Matrix4::identity(),
+                mesh: Some(0),
+                camera: None,
+                children: vec![1],
+            },
+            NodeData {
+                name: Some("arm".to_string()),
+                transform: Matrix4::from_translation(cgmath::vec3(0.0, 0.0, 4.0)),
+                mesh: Some(0),
+                camera: None,
+                children: Vec::new(),
+            },
+        ],
+        roots: vec![0],
+        ..Default::default()
+    };
+    let mut scene = Scene::new();
+    let model = scene.add_model(renderer.load_model(&data).unwrap(), None);
+    scene.set_transform(
+        model,
+        Matrix4::from_translation(cgmath::vec3(-1.0, 0.0, 0.0)),
+    );
+    scene.update();
+
+    let arm = scene.find("arm").unwrap();
+    let bounds = scene.node(arm).bounds().unwrap();
+    assert_eq!(bounds.min, Point3::new(-1.0, 0.0, 4.0));
+    let bounds = scene.node(model).bounds().unwrap();
+    assert_eq!(bounds.min, Point3::new(-1.0, 0.0, 0.0));
+    assert_eq!(bounds.max, Point3::new(0.0, 1.0, 4.0));
+
+    renderer.render_scene(&mut scene).unwrap();
+    renderer.destroy_scene(scene).unwrap();
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use wild::renderer::{RendererError, ShaderCompiler, ShaderStage, ShaderWatcher};
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// An empty directory for `name`'s shader files under the test binary's temp directory.
+fn shader_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("shader")
+        .join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(dir: &Path, name: &str, source: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+fn compile_error(result: wild::renderer::Result<Vec<u32>>) -> String {
+    match result {
+        Err(RendererError::ShaderCompile(message)) => message,
+        Err(error) => panic!("Expected a compile error, not {}", error),
+        Ok(_) => panic!("Expected a compile error"),
+    }
+}
+
+const FRAGMENT_SHADER: &str = r#"#version 450
+#include "color.glsl"
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = color();
+}
+"#;
+
+#[test]
+fn builtin_shaders_compile() {
+    let shaders = Path::new(env!("CARGO_MANIFEST_DIR")).join("shaders");
+    for name in ["shader.vert", "shader.frag"] {
+        let path = shaders.join(name);
+        let stage = ShaderStage::from_path(&path).unwrap();
+        let code = ShaderCompiler::new().compile_file(&path, stage).unwrap();
+        assert_eq!(code[0], SPIRV_MAGIC);
+    }
+}
+
+#[test]
+fn includes_and_defines_are_expanded() {
+    let dir = shader_dir("includes_and_defines_are_expanded");
+    let include_dir = dir.join("include");
+    std::fs::create_dir_all(&include_dir).unwrap();
+    write(
+        &include_dir,
+        "color.glsl",
+        "vec4 color() {\n    return vec4(RED, 0.0, 0.0, 1.0);\n}\n",
+    );
+    let path = write(&dir, "color.frag", FRAGMENT_SHADER);
+
+    let mut compiler = ShaderCompiler::new();
+    assert!(compiler.compile_file(&path, ShaderStage::Fragment).is_err());
+    compiler.include_dirs.push(include_dir);
+    compiler
+        .defines
+        .insert("RED".to_string(), "1.0".to_string());
+    let code = compiler.compile_file(&path, ShaderStage::Fragment).unwrap();
+    assert_eq!(code[0], SPIRV_MAGIC);
+}
+
+#[test]
+fn errors_point_at_the_file_and_line() {
+    let dir = shader_dir("errors_point_at_the_file_and_line");
+    write(
+        &dir,
+        "color.glsl",
+        "vec4 color() {\n    return vec4(undeclared, 0.0, 0.0, 1.0);\n}\n",
+    );
+    let path = write(&dir, "color.frag", FRAGMENT_SHADER);
+    let message = compile_error(ShaderCompiler::new().compile_file(&path, ShaderStage::Fragment));
+    let location = format!("{}:2:", dir.join("color.glsl").display());
+    assert!(message.contains(&location), "{}", message);
+
+    let missing = write(
+        &dir,
+        "missing.frag",
+        "#version 450\n\n#include \"missing.glsl\"\n",
+    );
+    let message =
+        compile_error(ShaderCompiler::new().compile_file(&missing, ShaderStage::Fragment));
+    assert!(message.contains("missing.frag:3"), "{}", message);
+
+    let cycle = write(&dir, "cycle.glsl", "#include \"./cycle.glsl\"\n");
+    let message = compile_error(ShaderCompiler::new().compile_file(&cycle, ShaderStage::Fragment));
+    assert!(message.contains("includes itself"), "{}", message);
+}
+
+#[test]
+fn compiled_shaders_are_cached_by_source() {
+    let dir = shader_dir("compiled_shaders_are_cached_by_source");
+    let cache_dir = dir.join("cache");
+    let cached = || std::fs::read_dir(&cache_dir).unwrap().count();
+    write(
+        &dir,
+        "color.glsl",
+        "vec4 color() {\n    return vec4(1.0);\n}\n",
+    );
+    let path = write(&dir, "color.frag", FRAGMENT_SHADER);
+    let compiler = ShaderCompiler {
+        cache_dir: Some(cache_dir.clone()),
+        ..Default::default()
+    };
+
+    let code = compiler.compile_file(&path, ShaderStage::Fragment).unwrap();
+    assert_eq!(cached(), 1);
+    assert_eq!(
+        compiler.compile_file(&path, ShaderStage::Fragment).unwrap(),
+        code
+    );
+    assert_eq!(cached(), 1);
+
+    // Editing an included file changes the key even though the including file is untouched.
+    write(
+        &dir,
+        "color.glsl",
+        "vec4 color() {\n    return vec4(0.5);\n}\n",
+    );
+    assert_ne!(
+        compiler.compile_file(&path, ShaderStage::Fragment).unwrap(),
+        code
+    );
+    assert_eq!(cached(), 2);
+
+    // A damaged entry is compiled again rather than handed to the driver.
+    for entry in std::fs::read_dir(&cache_dir).unwrap() {
+        std::fs::write(entry.unwrap().path(), [0; 8]).unwrap();
+    }
+    write(
+        &dir,
+        "color.glsl",
+        "vec4 color() {\n    return vec4(1.0);\n}\n",
+    );
+    assert_eq!(
+        compiler.compile_file(&path, ShaderStage::Fragment).unwrap(),
+        code
+    );
+}
+
+#[test]
+fn watchers_report_edited_files() {
+    let dir = shader_dir("watchers_report_edited_files");
+    let path = write(&dir, "color.glsl", "vec4 color();\n");
+    let mut watcher = ShaderWatcher::new(vec![path.clone()]);
+    watcher.interval = std::time::Duration::ZERO;
+    assert!(watcher.poll().is_empty());
+
+    write(&dir, "color.glsl", "vec4 color(float alpha);\n");
+    assert_eq!(watcher.poll(), std::slice::from_ref(&path));
+    assert!(watcher.poll().is_empty());
+
+    // Replacing a file by deleting and rewriting it is reported once it is back.
+    std::fs::remove_file(&path).unwrap();
+    assert!(watcher.poll().is_empty());
+    write(&dir, "color.glsl", "vec4 color();\n");
+    assert_eq!(watcher.poll(), std::slice::from_ref(&path));
+
+    // Edits made while the watched files are swapped out are still noticed.
+    write(&dir, "color.glsl", "vec4 color(vec2 uv);\n");
+    let other = write(&dir, "other.glsl", "");
+    watcher.watch(vec![path.clone(), other]);
+    assert_eq!(watcher.poll(), [path]);
+}
+
+#[test]
+fn reloading_shaders_swaps_the_pipeline() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    renderer.set_clock(wild::renderer::Clock::fixed(std::time::Duration::ZERO));
+    let before = common::render_frames(&mut renderer, 2);
+    renderer.reload_shaders().unwrap();
+    // Enough frames for the retired pipeline to be destroyed while the new one draws.
+    let after = common::render_frames(&mut renderer, 4);
+    assert_eq!(before.pixels, after.pixels);
+    assert!(renderer.shader_error().is_none());
+}
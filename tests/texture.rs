@@ -0,0 +1,408 @@
+mod common;
+
+use ash::vk;
+use wild::renderer::{RendererError, SamplerConfig, TextureData};
+
+#[test]
+fn png_is_decoded_to_rgba8() {
+    let rgb = common::encode_png(2, 1, png::ColorType::Rgb, &[255, 0, 0, 0, 0, 255]);
+    let texture = TextureData::from_memory(&rgb).unwrap();
+    assert_eq!((texture.width, texture.height), (2, 1));
+    assert_eq!(texture.pixels, [255, 0, 0, 255, 0, 0, 255, 255]);
+
+    let gray = common::encode_png(1, 1, png::ColorType::Grayscale, &[128]);
+    let texture = TextureData::from_memory(&gray).unwrap();
+    assert_eq!(texture.pixels, [128, 128, 128, 255]);
+}
+
+#[test]
+fn unknown_formats_are_rejected() {
+    assert!(matches!(
+        TextureData::from_memory(b"GIF89a"),
+        Err(RendererError::ImageDecode(_))
+    ));
+}
+
+#[test]
+fn texture_can_be_replaced() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let texture = TextureData::from_rgba8(2, 2, vec![255; 16]);
+    renderer
+        .set_texture(&texture, &SamplerConfig::default())
+        .unwrap();
+    common::render_frames(&mut renderer, 1);
+    renderer.render().unwrap();
+    assert!(renderer.is_ready());
+}
+
+#[test]
+fn mipmapped_textures_upload() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    // 8-bit sRGB mips are blitted; 32-bit float ones fall back to compute where needed.
+    let textures = [
+        TextureData::from_rgba8(64, 48, vec![200; 64 * 48 * 4]),
+        TextureData::from_rgba32f(37, 20, &vec![0.5; 37 * 20 * 4]),
+    ];
+    for texture in &textures {
+        renderer
+            .set_texture(texture, &SamplerConfig::default())
+            .unwrap();
+        common::render_frames(&mut renderer, 1);
+        renderer.render().unwrap();
+        assert!(renderer.is_ready());
+    }
+}
+
+fn encode_bc1_cube(width: u32, height: u32, mip_levels: u32) -> Vec<u8> {
+    let mut dds = ddsfile::Dds::new_dxgi(ddsfile::NewDxgiParams {
+        height,
+        width,
+        depth: None,
+        format: ddsfile::DxgiFormat::BC1_UNorm,
+        mipmap_levels: Some(mip_levels),
+        array_layers: Some(6),
+        caps2: None,
+        is_cubemap: true,
+        resource_dimension: ddsfile::D3D10ResourceDimension::Texture2D,
+        alpha_mode: ddsfile::AlphaMode::Unknown,
+    })
+    .unwrap();
+    // Every face is solid red in 4-color mode, tagged with its index in the unused color1.
+    let face_size = dds.data.len() / 6;
+    for (face, data) in dds.data.chunks_mut(face_size).enumerate() {
+        for block in data.chunks_mut(8) {
+            block.copy_from_slice(&[0x00, 0xf8, face as u8, 0x00, 0, 0, 0, 0]);
+        }
+    }
+    let mut bytes = Vec::new();
+    dds.write(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn dds_cube_levels_are_grouped_by_level() {
+    let texture = TextureData::from_memory(&encode_bc1_cube(8, 4, 2)).unwrap();
+    assert_eq!(texture.format, vk::Format::BC1_RGBA_UNORM_BLOCK);
+    assert_eq!((texture.array_layers, texture.cube), (6, true));
+    assert_eq!(texture.mip_levels(), 2);
+    assert_eq!(texture.view_type(), vk::ImageViewType::CUBE);
+    // Two blocks per face in level 0, one in level 1.
+    assert_eq!(texture.level_size(0).unwrap(), 6 * 16);
+    assert_eq!(texture.level_size(1).unwrap(), 6 * 8);
+    let level_1 = &texture.pixels[texture.level_offsets[1]..][..6 * 8];
+    let faces = level_1.chunks(8).map(|block| block[2]).collect::<Vec<_>>();
+    assert_eq!(faces, [0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn bc1_is_decompressed_to_rgba8() {
+    let texture = TextureData::from_memory(&encode_bc1_cube(4, 4, 1)).unwrap();
+    let decompressed = texture.decompressed().unwrap();
+    assert_eq!(decompressed.format, vk::Format::R8G8B8A8_UNORM);
+    assert_eq!((decompressed.width, decompressed.height), (4, 4));
+    assert_eq!(decompressed.array_layers, 6);
+    assert_eq!(decompressed.pixels.len(), 6 * 4 * 4 * 4);
+    assert!(decompressed
+        .pixels
+        .chunks(4)
+        .all(|texel| texel == [255, 0, 0, 255]));
+}
+
+#[test]
+fn bc1_blocks_with_ordered_endpoints_use_three_colors() {
+    // Blue as color0 sorts below red as color1; the first row indexes all four entries.
+    let block = [0x1f, 0x00, 0x00, 0xf8, 0xe4, 0, 0, 0];
+    let first_row = |format| {
+        let texture = TextureData::from_levels(4, 4, format, 1, false, &[&block]).unwrap();
+        texture.decompressed().unwrap().pixels[..16].to_vec()
+    };
+    let colors = [0, 0, 255, 255, 255, 0, 0, 255, 127, 0, 127, 255, 0, 0, 0];
+    assert_eq!(
+        first_row(vk::Format::BC1_RGB_UNORM_BLOCK),
+        [&colors[..], &[255]].concat()
+    );
+    assert_eq!(
+        first_row(vk::Format::BC1_RGBA_UNORM_BLOCK),
+        [&colors[..], &[0]].concat()
+    );
+}
+
+fn decompress_block(format: vk::Format, block: &[u8]) -> Vec<u8> {
+    let texture = TextureData::from_levels(4, 4, format, 1, false, &[block]).unwrap();
+    texture.decompressed().unwrap().pixels
+}
+
+/// Packs `(value, bits)` fields into a 16 byte block, least significant bits first.
+fn pack_bits(fields: &[(u32, u32)]) -> [u8; 16] {
+    let mut bits = 0u128;
+    let mut offset = 0;
+    for &(value, count) in fields {
+        bits |= (value as u128) << offset;
+        offset += count;
+    }
+    assert_eq!(offset, 128);
+    bits.to_le_bytes()
+}
+
+#[test]
+fn bc7_blocks_are_decompressed() {
+    // Mode 6 spreads red from 0 to 255 over the 4-bit weights, with texel i using index i. The
+    // endpoints' p-bits of 0 and 1 also take green and blue to 1 and alpha from 254.
+    let mut fields = vec![
+        (0x40, 7),
+        (0, 7),
+        (127, 7),
+        (0, 28),
+        (0x3fff, 14),
+        (0, 1),
+        (1, 1),
+    ];
+    fields.push((0, 3));
+    fields.extend((1..16).map(|index| (index, 4)));
+    let weights = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+    let pixels = decompress_block(vk::Format::BC7_UNORM_BLOCK, &pack_bits(&fields));
+    for (texel, weight) in pixels.chunks(4).zip(weights.iter()) {
+        let interpolate =
+            |value0: u32, value1: u32| (((64 - weight) * value0 + weight * value1 + 32) >> 6) as u8;
+        let (red, green, alpha) = (
+            interpolate(0, 255),
+            interpolate(0, 1),
+            interpolate(254, 255),
+        );
+        assert_eq!(texel, [red, green, green, alpha]);
+    }
+
+    // Mode 1 partition 13 splits the block into a red top half and a blue bottom half.
+    let fields = [
+        (0b10, 2),
+        (13, 6),
+        (63, 12),
+        (0, 12),
+        (0, 24),
+        (0, 12),
+        (63, 12),
+        (1, 1),
+        (1, 1),
+        (0, 46),
+    ];
+    let pixels = decompress_block(vk::Format::BC7_SRGB_BLOCK, &pack_bits(&fields));
+    assert_eq!(pixels[..32], [255, 2, 2, 255].repeat(8)[..]);
+    assert_eq!(pixels[32..], [2, 2, 255, 255].repeat(8)[..]);
+}
+
+#[test]
+fn etc2_blocks_are_decompressed() {
+    // A differential block of gray 132 whose first texel takes index 2 and the rest index 0.
+    let bits = (16u64 << 59) | (16 << 51) | (16 << 43) | (1 << 16);
+    let opaque = (bits | (1 << 33)).to_be_bytes();
+    let pixels = decompress_block(vk::Format::ETC2_R8G8B8_UNORM_BLOCK, &opaque);
+    assert_eq!(pixels[..4], [130, 130, 130, 255]);
+    assert!(pixels[4..]
+        .chunks(4)
+        .all(|texel| texel == [134, 134, 134, 255]));
+
+    // Without the opaque bit, punch-through blocks make index 2 transparent and drop index 0's
+    // modifier.
+    let pixels = decompress_block(vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK, &bits.to_be_bytes());
+    assert_eq!(pixels[..4], [0, 0, 0, 0]);
+    assert!(pixels[4..]
+        .chunks(4)
+        .all(|texel| texel == [132, 132, 132, 255]));
+
+    // RGBA8 blocks lead with an EAC alpha block: 200 plus a multiplier of 1 times -3.
+    let alpha = [200, 0x10, 0, 0, 0, 0, 0, 0];
+    let block = [&alpha[..], &opaque[..]].concat();
+    let pixels = decompress_block(vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK, &block);
+    assert_eq!(pixels[4..8], [134, 134, 134, 197]);
+}
+
+#[test]
+fn eac_and_signed_bc_blocks_are_decompressed() {
+    // Base 128 and modifier -3 times 8: 1004 of 2047.
+    let pixels = decompress_block(
+        vk::Format::EAC_R11_UNORM_BLOCK,
+        &[128, 0x10, 0, 0, 0, 0, 0, 0],
+    );
+    assert!(pixels.chunks(4).all(|texel| texel == [125, 0, 0, 255]));
+
+    // Base 16 * 8 and modifier -3 times 8: 104 of 1023, with green decoded from the second half.
+    let red = [16, 0x10, 0, 0, 0, 0, 0, 0];
+    let block = [&red[..], &[0x81, 0, 0, 0, 0, 0, 0, 0]].concat();
+    let texture = TextureData::from_levels(
+        4,
+        4,
+        vk::Format::EAC_R11G11_SNORM_BLOCK,
+        1,
+        false,
+        &[&block],
+    )
+    .unwrap()
+    .decompressed()
+    .unwrap();
+    assert_eq!(texture.format, vk::Format::R8G8B8A8_SNORM);
+    assert!(texture
+        .pixels
+        .chunks(4)
+        .all(|texel| texel == [13, 0x81, 0, 127]));
+
+    let pixels = decompress_block(vk::Format::BC4_SNORM_BLOCK, &[0x80, 0x7f, 0, 0, 0, 0, 0, 0]);
+    assert!(pixels.chunks(4).all(|texel| texel == [0x81, 0, 0, 127]));
+}
+
+/// Reads the half float channels of decompressed texels.
+fn halves(pixels: &[u8]) -> Vec<u16> {
+    pixels
+        .chunks(2)
+        .map(|half| u16::from_le_bytes([half[0], half[1]]))
+        .collect()
+}
+
+#[test]
+fn bc6h_blocks_are_decompressed() {
+    // Mode 11 stores 10-bit endpoints directly. Texel i uses index i, so the first and last
+    // texels hit the endpoints, and the weight of 34 lands between their half float bits.
+    let mut fields = vec![(0b00011, 5), (0, 30), (0x3fff_ffff, 30), (0, 3)];
+    fields.extend((1..16).map(|index| (index, 4)));
+    let texture = TextureData::from_levels(
+        4,
+        4,
+        vk::Format::BC6H_UFLOAT_BLOCK,
+        1,
+        false,
+        &[&pack_bits(&fields)],
+    )
+    .unwrap()
+    .decompressed()
+    .unwrap();
+    assert_eq!(texture.format, vk::Format::R16G16B16A16_SFLOAT);
+    let channels = halves(&texture.pixels);
+    assert_eq!(channels[..4], [0, 0, 0, 0x3c00]);
+    assert_eq!(channels[32..36], [0x41df, 0x41df, 0x41df, 0x3c00]);
+    assert_eq!(channels[60..], [0x7bff, 0x7bff, 0x7bff, 0x3c00]);
+
+    // Signed endpoints at the ends of the 10-bit range reach the largest finite halves.
+    let mut fields = vec![(0b00011, 5), (0x2008_0200, 30), (0x1ff7_fdff, 30), (0, 3)];
+    fields.extend((1..16).map(|_| (15, 4)));
+    let pixels = decompress_block(vk::Format::BC6H_SFLOAT_BLOCK, &pack_bits(&fields));
+    let channels = halves(&pixels);
+    assert_eq!(channels[..4], [0xfbff, 0xfbff, 0xfbff, 0x3c00]);
+    assert_eq!(channels[4..8], [0x7bff, 0x7bff, 0x7bff, 0x3c00]);
+
+    // Mode 1 partition 13 splits the block into halves, with the bottom subset's endpoints one
+    // below the top's through deltas of -1.
+    let fields = [
+        (0b00, 2),
+        (0b111, 3),
+        (0x3fff_ffff, 30),
+        (0, 5),
+        (1, 1),
+        (0xf, 4),
+        (0, 5),
+        (0x1f, 5),
+        (0, 5),
+        (0x1_ffff, 17),
+        (13, 5),
+        (0, 46),
+    ];
+    let pixels = decompress_block(vk::Format::BC6H_UFLOAT_BLOCK, &pack_bits(&fields));
+    let channels = halves(&pixels);
+    assert_eq!(
+        channels[..32],
+        [0x7bff, 0x7bff, 0x7bff, 0x3c00].repeat(8)[..]
+    );
+    assert_eq!(
+        channels[32..],
+        [0x7bd1, 0x7bd1, 0x7bd1, 0x3c00].repeat(8)[..]
+    );
+
+    // Reserved modes decode to black.
+    let pixels = decompress_block(
+        vk::Format::BC6H_UFLOAT_BLOCK,
+        &pack_bits(&[(0b10011, 5), (0, 123)]),
+    );
+    assert!(halves(&pixels)
+        .chunks(4)
+        .all(|texel| texel == [0, 0, 0, 0x3c00]));
+}
+
+/// Packs a void extent ASTC block, which fills its footprint with one 16-bit color.
+fn astc_void_extent(color: [u16; 4]) -> [u8; 16] {
+    let mut fields = vec![(0xdfc, 12), (u32::MAX, 32), (0xfffff, 20)];
+    fields.extend(color.iter().map(|&channel| (channel as u32, 16)));
+    pack_bits(&fields)
+}
+
+#[test]
+fn astc_blocks_are_decompressed() {
+    // Two 6x5 void extent blocks cover an 8x5 image, the second one cut off after two columns.
+    let blocks = [
+        astc_void_extent([0x1234, 0xff00, 0x0080, 0xffff]),
+        astc_void_extent([0, 0x8000, 0xffff, 0x4000]),
+    ]
+    .concat();
+    let texture =
+        TextureData::from_levels(8, 5, vk::Format::ASTC_6X5_UNORM_BLOCK, 1, false, &[&blocks])
+            .unwrap()
+            .decompressed()
+            .unwrap();
+    assert_eq!(texture.format, vk::Format::R8G8B8A8_UNORM);
+    for row in texture.pixels.chunks(8 * 4) {
+        assert_eq!(row[..24], [0x12, 0xff, 0x00, 0xff].repeat(6)[..]);
+        assert_eq!(row[24..], [0x00, 0x80, 0xff, 0x40].repeat(2)[..]);
+    }
+
+    // Block mode 0x42 has a 4x4 grid of 2-bit weights, leaving room for the 8-bit values of a
+    // single RGBA endpoint pair. Texel i takes weight i % 4, which are stored backwards from the
+    // end of the block.
+    let endpoints = [0, 255, 255, 0, 40, 200, 255, 128];
+    let weights = (0..16).fold(0u32, |weights, texel| {
+        weights | ((texel % 4) << (texel * 2))
+    });
+    let mut fields = vec![(0x42, 11), (0, 2), (12, 4)];
+    fields.extend(endpoints.iter().map(|&value| (value, 8)));
+    fields.push((0, 15));
+    fields.push((weights.reverse_bits(), 32));
+    let pixels = decompress_block(vk::Format::ASTC_4X4_UNORM_BLOCK, &pack_bits(&fields));
+    let palette = [
+        [0, 255, 40, 255],
+        [84, 171, 92, 214],
+        [171, 84, 148, 170],
+        [255, 0, 200, 128],
+    ];
+    for (texel, color) in pixels.chunks(4).enumerate() {
+        assert_eq!(color, palette[texel % 4]);
+    }
+
+    // Reserved block modes decode to magenta.
+    let pixels = decompress_block(vk::Format::ASTC_4X4_SRGB_BLOCK, &[0; 16]);
+    assert_eq!(pixels, [255, 0, 255, 255].repeat(16));
+}
+
+#[test]
+fn uncompressed_formats_cannot_be_decompressed() {
+    let format = vk::Format::R8G8B8A8_UNORM;
+    let texture = TextureData::from_levels(4, 4, format, 1, false, &[&[0; 64]]).unwrap();
+    assert!(matches!(
+        texture.decompressed(),
+        Err(RendererError::DecompressionUnsupported(unsupported)) if unsupported == format
+    ));
+}
+
+#[test]
+fn cube_textures_cannot_replace_the_2d_texture() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    let texture = TextureData::from_memory(&encode_bc1_cube(4, 4, 1)).unwrap();
+    assert!(matches!(
+        renderer.set_texture(&texture, &SamplerConfig::default()),
+        Err(RendererError::ViewTypeMismatch { .. })
+    ));
+}
@@ -0,0 +1,13 @@
+mod common;
+
+#[test]
+fn scene_becomes_ready_after_upload() {
+    let mut renderer = match common::headless_renderer() {
+        Some(renderer) => renderer,
+        None => return,
+    };
+    // Capturing waits for the graphics queue, so the upload batch has finished by the next frame.
+    common::render_frames(&mut renderer, 1);
+    renderer.render().unwrap();
+    assert!(renderer.is_ready());
+}
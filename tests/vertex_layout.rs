@@ -0,0 +1,58 @@
+use ash::vk;
+use wild::renderer::{Unorm, VertexFormat, VertexLayout};
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct MeshVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    uv: [f32; 2],
+    color: Unorm<[u8; 4]>,
+}
+
+wild::vertex_layout!(MeshVertex {
+    position,
+    normal,
+    uv,
+    color
+});
+
+#[test]
+fn binding_uses_struct_stride() {
+    let binding = MeshVertex::binding_description(1);
+    assert_eq!(binding.binding, 1);
+    assert_eq!(binding.stride, 36);
+    assert_eq!(binding.input_rate, vk::VertexInputRate::VERTEX);
+}
+
+#[test]
+fn attributes_follow_field_order() {
+    let attributes = MeshVertex::attribute_descriptions(1)
+        .iter()
+        .map(|attribute| {
+            (
+                attribute.binding,
+                attribute.location,
+                attribute.format,
+                attribute.offset,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        attributes,
+        [
+            (1, 0, vk::Format::R32G32B32_SFLOAT, 0),
+            (1, 1, vk::Format::R32G32B32_SFLOAT, 12),
+            (1, 2, vk::Format::R32G32_SFLOAT, 24),
+            (1, 3, vk::Format::R8G8B8A8_UNORM, 32),
+        ]
+    );
+}
+
+#[test]
+fn integer_fields_are_normalized_only_when_wrapped() {
+    assert_eq!(<[u8; 4]>::FORMAT, vk::Format::R8G8B8A8_UINT);
+    assert_eq!(<[u16; 4]>::FORMAT, vk::Format::R16G16B16A16_UINT);
+    assert_eq!(Unorm::<[u8; 4]>::FORMAT, vk::Format::R8G8B8A8_UNORM);
+    assert_eq!(Unorm::<[u16; 4]>::FORMAT, vk::Format::R16G16B16A16_UNORM);
+}